@@ -12,6 +12,7 @@ import {
 import { ExportedData, ImportOptions, ConflictResolution, DataConflict } from '@/types';
 import Icon from '@/components/ui/Icon.tsx';
 import Button from '@/components/ui/Button.tsx';
+import ConfirmDeleteModal from '@/components/ui/ConfirmDeleteModal.tsx';
 import { twMerge } from 'tailwind-merge';
 import * as Dialog from '@radix-ui/react-dialog';
 import * as Select from '@radix-ui/react-select';
@@ -207,7 +208,11 @@ const DataSettings: React.FC = () => {
     const [isImporting, setIsImporting] = useState(false);
     const [conflicts, setConflicts] = useState<DataConflict[]>([]);
     const [pendingImport, setPendingImport] = useState<{ data: ExportedData; options: ImportOptions } | null>(null);
+    const [isResetConfirmOpen, setIsResetConfirmOpen] = useState(false);
+    const [isResetting, setIsResetting] = useState(false);
+    const [keepSettingsOnReset, setKeepSettingsOnReset] = useState(true);
     const fileInputRef = useRef<HTMLInputElement>(null);
+    const canFactoryReset = typeof storageManager.get().factoryReset === 'function';
 
     const handleExportData = useCallback(async () => {
         try {
@@ -331,6 +336,38 @@ const DataSettings: React.FC = () => {
         }
     }, [addNotification, t, setTasks, setLists, setSummaries, setAppearanceSettings, setPreferencesSettings, setAISettings]);
 
+    const handleFactoryReset = useCallback(async () => {
+        setIsResetConfirmOpen(false);
+        const storage = storageManager.get();
+        if (!storage.factoryReset) return;
+        try {
+            setIsResetting(true);
+            await storage.factoryReset(keepSettingsOnReset);
+
+            setTasks(storage.fetchTasks());
+            setLists(storage.fetchLists());
+            setSummaries(storage.fetchSummaries());
+
+            const settings = storage.fetchSettings();
+            setAppearanceSettings(settings.appearance);
+            setPreferencesSettings(settings.preferences);
+            setAISettings(settings.ai);
+
+            addNotification({
+                type: 'success',
+                message: t('settings.data.reset.success')
+            });
+        } catch (error) {
+            console.error('Factory reset failed:', error);
+            addNotification({
+                type: 'error',
+                message: t('settings.data.reset.error')
+            });
+        } finally {
+            setIsResetting(false);
+        }
+    }, [addNotification, t, keepSettingsOnReset, setTasks, setLists, setSummaries, setAppearanceSettings, setPreferencesSettings, setAISettings]);
+
     const handleConflictsResolved = useCallback((resolutions: Map<string, ConflictResolution>) => {
         if (pendingImport) {
             performImport(pendingImport.data, pendingImport.options, resolutions);
@@ -399,6 +436,64 @@ const DataSettings: React.FC = () => {
                 />
             </div>
 
+            {canFactoryReset && (
+                <div className="space-y-0 divide-y divide-grey-light dark:divide-neutral-700">
+                    <h3 className="text-[12px] font-medium text-red-600 dark:text-red-400 pt-2">
+                        {t('settings.data.reset.dangerZone')}
+                    </h3>
+                    <SettingsRow
+                        label={t('settings.data.reset.keepSettings')}
+                        description={t('settings.data.reset.keepSettingsDescription')}
+                        htmlFor="keepSettingsOnResetToggle"
+                    >
+                        <RadixSwitch.Root
+                            id="keepSettingsOnResetToggle"
+                            checked={keepSettingsOnReset}
+                            onCheckedChange={setKeepSettingsOnReset}
+                            aria-label="Toggle keep settings on reset"
+                            className={twMerge(
+                                "custom-switch-track",
+                                keepSettingsOnReset ? "custom-switch-track-on" : "custom-switch-track-off"
+                            )}
+                        >
+                            <RadixSwitch.Thumb
+                                className={twMerge("custom-switch-thumb", keepSettingsOnReset ? "custom-switch-thumb-on" : "custom-switch-thumb-off")}/>
+                        </RadixSwitch.Root>
+                    </SettingsRow>
+                    <SettingsRow
+                        label={t('settings.data.reset.title')}
+                        description={t('settings.data.reset.description')}
+                        action={
+                            <Button
+                                onClick={() => setIsResetConfirmOpen(true)}
+                                disabled={isResetting}
+                                icon={isResetting ? 'loader' : 'trash'}
+                                variant="danger"
+                                size="sm"
+                                iconProps={{
+                                    className: isResetting ? 'animate-spin' : undefined,
+                                    size: 14
+                                }}
+                            >
+                                {t('settings.data.reset.button')}
+                            </Button>
+                        }
+                    />
+                </div>
+            )}
+
+            <ConfirmDeleteModal
+                isOpen={isResetConfirmOpen}
+                onClose={() => setIsResetConfirmOpen(false)}
+                onConfirm={handleFactoryReset}
+                itemTitle=""
+                title={t('settings.data.reset.confirmTitle')}
+                description={t(keepSettingsOnReset
+                    ? 'settings.data.reset.confirmDescriptionKeepSettings'
+                    : 'settings.data.reset.confirmDescription')}
+                confirmText={t('settings.data.reset.confirmButton')}
+            />
+
             <input
                 ref={fileInputRef}
                 type="file"
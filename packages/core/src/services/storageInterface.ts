@@ -73,6 +73,9 @@ export interface IStorageService {
     batchUpdateTasks?(tasks: Task[]): Promise<void>;
     batchUpdateLists?(lists: List[]): Promise<void>;
 
+    // Optional: delete all lists, tasks and reports, keeping the settings if asked to
+    factoryReset?(keepSettings: boolean): Promise<void>;
+
     // Optional persistence control to ensure data is saved
     flush?(): Promise<void>;
     enableAutoPersist?(enabled: boolean): void;
@@ -132,8 +132,29 @@ export interface PreferencesSettings {
     echoJobTypes: string[]; // Selected job types for Echo
     echoPastExamples?: string; // User provided past report examples
     alwaysUseAITask: boolean; // Toggle for always using AI task input
+    recurrenceMode?: 'spawnOnComplete' | 'preMaterialize'; // When the next occurrence of a recurring task is created
+    workingHours?: WorkingHours | null; // Reminders outside these hours wait for the next window
+    taskVersionLimit?: number; // Earlier title, content and due date versions kept per task
+    dailyCapacityMinutes?: number; // Estimated minutes a day above which a list counts as overcommitted
+    defaultEstimateMinutes?: number; // What a task without an estimate counts as in the workload
+    localApi?: { enabled: boolean; port: number }; // Read-only HTTP server on 127.0.0.1, off by default
+    autoCompleteParent?: boolean; // Complete a task when ticking its subtasks finishes them all
+    weeklyScore?: { completed: number; onTime: number; carryover: number; completedTarget: number }; // Weights of the weekly productivity score
 }
 
+/**
+ * A working window in local "HH:MM" times, the end is exclusive.
+ */
+export interface WorkingWindow {
+    start: string;
+    end: string;
+}
+
+/**
+ * Working hours per weekday, days left out have none.
+ */
+export type WorkingHours = Partial<Record<'monday' | 'tuesday' | 'wednesday' | 'thursday' | 'friday' | 'saturday' | 'sunday', WorkingWindow | null>>;
+
 /**
  * Defines the structure for AI-related settings.
  */
@@ -0,0 +1,505 @@
+use crate::reminders::ReminderScheduler;
+use crate::tags::{self, TagCount};
+use crate::tasks::{self, NewTask, Task, TASK_COLUMNS};
+use crate::{dates, db, journal, list_defaults, secrets, settings, tray};
+use rusqlite::{params, params_from_iter, Connection};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_http::reqwest;
+use uuid::Uuid;
+
+// Chat endpoints of the providers that speak the OpenAI protocol. Providers with their
+// own protocol (Claude, Cohere, Qwen, ...) stay with the frontend implementation.
+const OPENAI_COMPATIBLE: &[(&str, &str)] = &[
+    ("openai", "https://api.openai.com/v1/chat/completions"),
+    ("gemini", "https://generativelanguage.googleapis.com/v1beta/openai/chat/completions"),
+    ("xai", "https://api.x.ai/v1/chat/completions"),
+    ("groq", "https://api.groq.com/openai/v1/chat/completions"),
+    ("openrouter", "https://openrouter.ai/api/v1/chat/completions"),
+    ("siliconflow", "https://api.siliconflow.cn/v1/chat/completions"),
+    ("302", "https://api.302.ai/v1/chat/completions"),
+    ("moonshot", "https://api.moonshot.cn/v1/chat/completions"),
+    ("deepseek", "https://api.deepseek.com/v1/chat/completions"),
+    ("zhipu", "https://open.bigmodel.cn/api/paas/v4/chat/completions"),
+    ("bytedance", "https://ark.cn-beijing.volces.com/api/v3/chat/completions"),
+];
+// Self-hosted providers: `baseUrl` plus this path
+const SELF_HOSTED: &[&str] = &["custom", "ollama"];
+const SELF_HOSTED_PATH: &str = "/v1/chat/completions";
+
+pub const SUMMARY_CHUNK_EVENT: &str = "summary-chunk";
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You write concise work summaries in Markdown from a list of tasks. \
+Group related work, call out what was finished and what is still open, and keep it short.";
+
+const EXTRACT_SYSTEM_PROMPT: &str = "You turn free-form notes into a to-do list. Answer with a JSON array only, \
+no prose and no code fence. Every element is an object with a \"title\" (a short imperative sentence), \
+optionally a \"due_date\" (YYYY-MM-DD, or YYYY-MM-DDTHH:MM when a time is given) and optionally a \
+\"priority\" (1 high, 2 medium, 3 low). Only include actual action items. Answer [] if there are none.";
+// Longer titles are cut, the notes may hold whole paragraphs
+const MAX_EXTRACTED_TITLE_CHARS: usize = 200;
+
+const SUGGEST_TAGS_SYSTEM_PROMPT: &str = "You suggest tags for a to-do item. Answer with a JSON array of 3 to 5 \
+short tags only, no prose, no code fence and no leading #. Prefer tags from the existing vocabulary, only \
+invent a new tag when none of them fits.";
+const MAX_SUGGESTED_TAGS: usize = 5;
+// The most used tags sent along as the vocabulary, the rest would only add noise
+const MAX_VOCABULARY: usize = 200;
+
+// Errors are tagged so the frontend can tell configuration problems (ask the user to
+// open the AI settings) apart from failures worth retrying.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum AiError {
+    MissingApiKey,
+    MissingModel,
+    UnsupportedProvider(String),
+    // The request never got an answer
+    Network(String),
+    // The provider answered with an error status or an unreadable body
+    Provider(String),
+    Storage(String),
+}
+
+impl From<String> for AiError {
+    fn from(message: String) -> Self {
+        AiError::Storage(message)
+    }
+}
+
+impl From<rusqlite::Error> for AiError {
+    fn from(e: rusqlite::Error) -> Self {
+        AiError::Storage(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AiConfig {
+    pub endpoint: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+// Resolve the `ai` settings (the key lives in the secret store) to a chat endpoint
+pub fn load_config(conn: &Connection) -> Result<AiConfig, AiError> {
+    let ai = settings::read(conn, "ai")?.unwrap_or(Value::Null);
+    let field = |name: &str| ai.get(name).and_then(Value::as_str).unwrap_or("").trim().to_string();
+    let provider = field("provider");
+    let model = field("model");
+    let base_url = field("baseUrl");
+    let api_key = secrets::load(conn, secrets::AI_API_KEY)
+        .filter(|key| !key.is_empty())
+        .unwrap_or_else(|| field("apiKey"));
+
+    let endpoint = if SELF_HOSTED.contains(&provider.as_str()) {
+        if base_url.is_empty() {
+            return Err(AiError::UnsupportedProvider(format!("{} needs a base URL", provider)));
+        }
+        format!("{}{}", base_url.trim_end_matches('/'), SELF_HOSTED_PATH)
+    } else {
+        OPENAI_COMPATIBLE
+            .iter()
+            .find(|(id, _)| *id == provider)
+            .map(|(_, endpoint)| endpoint.to_string())
+            .ok_or_else(|| AiError::UnsupportedProvider(provider.clone()))?
+    };
+
+    // Ollama runs locally without a key, every other provider needs one
+    if api_key.is_empty() && provider != "ollama" {
+        return Err(AiError::MissingApiKey);
+    }
+    if model.is_empty() {
+        return Err(AiError::MissingModel);
+    }
+    Ok(AiConfig { endpoint, api_key, model })
+}
+
+fn request(config: &AiConfig, system_prompt: &str, user_prompt: &str, stream: bool) -> reqwest::RequestBuilder {
+    let mut builder = reqwest::Client::new().post(&config.endpoint).json(&json!({
+        "model": config.model,
+        "messages": [
+            { "role": "system", "content": system_prompt },
+            { "role": "user", "content": user_prompt },
+        ],
+        "temperature": 0.5,
+        "stream": stream,
+    }));
+    if !config.api_key.is_empty() {
+        builder = builder.bearer_auth(&config.api_key);
+    }
+    builder
+}
+
+async fn send(builder: reqwest::RequestBuilder) -> Result<reqwest::Response, AiError> {
+    let response = builder.send().await.map_err(|e| AiError::Network(e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AiError::Provider(format!("{}: {}", status, body)));
+    }
+    Ok(response)
+}
+
+pub async fn complete(config: &AiConfig, system_prompt: &str, user_prompt: &str) -> Result<String, AiError> {
+    let response = send(request(config, system_prompt, user_prompt, false)).await?;
+    let body: Value = response.json().await.map_err(|e| AiError::Provider(e.to_string()))?;
+    body.pointer("/choices/0/message/content")
+        .and_then(Value::as_str)
+        .map(String::from)
+        .ok_or_else(|| AiError::Provider("The response contains no message".to_string()))
+}
+
+// Read a server-sent event stream and hand every content delta to `on_delta`, returns the
+// whole text
+pub async fn complete_streaming(
+    config: &AiConfig,
+    system_prompt: &str,
+    user_prompt: &str,
+    mut on_delta: impl FnMut(&str),
+) -> Result<String, AiError> {
+    let mut response = send(request(config, system_prompt, user_prompt, true)).await?;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut text = String::new();
+
+    while let Some(chunk) = response.chunk().await.map_err(|e| AiError::Network(e.to_string()))? {
+        buffer.extend_from_slice(&chunk);
+        // Events may be split across chunks, only complete lines are parsed
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                return Ok(text);
+            }
+            let delta = serde_json::from_str::<Value>(data)
+                .ok()
+                .and_then(|event| event.pointer("/choices/0/delta/content").and_then(Value::as_str).map(String::from));
+            if let Some(delta) = delta.filter(|d| !d.is_empty()) {
+                on_delta(&delta);
+                text.push_str(&delta);
+            }
+        }
+    }
+    Ok(text)
+}
+
+// Inline images would only burn tokens, keep their alt text
+fn strip_data_images(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("![") {
+        let after = &rest[start..];
+        let image = after.find("](data:").and_then(|alt_end| {
+            after[alt_end..].find(')').map(|close| (&after[2..alt_end], alt_end + close + 1))
+        });
+        match image {
+            Some((alt, len)) => {
+                out.push_str(&rest[..start]);
+                if alt.is_empty() {
+                    out.push_str("[Image]");
+                } else {
+                    out.push_str(&format!("[Image: {}]", alt));
+                }
+                rest = &rest[start + len..];
+            }
+            None => {
+                out.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn load_tasks(conn: &Connection, ids: &[String]) -> rusqlite::Result<Vec<Task>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT {} FROM tasks WHERE deleted_at IS NULL AND id IN ({}) ORDER BY "order""#,
+        TASK_COLUMNS, placeholders
+    ))?;
+    let rows = stmt.query_map(params_from_iter(ids), Task::from_row)?;
+    rows.collect()
+}
+
+// Same task description the frontend sends for summaries
+fn summary_prompt(tasks: &[Task]) -> String {
+    let lines: Vec<String> = tasks
+        .iter()
+        .map(|t| {
+            let progress = t.complete_percentage.filter(|p| *p > 0).map(|p| format!(", {}% done", p)).unwrap_or_default();
+            let notes = t.content.as_deref().filter(|c| !c.is_empty()).map(strip_data_images).unwrap_or_else(|| "N/A".to_string());
+            format!(
+                "- Task: \"{}\" (Status: {}{})\n  Notes: {}",
+                t.title,
+                if t.completed { "Completed" } else { "Incomplete" },
+                progress,
+                notes
+            )
+        })
+        .collect();
+    format!("## Tasks from the summary period:\n{}", lines.join("\n"))
+}
+
+fn save_summary(conn: &Connection, period_key: &str, list_key: &str, task_ids: &[String], text: &str) -> rusqlite::Result<()> {
+    let now = dates::now_ms();
+    conn.execute(
+        r#"
+        INSERT INTO summaries (id, created_at, updated_at, period_key, list_key, task_ids, summary_text)
+        VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        params![
+            Uuid::new_v4().to_string(),
+            now,
+            period_key,
+            list_key,
+            serde_json::to_string(task_ids).unwrap_or_default(),
+            text
+        ],
+    )?;
+    Ok(())
+}
+
+fn prepare_summary(app: &AppHandle, task_ids: &[String]) -> Result<(AiConfig, String), AiError> {
+    let conn = db::connect(app)?;
+    let config = load_config(&conn)?;
+    let tasks = load_tasks(&conn, task_ids)?;
+    if tasks.is_empty() {
+        return Err(AiError::Storage("None of the given tasks exist".to_string()));
+    }
+    Ok((config, summary_prompt(&tasks)))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryChunk<'a> {
+    period_key: &'a str,
+    list_key: &'a str,
+    delta: &'a str,
+}
+
+#[tauri::command]
+pub async fn generate_summary(
+    app: AppHandle,
+    period_key: String,
+    list_key: String,
+    task_ids: Vec<String>,
+) -> Result<String, AiError> {
+    let (config, prompt) = prepare_summary(&app, &task_ids)?;
+    let text = complete(&config, SUMMARY_SYSTEM_PROMPT, &prompt).await?;
+    save_summary(&*db::connect(&app)?, &period_key, &list_key, &task_ids, &text)?;
+    Ok(text)
+}
+
+// Like `generate_summary`, emitting a `summary-chunk` event for every delta as it arrives
+#[tauri::command]
+pub async fn generate_summary_stream(
+    app: AppHandle,
+    period_key: String,
+    list_key: String,
+    task_ids: Vec<String>,
+) -> Result<String, AiError> {
+    let (config, prompt) = prepare_summary(&app, &task_ids)?;
+    let text = complete_streaming(&config, SUMMARY_SYSTEM_PROMPT, &prompt, |delta| {
+        let chunk = SummaryChunk {
+            period_key: &period_key,
+            list_key: &list_key,
+            delta,
+        };
+        if let Err(e) = app.emit(SUMMARY_CHUNK_EVENT, chunk) {
+            eprintln!("Failed to emit a summary chunk: {}", e);
+        }
+    })
+    .await?;
+    save_summary(&*db::connect(&app)?, &period_key, &list_key, &task_ids, &text)?;
+    Ok(text)
+}
+
+fn extract_prompt(text: &str, now_ms: i64) -> String {
+    let today = dates::local_date(now_ms)
+        .map(|date| date.format("%Y-%m-%d (%A)").to_string())
+        .unwrap_or_default();
+    format!("Today is {}. Resolve relative dates against it.\n\nNotes:\n{}", today, text.trim())
+}
+
+// The JSON array in a reply, models sometimes wrap it in a code fence or a sentence
+fn json_array(reply: &str) -> Option<Vec<Value>> {
+    let start = reply.find('[')?;
+    let end = reply.rfind(']')?;
+    if end < start {
+        return None;
+    }
+    serde_json::from_str(&reply[start..=end]).ok()
+}
+
+// One array element as a task. Entries without a usable title are dropped, a due date or
+// priority that can't be read only drops that field.
+fn extracted_task(entry: &Value) -> Option<NewTask> {
+    let title = entry.get("title")?.as_str()?.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    let due_date = match entry.get("due_date").or_else(|| entry.get("dueDate")) {
+        Some(Value::String(text)) => dates::parse_due_date(text),
+        Some(Value::Number(ms)) => ms.as_i64(),
+        _ => None,
+    };
+    let priority = match entry.get("priority") {
+        Some(Value::Number(n)) => n.as_i64().filter(|p| (1..=3).contains(p)),
+        Some(Value::String(text)) => tasks::parse_priority(text),
+        _ => None,
+    };
+    Some(NewTask {
+        title: title.chars().take(MAX_EXTRACTED_TITLE_CHARS).collect(),
+        due_date,
+        priority,
+        ..Default::default()
+    })
+}
+
+fn insert_extracted(conn: &mut Connection, list_id: &str, entries: &[Value]) -> Result<Vec<Task>, AiError> {
+    let tx = conn.transaction()?;
+    journal::begin(&tx, "extract_tasks")?;
+    let list_name = tasks::list_name(&tx, list_id)?
+        .ok_or_else(|| AiError::Storage(format!("List {} not found", list_id)))?;
+    let defaults = list_defaults::load(&tx, list_id)?.unwrap_or_default();
+    let mut created = Vec::new();
+    for entry in entries {
+        let Some(mut task) = extracted_task(entry) else {
+            continue;
+        };
+        defaults.apply(&mut task);
+        let id = tasks::insert(&tx, list_id, &list_name, &task)?;
+        if let Some(task) = tasks::get(&tx, &id)? {
+            created.push(task);
+        }
+    }
+    journal::end(&tx)?;
+    tx.commit()?;
+    Ok(created)
+}
+
+// Ask the model for the action items in `text` and append them to the list. A reply that
+// holds no JSON array at all is an error, malformed entries inside it are skipped.
+#[tauri::command]
+pub async fn extract_tasks_from_text(app: AppHandle, text: String, list_id: String) -> Result<Vec<Task>, AiError> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let config = {
+        let conn = db::connect(&app)?;
+        if tasks::list_name(&conn, &list_id)?.is_none() {
+            return Err(AiError::Storage(format!("List {} not found", list_id)));
+        }
+        load_config(&conn)?
+    };
+    let reply = complete(&config, EXTRACT_SYSTEM_PROMPT, &extract_prompt(&text, dates::now_ms())).await?;
+    let entries = json_array(&reply)
+        .ok_or_else(|| AiError::Provider("The response contains no JSON array of tasks".to_string()))?;
+    let created = insert_extracted(&mut *db::connect(&app)?, &list_id, &entries)?;
+    if !created.is_empty() {
+        app.state::<ReminderScheduler>().wake();
+        if let Err(e) = tray::refresh(&app) {
+            eprintln!("Failed to refresh tray: {}", e);
+        }
+    }
+    Ok(created)
+}
+
+fn suggest_prompt(task: &Task, vocabulary: &[TagCount]) -> String {
+    let vocabulary: Vec<&str> = vocabulary.iter().take(MAX_VOCABULARY).map(|t| t.tag.as_str()).collect();
+    let notes = task.content.as_deref().filter(|c| !c.trim().is_empty()).map(strip_data_images);
+    format!(
+        "Existing tags: {}\nTags the task already has: {}\n\nTask: {}\nNotes: {}",
+        if vocabulary.is_empty() { "none".to_string() } else { vocabulary.join(", ") },
+        if task.tags.is_empty() { "none".to_string() } else { task.tags.join(", ") },
+        task.title,
+        notes.unwrap_or_else(|| "N/A".to_string())
+    )
+}
+
+// Existing tags spelled the way the vocabulary has them, the task's own tags and repeats
+// left out
+fn clean_suggestions(suggested: Vec<String>, task: &Task, vocabulary: &[TagCount]) -> Vec<String> {
+    let suggested = suggested
+        .into_iter()
+        .map(|tag| tag.trim().trim_start_matches('#').trim().to_string())
+        .map(|tag| {
+            vocabulary
+                .iter()
+                .find(|known| known.tag.to_lowercase() == tag.to_lowercase())
+                .map(|known| known.tag.clone())
+                .unwrap_or(tag)
+        })
+        .filter(|tag| !task.tags.iter().any(|own| own.to_lowercase() == tag.to_lowercase()))
+        .collect();
+    tasks::normalize_tags(suggested).into_iter().take(MAX_SUGGESTED_TAGS).collect()
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Offline stand-in for the model: existing tags whose words occur in the title or notes
+// (or that occur there whole, which catches scripts without spaces), most matched words
+// first, then the most used. May find fewer than three, or none.
+pub fn keyword_tags(task: &Task, vocabulary: &[TagCount]) -> Vec<String> {
+    let text = format!("{} {}", task.title, task.content.as_deref().unwrap_or("")).to_lowercase();
+    let text_words = words(&text);
+    let mut scored: Vec<(usize, usize, &str)> = vocabulary
+        .iter()
+        .filter_map(|known| {
+            let tag = known.tag.to_lowercase();
+            let matched = words(&tag).intersection(&text_words).count();
+            let score = if matched == 0 && text.contains(&tag) { 1 } else { matched };
+            (score > 0).then_some((score, known.count, known.tag.as_str()))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)).then(a.2.cmp(b.2)));
+    let suggested = scored.into_iter().map(|(_, _, tag)| tag.to_string()).collect();
+    clean_suggestions(suggested, task, vocabulary)
+}
+
+// Tags for a task, not applied. The model picks them with the existing tags as the
+// vocabulary; without an AI configured, or when the call fails or the answer holds no
+// tags, `keyword_tags` does.
+#[tauri::command]
+pub async fn suggest_tags(app: AppHandle, task_id: String) -> Result<Vec<String>, AiError> {
+    let (task, vocabulary, config) = {
+        let conn = db::connect(&app)?;
+        let task = tasks::get(&conn, &task_id)?
+            .ok_or_else(|| AiError::Storage(format!("Task {} not found", task_id)))?;
+        (task, tags::count_tags(&conn)?, load_config(&conn))
+    };
+    let Ok(config) = config else {
+        return Ok(keyword_tags(&task, &vocabulary));
+    };
+    let prompt = suggest_prompt(&task, &vocabulary);
+    let suggested = match complete(&config, SUGGEST_TAGS_SYSTEM_PROMPT, &prompt).await {
+        Ok(reply) => json_array(&reply)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|tag| tag.as_str().map(String::from))
+            .collect(),
+        Err(e) => {
+            eprintln!("Tag suggestions fall back to keywords: {:?}", e);
+            Vec::new()
+        }
+    };
+    let suggested = clean_suggestions(suggested, &task, &vocabulary);
+    if suggested.is_empty() {
+        return Ok(keyword_tags(&task, &vocabulary));
+    }
+    Ok(suggested)
+}
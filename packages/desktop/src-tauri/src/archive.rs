@@ -0,0 +1,177 @@
+use crate::export::{self, Row, INBOX_LIST_ID};
+use crate::tasks::{self, Task, TASK_COLUMNS};
+use crate::{dates, db, journal};
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::AppHandle;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// Completed live tasks before the cutoff. Series parents stay, they hold the rule, and so
+// does the latest instance of each series: `recurrence::materialize` resumes after it for
+// series without `recurrence_materialized_until`.
+// Tasks with attachments stay too, deleting them would cascade to their attachment rows.
+const ARCHIVABLE: &str = r#"
+    SELECT id FROM tasks t
+    WHERE completed = 1 AND completed_at < ?1 AND deleted_at IS NULL AND list_name != 'Trash'
+      AND NOT EXISTS (SELECT 1 FROM attachments a WHERE a.task_id = t.id)
+      AND NOT (recurrence IS NOT NULL AND recurrence_parent_id IS NULL)
+      AND NOT (recurrence_parent_id IS NOT NULL AND due_date = (
+          SELECT MAX(due_date) FROM tasks i WHERE i.recurrence_parent_id = t.recurrence_parent_id))
+"#;
+
+// Columns of `tasks` that `archived_tasks` has as well, quoted for SQL
+fn shared_columns(conn: &Connection) -> rusqlite::Result<String> {
+    let archived = export::table_columns(conn, "archived_tasks")?;
+    Ok(export::table_columns(conn, "tasks")?
+        .into_iter()
+        .filter(|c| archived.contains(c))
+        .map(|c| format!("\"{}\"", c))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+// Move tasks completed more than `older_than_days` ago into `archived_tasks`, returns how
+// many moved. Their live subtasks, comments and earlier versions are stored with them,
+// subtasks they had in the trash are dropped. Archiving isn't journaled: undo only knows
+// about the live tables and would bring back a task that also sits in the archive.
+pub fn archive(conn: &mut Connection, older_than_days: i64) -> rusqlite::Result<usize> {
+    let now = dates::now_ms();
+    let cutoff = now - older_than_days.max(0) * DAY_MS;
+    let tx = conn.transaction()?;
+    journal::pause(&tx)?;
+    let ids: Vec<String> = {
+        let mut stmt = tx.prepare(ARCHIVABLE)?;
+        let rows = stmt.query_map([cutoff], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let columns = shared_columns(&tx)?;
+    for id in &ids {
+        let subtasks = export::dump_rows(
+            &tx,
+            r#"SELECT * FROM subtasks WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY "order""#,
+            [id],
+        )?;
+        let subtasks = serde_json::to_string(&subtasks).unwrap_or_else(|_| "[]".into());
+        let comments = export::dump_rows(&tx, "SELECT * FROM task_comments WHERE task_id = ?1 ORDER BY id", [id])?;
+        let comments = serde_json::to_string(&comments).unwrap_or_else(|_| "[]".into());
+        let versions = export::dump_rows(&tx, "SELECT * FROM task_versions WHERE task_id = ?1 ORDER BY id", [id])?;
+        let versions = serde_json::to_string(&versions).unwrap_or_else(|_| "[]".into());
+        tx.execute(
+            &format!(
+                "INSERT INTO archived_tasks ({0}, archived_at, subtasks, comments, versions)
+                 SELECT {0}, ?1, ?2, ?3, ?4 FROM tasks WHERE id = ?5",
+                columns
+            ),
+            params![now, subtasks, comments, versions, id],
+        )?;
+        tx.execute("DELETE FROM subtasks WHERE parent_id = ?1", [id])?;
+        tx.execute("DELETE FROM task_comments WHERE task_id = ?1", [id])?;
+        tx.execute("DELETE FROM task_versions WHERE task_id = ?1", [id])?;
+        tx.execute("DELETE FROM tasks WHERE id = ?1", [id])?;
+    }
+    journal::end(&tx)?;
+    tx.commit()?;
+    Ok(ids.len())
+}
+
+// List id and the serialized subtasks, comments and versions of an archived task
+type ArchivedRefs = (Option<String>, String, String, String);
+
+// Newest completions first
+pub fn archived(conn: &Connection, list_id: Option<&str>) -> rusqlite::Result<Vec<Task>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM archived_tasks WHERE ?1 IS NULL OR list_id = ?1 ORDER BY completed_at DESC",
+        TASK_COLUMNS
+    ))?;
+    let rows = stmt.query_map([list_id], Task::from_row)?;
+    rows.collect()
+}
+
+// Move an archived task back to the end of its list (the inbox if the list is gone),
+// subtasks, comments and earlier versions included
+pub fn unarchive(conn: &mut Connection, id: &str) -> Result<Task, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::pause(&tx).map_err(|e| e.to_string())?;
+    let archived: Option<ArchivedRefs> = tx
+        .query_row(
+            "SELECT list_id, subtasks, comments, versions FROM archived_tasks WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((list_id, subtasks, comments, versions)) = archived else {
+        return Err(format!("Task {} is not archived", id));
+    };
+
+    let list = match list_id {
+        Some(list_id) => tasks::list_name(&tx, &list_id)
+            .map_err(|e| e.to_string())?
+            .map(|name| (list_id, name)),
+        None => None,
+    };
+    let (list_id, list_name) = match list {
+        Some(list) => list,
+        None => (
+            INBOX_LIST_ID.to_string(),
+            export::ensure_inbox(&tx).map_err(|e| e.to_string())?,
+        ),
+    };
+
+    let columns = shared_columns(&tx).map_err(|e| e.to_string())?;
+    tx.execute(
+        &format!("INSERT INTO tasks ({0}) SELECT {0} FROM archived_tasks WHERE id = ?1", columns),
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    let order = tasks::next_order(&tx, Some(&list_id)).map_err(|e| e.to_string())?;
+    tx.execute(
+        r#"UPDATE tasks SET list_id = ?1, list_name = ?2, "order" = ?3, updated_at = ?4 WHERE id = ?5"#,
+        params![list_id, list_name, order, dates::now_ms(), id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let subtasks: Vec<Row> = serde_json::from_str(&subtasks).unwrap_or_default();
+    let subtask_columns = export::table_columns(&tx, "subtasks").map_err(|e| e.to_string())?;
+    for subtask in &subtasks {
+        export::insert_row(&tx, "subtasks", &subtask_columns, subtask).map_err(|e| e.to_string())?;
+    }
+    let comments: Vec<Row> = serde_json::from_str(&comments).unwrap_or_default();
+    let comment_columns = export::table_columns(&tx, "task_comments").map_err(|e| e.to_string())?;
+    for comment in &comments {
+        export::insert_row(&tx, "task_comments", &comment_columns, comment).map_err(|e| e.to_string())?;
+    }
+    let versions: Vec<Row> = serde_json::from_str(&versions).unwrap_or_default();
+    let version_columns = export::table_columns(&tx, "task_versions").map_err(|e| e.to_string())?;
+    for version in &versions {
+        export::insert_row(&tx, "task_versions", &version_columns, version).map_err(|e| e.to_string())?;
+    }
+    tx.execute("DELETE FROM archived_tasks WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    let task = tasks::get(&tx, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn archive_completed(app: AppHandle, older_than_days: i64) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    archive(&mut conn, older_than_days).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_archived(app: AppHandle, list_id: Option<String>) -> Result<Vec<Task>, String> {
+    let conn = db::connect(&app)?;
+    archived(&conn, list_id.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unarchive_task(app: AppHandle, id: String) -> Result<Task, String> {
+    let mut conn = db::connect(&app)?;
+    unarchive(&mut conn, &id)
+}
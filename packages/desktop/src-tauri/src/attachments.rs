@@ -0,0 +1,251 @@
+use crate::{dates, db, settings};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+// Copies live here, in the config directory. They stay there when the database is
+// relocated, only tada.db and its companions move (see relocate.rs).
+const ATTACHMENTS_DIR: &str = "attachments";
+pub const DEFAULT_MAX_SIZE_MB: u64 = 25;
+const MAX_FILENAME_CHARS: usize = 120;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    pub task_id: String,
+    pub filename: String,
+    // Relative to the attachments directory
+    pub stored_path: String,
+    pub mime: Option<String>,
+    pub size: i64,
+    pub created_at: i64,
+}
+
+impl Attachment {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            filename: row.get("filename")?,
+            stored_path: row.get("stored_path")?,
+            mime: row.get("mime")?,
+            size: row.get("size")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+// `preferences.attachmentMaxSizeMb`, falling back to the default
+pub fn max_size_bytes(conn: &Connection) -> u64 {
+    settings::read(conn, "preferences")
+        .ok()
+        .flatten()
+        .and_then(|p| p.get("attachmentMaxSizeMb").and_then(|m| m.as_u64()))
+        .filter(|mb| *mb > 0)
+        .unwrap_or(DEFAULT_MAX_SIZE_MB)
+        * 1024
+        * 1024
+}
+
+pub fn attachments_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(ATTACHMENTS_DIR))
+}
+
+// A plain file name: no directories, no characters Windows rejects, no leading dots
+pub fn sanitize_filename(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_FILENAME_CHARS)
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim();
+    if cleaned.is_empty() {
+        "attachment".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn mime_for(filename: &str) -> Option<&'static str> {
+    let extension = Path::new(filename).extension()?.to_str()?.to_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "heic" => "image/heic",
+        "pdf" => "application/pdf",
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        _ => return None,
+    })
+}
+
+// Resolve a stored path, refusing anything that would point outside `dir`
+fn resolve(dir: &Path, stored_path: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(stored_path);
+    let plain = relative.components().count() == 1
+        && relative.file_name().is_some_and(|name| name == relative.as_os_str());
+    if !plain {
+        return Err(format!("Refusing attachment path '{}'", stored_path));
+    }
+    Ok(dir.join(relative))
+}
+
+pub fn add(conn: &Connection, dir: &Path, task_id: &str, source_path: &Path) -> Result<Attachment, String> {
+    let live: Option<Option<i64>> = conn
+        .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", [task_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match live {
+        None => return Err(format!("Task {} not found", task_id)),
+        Some(Some(_)) => return Err(format!("Task {} is in the trash", task_id)),
+        Some(None) => {}
+    }
+
+    let metadata = fs::metadata(source_path)
+        .map_err(|e| format!("Failed to read '{}': {}", source_path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("'{}' is not a file", source_path.display()));
+    }
+    let limit = max_size_bytes(conn);
+    if metadata.len() > limit {
+        return Err(format!(
+            "'{}' is {:.1} MB, attachments can be at most {} MB",
+            source_path.display(),
+            metadata.len() as f64 / (1024.0 * 1024.0),
+            limit / (1024 * 1024)
+        ));
+    }
+
+    let filename = sanitize_filename(
+        &source_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    );
+    let id = Uuid::new_v4().to_string();
+    // The id prefix keeps two files of the same name apart
+    let stored_path = format!("{}-{}", id, filename);
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let target = resolve(dir, &stored_path)?;
+    let size = fs::copy(source_path, &target)
+        .map_err(|e| format!("Failed to copy '{}': {}", source_path.display(), e))?;
+
+    let attachment = Attachment {
+        id,
+        task_id: task_id.to_string(),
+        mime: mime_for(&filename).map(String::from),
+        filename,
+        stored_path,
+        size: size as i64,
+        created_at: dates::now_ms(),
+    };
+    let inserted = conn.execute(
+        "INSERT INTO attachments (id, task_id, filename, stored_path, mime, size, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            attachment.id,
+            attachment.task_id,
+            attachment.filename,
+            attachment.stored_path,
+            attachment.mime,
+            attachment.size,
+            attachment.created_at
+        ],
+    );
+    if let Err(e) = inserted {
+        let _ = fs::remove_file(&target);
+        return Err(e.to_string());
+    }
+    Ok(attachment)
+}
+
+// Stored paths of the attachments of the tasks `task_ids` selects, read before deleting the
+// tasks (the rows go with them) so `remove_files` can clean up after the commit
+pub fn stored_paths(conn: &Connection, task_ids: &str, params: impl rusqlite::Params) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT stored_path FROM attachments WHERE task_id IN ({})",
+        task_ids
+    ))?;
+    let rows = stmt.query_map(params, |row| row.get(0))?;
+    rows.collect()
+}
+
+fn remove_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path) && e.kind() != std::io::ErrorKind::NotFound {
+        eprintln!("Failed to delete attachment file '{}': {}", path.display(), e);
+    }
+}
+
+// The files of attachment rows that are already gone, a failure only leaves an orphan
+pub fn remove_files(dir: &Path, stored_paths: &[String]) {
+    for stored_path in stored_paths {
+        match resolve(dir, stored_path) {
+            Ok(path) => remove_file(&path),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+pub fn for_task(conn: &Connection, task_id: &str) -> rusqlite::Result<Vec<Attachment>> {
+    let mut stmt = conn.prepare("SELECT * FROM attachments WHERE task_id = ?1 ORDER BY created_at")?;
+    let rows = stmt.query_map([task_id], Attachment::from_row)?;
+    rows.collect()
+}
+
+// The row goes first, a file that can't be deleted only leaves an orphan behind
+pub fn remove(conn: &Connection, dir: &Path, id: &str) -> Result<(), String> {
+    let stored_path: Option<String> = conn
+        .query_row("SELECT stored_path FROM attachments WHERE id = ?1", [id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(stored_path) = stored_path else {
+        return Err(format!("Attachment {} not found", id));
+    };
+    let path = resolve(dir, &stored_path)?;
+    conn.execute("DELETE FROM attachments WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    remove_file(&path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_attachment(app: AppHandle, task_id: String, source_path: String) -> Result<Attachment, String> {
+    let conn = db::connect(&app)?;
+    add(&conn, &attachments_dir(&app)?, &task_id, Path::new(&source_path))
+}
+
+#[tauri::command]
+pub async fn list_attachments(app: AppHandle, task_id: String) -> Result<Vec<Attachment>, String> {
+    let conn = db::connect(&app)?;
+    for_task(&conn, &task_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_attachment(app: AppHandle, id: String) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    remove(&conn, &attachments_dir(&app)?, &id)
+}
@@ -0,0 +1,238 @@
+use crate::{dates, db, relocate, AppState};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Manager};
+
+// A validated backup waiting to replace the live database on the next launch
+const STAGED_RESTORE_FILE: &str = "tada.db.restore";
+// The database that was replaced by the last restore, kept around just in case
+const PRE_RESTORE_FILE: &str = "tada.db.pre-restore";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub path: String,
+    pub app_version: String,
+    pub created_at: i64,
+}
+
+// Resolve a path that may not exist yet by canonicalizing its parent directory
+fn normalize(path: &Path) -> Result<PathBuf, String> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("'{}' is not a file path", path.display()))?;
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Directory of '{}' is not accessible: {}", path.display(), e))?;
+    Ok(parent.join(file_name))
+}
+
+// Refuse to touch the live database or its WAL/SHM companions
+pub fn ensure_not_live_database(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let live = normalize(&db::db_path(app)?)?;
+    let target = normalize(path)?;
+    let live_name = live.to_string_lossy().to_string();
+    let target_name = target.to_string_lossy().to_string();
+    if target_name == live_name
+        || target_name == format!("{}-wal", live_name)
+        || target_name == format!("{}-shm", live_name)
+    {
+        return Err("Refusing to overwrite the live database".to_string());
+    }
+    Ok(())
+}
+
+// Write a consistent snapshot of the live database to `target`. `VACUUM INTO` reads inside a
+// single transaction, so concurrent writes from the sql plugin can't tear the copy. It
+// refuses existing files, so the snapshot is written next to the target and renamed over it.
+pub fn snapshot_to(app: &AppHandle, target: &Path) -> Result<(), String> {
+    ensure_not_live_database(app, target)?;
+
+    let staging = target.with_extension(format!("tmp-{}", dates::now_ms()));
+    let conn = db::connect(app)?;
+    conn.execute("VACUUM INTO ?1", [staging.to_string_lossy()])
+        .map_err(|e| e.to_string())?;
+
+    fs::rename(&staging, target).map_err(|e| {
+        let _ = fs::remove_file(&staging);
+        format!("Failed to write backup to '{}': {}", target.display(), e)
+    })
+}
+
+// Trim a fresh copy for `export_sqlite`, then rebuild it so nothing removed lingers in
+// free pages, and leave it in rollback journal mode so it's a single self-contained file
+fn sanitize_copy(path: &Path, include_archived: bool, redact_secrets: bool) -> rusqlite::Result<()> {
+    let conn = Connection::open(path)?;
+    if !include_archived {
+        conn.execute("DELETE FROM archived_tasks", [])?;
+    }
+    if redact_secrets {
+        conn.execute("DELETE FROM secrets", [])?;
+        for table in ["settings", "settings_history"] {
+            conn.execute(
+                &format!(
+                    "UPDATE {} SET value = json_set(value, '$.apiKey', '') WHERE key = 'ai' AND json_valid(value)",
+                    table
+                ),
+                [],
+            )?;
+        }
+    }
+    conn.execute_batch("VACUUM; PRAGMA journal_mode = DELETE;")
+}
+
+// A plain SQLite file to open in other tools, unlike `backup_database` it can leave out the
+// archive and the stored credentials (the secrets table and the AI API key in settings)
+pub fn export_copy(app: &AppHandle, target: &Path, include_archived: bool, redact_secrets: bool) -> Result<(), String> {
+    ensure_not_live_database(app, target)?;
+    let staging = target.with_extension(format!("export-{}", dates::now_ms()));
+    snapshot_to(app, &staging)?;
+    let result = sanitize_copy(&staging, include_archived, redact_secrets)
+        .map_err(|e| format!("Failed to prepare the copy: {}", e))
+        .and_then(|_| {
+            fs::rename(&staging, target).map_err(|e| format!("Failed to write '{}': {}", target.display(), e))
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&staging);
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn export_sqlite(
+    app: AppHandle,
+    path: String,
+    include_archived: bool,
+    redact_secrets: bool,
+) -> Result<(), String> {
+    export_copy(&app, Path::new(&path), include_archived, redact_secrets)
+}
+
+#[tauri::command]
+pub async fn backup_database(app: AppHandle, target_path: String) -> Result<BackupInfo, String> {
+    let app_version = app.package_info().version.to_string();
+    let created_at = dates::now_ms();
+
+    // A directory gets a versioned, timestamped file name
+    let mut target = PathBuf::from(target_path);
+    if target.is_dir() {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        target = target.join(format!("tada-backup-{}-{}.db", app_version, stamp));
+    }
+
+    snapshot_to(&app, &target)?;
+
+    Ok(BackupInfo {
+        path: normalize(&target)?.to_string_lossy().to_string(),
+        app_version,
+        created_at,
+    })
+}
+
+// Check that `path` is an intact Tada database this build can migrate, returning its schema version
+fn validate_backup(path: &Path) -> Result<i64, String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Cannot open backup: {}", e))?;
+
+    let check: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| format!("'{}' is not a SQLite database: {}", path.display(), e))?;
+    if check != "ok" {
+        return Err(format!("Backup is corrupted: {}", check));
+    }
+
+    for table in ["lists", "tasks", "subtasks", "settings", "_sqlx_migrations"] {
+        let exists: Option<String> = conn
+            .query_row(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if exists.is_none() {
+            return Err(format!("Backup is not a Tada database (missing table '{}')", table));
+        }
+    }
+
+    let settings_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM settings", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if settings_count == 0 {
+        return Err("Backup has no settings, it does not look like a Tada database".to_string());
+    }
+
+    let version: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success = 1",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let supported = crate::latest_schema_version();
+    if version > supported {
+        return Err(format!(
+            "Backup was made by a newer version of Tada (schema {}, this version supports up to {}). Update Tada before restoring it.",
+            version, supported
+        ));
+    }
+
+    Ok(version)
+}
+
+fn rename_if_exists(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.exists() {
+        fs::rename(from, to)?;
+    }
+    Ok(())
+}
+
+// Swap a staged backup in before the sql plugin opens the database (see
+// `relocate::prepare`). The replaced database, including its WAL/SHM files, is kept as
+// `tada.db.pre-restore`.
+pub fn apply_staged_restore(dir: &Path) -> std::io::Result<()> {
+    let staged = dir.join(STAGED_RESTORE_FILE);
+    if !staged.exists() {
+        return Ok(());
+    }
+
+    let live = dir.join(db::DB_FILE);
+    let previous = dir.join(PRE_RESTORE_FILE);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", previous.display(), suffix));
+        rename_if_exists(
+            Path::new(&format!("{}{}", live.display(), suffix)),
+            Path::new(&format!("{}{}", previous.display(), suffix)),
+        )?;
+    }
+    fs::rename(&staged, &live)
+}
+
+// Validate the backup, stage it next to the live database and relaunch. The sql plugin
+// keeps the live file open, so the swap itself happens on the next start (see `relocate::prepare`).
+#[tauri::command]
+pub async fn restore_database(app: AppHandle, source_path: String) -> Result<(), String> {
+    let source = PathBuf::from(source_path);
+    ensure_not_live_database(&app, &source)?;
+    validate_backup(&source)?;
+
+    let dir = db::db_path(&app)?
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "Invalid database location".to_string())?;
+    let staged = dir.join(STAGED_RESTORE_FILE);
+    let partial = dir.join(format!("{}.partial", STAGED_RESTORE_FILE));
+    fs::copy(&source, &partial).map_err(|e| format!("Failed to stage backup: {}", e))?;
+    fs::rename(&partial, &staged).map_err(|e| format!("Failed to stage backup: {}", e))?;
+
+    // Let the relaunch close the main window instead of hiding it to the tray
+    app.state::<AppState>().is_quitting.store(true, Ordering::Relaxed);
+    app.restart()
+}
@@ -0,0 +1,81 @@
+use crate::reminders::ReminderScheduler;
+use crate::tasks::{self, NewTask, Task};
+use crate::{dates, db, journal, list_defaults, natural_dates, quick_add, settings, tray};
+use rusqlite::Connection;
+use tauri::{AppHandle, Manager};
+
+// Longer lines are cut, a pasted paragraph shouldn't become one giant title
+const MAX_TITLE_CHARS: usize = 500;
+
+// A line without its list marker: "-", "*", "•" or a Markdown checkbox
+fn strip_bullet(line: &str) -> &str {
+    let line = line.trim();
+    for bullet in ["- [ ]", "- [x]", "* [ ]", "* [x]", "-", "*", "•"] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return rest.trim_start();
+        }
+    }
+    line
+}
+
+// One task per non-blank line, in the order given, with date phrases parsed like the
+// quick add window does. Each line is inserted under its own savepoint: a line that fails
+// is logged and skipped, the rest of the batch still goes in.
+pub fn create_bulk(conn: &mut Connection, list_id: &str, text: &str) -> Result<Vec<Task>, String> {
+    let now = dates::now_ms();
+    let mut tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "create_tasks_bulk").map_err(|e| e.to_string())?;
+    let list_name = tasks::list_name(&tx, list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+    let defaults = list_defaults::load(&tx, list_id)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let language = natural_dates::Language::from_setting(settings::language(&tx).as_deref());
+
+    let mut created = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = strip_bullet(line);
+        if line.is_empty() {
+            continue;
+        }
+        let line: String = line.chars().take(MAX_TITLE_CHARS).collect();
+        let (title, due_date) = quick_add::split_due_date(&line, now, language);
+        let mut task = NewTask {
+            title,
+            due_date,
+            ..Default::default()
+        };
+        defaults.apply(&mut task);
+
+        let inserted = tx.savepoint().and_then(|savepoint| {
+            let id = tasks::insert(&savepoint, list_id, &list_name, &task)?;
+            let task = tasks::get(&savepoint, &id)?;
+            savepoint.commit()?;
+            Ok(task)
+        });
+        match inserted {
+            Ok(Some(task)) => created.push(task),
+            Ok(None) => {}
+            Err(e) => eprintln!("Skipped line {} of the bulk add: {}", number + 1, e),
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn create_tasks_bulk(app: AppHandle, list_id: String, text: String) -> Result<Vec<Task>, String> {
+    let created = {
+        let mut conn = db::connect(&app)?;
+        create_bulk(&mut conn, &list_id, &text)?
+    };
+    if !created.is_empty() {
+        app.state::<ReminderScheduler>().wake();
+        if let Err(e) = tray::refresh(&app) {
+            eprintln!("Failed to refresh tray: {}", e);
+        }
+    }
+    Ok(created)
+}
@@ -0,0 +1,140 @@
+use crate::{db, dependencies};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+pub const ENTITY_CHANGED_EVENT: &str = "entity-changed";
+
+// The outbox is filled by triggers (migration 16), so writes from the frontend's sql
+// plugin land there too. This side only reads it: new rows are emitted as events, and
+// consumers that were offline catch up with `drain_outbox`.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_BATCH: i64 = 500;
+// Changes nobody acknowledged are dropped after this long, without a sync consumer the
+// table would otherwise grow with every edit
+const RETENTION_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+    pub seq: i64,
+    pub table: String,
+    pub id: String,
+    // "insert", "update" or "delete"
+    pub op: String,
+    pub updated_at: i64,
+}
+
+impl ChangeEvent {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            seq: row.get(0)?,
+            table: row.get(1)?,
+            id: row.get(2)?,
+            op: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    }
+}
+
+fn is_installed(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'outbox')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+fn after_seq(conn: &Connection, seq: i64, limit: i64) -> rusqlite::Result<Vec<ChangeEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT seq, entity_table, entity_id, op, updated_at FROM outbox WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![seq, limit], ChangeEvent::from_row)?;
+    rows.collect()
+}
+
+// Unacknowledged changes made at or after `since_ms`, oldest first
+pub fn drain(conn: &Connection, since_ms: i64) -> rusqlite::Result<Vec<ChangeEvent>> {
+    if !is_installed(conn)? {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare(
+        "SELECT seq, entity_table, entity_id, op, updated_at FROM outbox WHERE updated_at >= ?1 ORDER BY seq",
+    )?;
+    let rows = stmt.query_map([since_ms], ChangeEvent::from_row)?;
+    rows.collect()
+}
+
+// Forget every change up to and including `seq`
+pub fn ack(conn: &Connection, seq: i64) -> rusqlite::Result<usize> {
+    if !is_installed(conn)? {
+        return Ok(0);
+    }
+    conn.execute("DELETE FROM outbox WHERE seq <= ?1", [seq])
+}
+
+// Called on startup, see RETENTION_DAYS
+pub fn prune(conn: &Connection, now_ms: i64) -> rusqlite::Result<usize> {
+    if !is_installed(conn)? {
+        return Ok(0);
+    }
+    conn.execute(
+        "DELETE FROM outbox WHERE updated_at < ?1",
+        [now_ms - RETENTION_DAYS * 86_400_000],
+    )
+}
+
+// Rows added since the last call. The first call only notes where the outbox ends, rows
+// already there when the app starts are left to `drain_outbox`.
+fn poll(conn: &Connection, emitted: &mut Option<i64>) -> rusqlite::Result<Vec<ChangeEvent>> {
+    if !is_installed(conn)? {
+        // Everything in the outbox once the migration creates it is new
+        *emitted = Some(0);
+        return Ok(Vec::new());
+    }
+    let Some(seq) = *emitted else {
+        let last: Option<i64> = conn.query_row("SELECT MAX(seq) FROM outbox", [], |row| row.get(0))?;
+        *emitted = Some(last.unwrap_or(0));
+        return Ok(Vec::new());
+    };
+    let events = after_seq(conn, seq, MAX_BATCH)?;
+    if let Some(last) = events.last() {
+        *emitted = Some(last.seq);
+    }
+    Ok(events)
+}
+
+// Emit an `entity-changed` event for every change written to the outbox while the app runs
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut emitted = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let result = db::connect(&app).and_then(|conn| {
+                let events = poll(&conn, &mut emitted).map_err(|e| e.to_string())?;
+                for event in &events {
+                    let _ = app.emit(ENTITY_CHANGED_EVENT, event);
+                }
+                dependencies::emit_unblocked(&app, &conn, &events).map_err(|e| e.to_string())
+            });
+            if let Err(e) = result {
+                eprintln!("Failed to read the outbox: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn drain_outbox(app: AppHandle, since_ms: i64) -> Result<Vec<ChangeEvent>, String> {
+    let conn = db::connect(&app)?;
+    drain(&conn, since_ms).map_err(|e| e.to_string())
+}
+
+// Returns how many changes were removed
+#[tauri::command]
+pub async fn ack_outbox(app: AppHandle, up_to_seq: i64) -> Result<usize, String> {
+    let conn = db::connect(&app)?;
+    ack(&conn, up_to_seq).map_err(|e| e.to_string())
+}
@@ -0,0 +1,82 @@
+use crate::{dates, db};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::AppHandle;
+
+// Longer comments are cut, in characters
+const MAX_BODY_CHARS: usize = 10_000;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    pub id: i64,
+    pub task_id: String,
+    // 'comment' for a user's comment. The rest is recorded by triggers (migration 23):
+    // 'completed' and 'reopened' with `{}`, 'moved' with the list names and
+    // 'dueDateChanged' with the due dates, both as `{"from": .., "to": ..}`.
+    pub kind: String,
+    pub body: String,
+    pub created_at: i64,
+}
+
+impl Comment {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            kind: row.get("kind")?,
+            body: row.get("body")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+pub fn add(conn: &Connection, task_id: &str, body: &str) -> Result<Comment, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("A comment can't be empty".to_string());
+    }
+    let body: String = body.chars().take(MAX_BODY_CHARS).collect();
+    let found = conn
+        .query_row("SELECT 1 FROM tasks WHERE id = ?1 AND deleted_at IS NULL", [task_id], |_| Ok(()))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    found.ok_or_else(|| format!("Task {} not found", task_id))?;
+    conn.query_row(
+        "INSERT INTO task_comments (task_id, kind, body, created_at) VALUES (?1, 'comment', ?2, ?3)
+         RETURNING id, task_id, kind, body, created_at",
+        params![task_id, body, dates::now_ms()],
+        Comment::from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+// Oldest first, the order of a timeline
+pub fn for_task(conn: &Connection, task_id: &str) -> rusqlite::Result<Vec<Comment>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, task_id, kind, body, created_at FROM task_comments WHERE task_id = ?1 ORDER BY created_at, id",
+    )?;
+    let rows = stmt.query_map([task_id], Comment::from_row)?;
+    rows.collect()
+}
+
+#[tauri::command]
+pub async fn add_comment(app: AppHandle, task_id: String, body: String) -> Result<Comment, String> {
+    let conn = db::connect(&app)?;
+    add(&conn, &task_id, &body)
+}
+
+#[tauri::command]
+pub async fn list_comments(app: AppHandle, task_id: String) -> Result<Vec<Comment>, String> {
+    let conn = db::connect(&app)?;
+    for_task(&conn, &task_id).map_err(|e| e.to_string())
+}
+
+// Returns whether there was such a comment
+#[tauri::command]
+pub async fn delete_comment(app: AppHandle, id: i64) -> Result<bool, String> {
+    let conn = db::connect(&app)?;
+    conn.execute("DELETE FROM task_comments WHERE id = ?1", [id])
+        .map(|removed| removed > 0)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,251 @@
+use crate::reminders::ReminderScheduler;
+use crate::settings::{self, Preferences};
+use crate::tasks::{self, Task};
+use crate::{dates, db, journal, recurrence, tray};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionResult {
+    pub task: Task,
+    // Subtasks this call completed, ones that were already done aren't listed
+    pub subtask_ids: Vec<String>,
+    // Next occurrence added for a recurring task, see `recurrence::spawn_next`
+    pub next_instance: Option<Task>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParentProgress {
+    pub task_id: String,
+    // Completed live subtasks / live subtasks as a whole percentage
+    pub complete_percentage: i64,
+    // Completed by this call because `preferences.autoCompleteParent` is set
+    pub auto_completed: bool,
+    pub next_instance: Option<Task>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParentUpdate {
+    // Subtasks this call changed, ones already in the requested state aren't listed
+    pub subtask_ids: Vec<String>,
+    // Every parent of a changed subtask, in the order of `subtask_ids`
+    pub parents: Vec<ParentProgress>,
+}
+
+fn live_task(conn: &Connection, id: &str) -> Result<Task, String> {
+    let deleted_at: Option<Option<i64>> = conn
+        .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match deleted_at {
+        None => return Err(format!("Task {} not found", id)),
+        Some(Some(_)) => return Err(format!("Task {} is in the trash", id)),
+        Some(None) => {}
+    }
+    tasks::get(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))
+}
+
+// Completed tasks leave their due-date bucket (see `dates::group_category`). A task
+// that is already done keeps its original `completed_at` and doesn't spawn another
+// occurrence of its series.
+pub fn complete(conn: &mut Connection, id: &str, cascade: bool) -> Result<CompletionResult, String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "complete_task").map_err(|e| e.to_string())?;
+    let task = live_task(&tx, id)?;
+    mark_completed(&tx, &task, now)?;
+
+    let mut subtask_ids = Vec::new();
+    if cascade {
+        subtask_ids = {
+            let mut stmt = tx
+                .prepare(r#"SELECT id FROM subtasks WHERE parent_id = ?1 AND completed = 0 AND deleted_at IS NULL ORDER BY "order""#)
+                .map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([id], |row| row.get(0)).map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        tx.execute(
+            "UPDATE subtasks SET completed = 1, completed_at = ?1, updated_at = ?1
+             WHERE parent_id = ?2 AND completed = 0 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let next_instance = spawn_next(&tx, &task, now)?;
+    let task = live_task(&tx, id)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(CompletionResult {
+        task,
+        subtask_ids,
+        next_instance,
+    })
+}
+
+fn mark_completed(conn: &Connection, task: &Task, now: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE tasks SET completed = 1, completed_at = COALESCE(completed_at, ?1), complete_percentage = 100,
+             group_category = ?2, updated_at = ?1
+         WHERE id = ?3",
+        params![now, dates::group_category(task.due_date, true, now), task.id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// The next occurrence of a recurring task that `task` (as it was before completing) wasn't
+// already done
+fn spawn_next(conn: &Connection, task: &Task, now: i64) -> Result<Option<Task>, String> {
+    if task.completed || !recurrence::spawns_on_complete(conn) {
+        return Ok(None);
+    }
+    match recurrence::spawn_next(conn, &task.id, now)? {
+        Some(next_id) => tasks::get(conn, &next_id).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+// Complete or reopen subtasks and bring each parent's `complete_percentage` in line with
+// its live subtasks. With `auto_complete_parent` a parent whose subtasks are now all done
+// is completed like `complete` does, reopening a subtask leaves its parent as it is.
+// Unknown and trashed subtasks are skipped.
+pub fn set_subtasks(
+    conn: &mut Connection,
+    ids: &[String],
+    completed: bool,
+    auto_complete_parent: bool,
+) -> Result<ParentUpdate, String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "set_subtasks_completed").map_err(|e| e.to_string())?;
+
+    let mut subtask_ids = Vec::new();
+    let mut parent_ids: Vec<String> = Vec::new();
+    for id in ids {
+        let parent_id: Option<String> = tx
+            .query_row(
+                "UPDATE subtasks SET completed = ?1, completed_at = CASE WHEN ?1 THEN ?2 END, updated_at = ?2
+                 WHERE id = ?3 AND completed IS NOT ?1 AND deleted_at IS NULL
+                 RETURNING parent_id",
+                params![completed, now, id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if let Some(parent_id) = parent_id {
+            subtask_ids.push(id.clone());
+            if !parent_ids.contains(&parent_id) {
+                parent_ids.push(parent_id);
+            }
+        }
+    }
+
+    let mut parents = Vec::new();
+    for parent_id in parent_ids {
+        let (done, total): (i64, i64) = tx
+            .query_row(
+                "SELECT COALESCE(SUM(completed), 0), COUNT(*) FROM subtasks WHERE parent_id = ?1 AND deleted_at IS NULL",
+                [&parent_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        let percentage = if total == 0 { 0 } else { (done * 100 + total / 2) / total };
+        tx.execute(
+            "UPDATE tasks SET complete_percentage = ?1, updated_at = ?2 WHERE id = ?3 AND complete_percentage IS NOT ?1",
+            params![percentage, now, parent_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let mut progress = ParentProgress {
+            task_id: parent_id,
+            complete_percentage: percentage,
+            auto_completed: false,
+            next_instance: None,
+        };
+        if auto_complete_parent && total > 0 && done == total {
+            let task = live_task(&tx, &progress.task_id).ok();
+            if let Some(task) = task.filter(|task| !task.completed) {
+                mark_completed(&tx, &task, now)?;
+                progress.next_instance = spawn_next(&tx, &task, now)?;
+                progress.auto_completed = true;
+            }
+        }
+        parents.push(progress);
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(ParentUpdate { subtask_ids, parents })
+}
+
+// Reopen a task and put it back in its due-date bucket. Subtasks keep their state.
+pub fn uncomplete(conn: &mut Connection, id: &str) -> Result<Task, String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "uncomplete_task").map_err(|e| e.to_string())?;
+    let task = live_task(&tx, id)?;
+    tx.execute(
+        "UPDATE tasks SET completed = 0, completed_at = NULL, complete_percentage = NULL,
+             group_category = ?1, updated_at = ?2
+         WHERE id = ?3",
+        params![dates::group_category(task.due_date, false, now), now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    let task = live_task(&tx, id)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn complete_task(app: AppHandle, id: String, cascade: bool) -> Result<CompletionResult, String> {
+    let result = {
+        let mut conn = db::connect(&app)?;
+        complete(&mut conn, &id, cascade)?
+    };
+    if result.next_instance.is_some() {
+        app.state::<ReminderScheduler>().wake();
+    }
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn uncomplete_task(app: AppHandle, id: String) -> Result<Task, String> {
+    let task = {
+        let mut conn = db::connect(&app)?;
+        uncomplete(&mut conn, &id)?
+    };
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn set_subtasks_completed(
+    app: AppHandle,
+    subtask_ids: Vec<String>,
+    completed: bool,
+) -> Result<ParentUpdate, String> {
+    let update = {
+        let mut conn = db::connect(&app)?;
+        let prefs = settings::get_typed::<Preferences>(&conn).map_err(|e| e.to_string())?;
+        set_subtasks(&mut conn, &subtask_ids, completed, prefs.auto_complete_parent)?
+    };
+    if update.parents.iter().any(|parent| parent.next_instance.is_some()) {
+        app.state::<ReminderScheduler>().wake();
+    }
+    if update.parents.iter().any(|parent| parent.auto_completed) && let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(update)
+}
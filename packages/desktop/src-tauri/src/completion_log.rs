@@ -0,0 +1,104 @@
+use crate::db;
+use crate::tag_export::ExportFormat;
+use chrono::{DateTime, FixedOffset};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::AppHandle;
+
+const CSV_HEADER: &[&str] = &["Completed At", "Local Time", "Title", "List", "Estimate (min)", "Actual (min)", "Archived"];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionEntry {
+    pub task_id: String,
+    pub title: String,
+    pub list_name: String,
+    pub completed_at: i64,
+    // `completed_at` at the requested offset, YYYY-MM-DD HH:MM
+    pub local_time: String,
+    pub estimate_minutes: Option<i64>,
+    pub actual_minutes: Option<i64>,
+    pub archived: bool,
+}
+
+// Tasks completed in [start_ms, end_ms), live and archived, oldest completion first
+pub fn entries(conn: &Connection, start_ms: i64, end_ms: i64, tz_offset_minutes: i32) -> Result<Vec<CompletionEntry>, String> {
+    let zone = FixedOffset::east_opt(tz_offset_minutes * 60)
+        .ok_or_else(|| format!("Invalid UTC offset: {} minutes", tz_offset_minutes))?;
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT id, title, list_name, completed_at, estimate_minutes, actual_minutes, 0 AS archived FROM tasks
+            WHERE completed = 1 AND completed_at >= ?1 AND completed_at < ?2 AND list_name != 'Trash' AND deleted_at IS NULL
+            UNION ALL
+            SELECT id, title, list_name, completed_at, estimate_minutes, actual_minutes, 1 FROM archived_tasks
+            WHERE completed_at >= ?1 AND completed_at < ?2
+            ORDER BY completed_at, id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start_ms, end_ms], |row| {
+            let completed_at: i64 = row.get(3)?;
+            Ok(CompletionEntry {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                list_name: row.get(2)?,
+                completed_at,
+                local_time: DateTime::from_timestamp_millis(completed_at)
+                    .map(|dt| dt.with_timezone(&zone).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+                estimate_minutes: row.get(4)?,
+                actual_minutes: row.get(5)?,
+                archived: row.get::<_, i64>(6)? != 0,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn render_csv(entries: &[CompletionEntry]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADER).map_err(|e| e.to_string())?;
+    let minutes = |m: Option<i64>| m.map(|m| m.to_string()).unwrap_or_default();
+    for entry in entries {
+        writer
+            .write_record([
+                entry.completed_at.to_string().as_str(),
+                entry.local_time.as_str(),
+                entry.title.as_str(),
+                entry.list_name.as_str(),
+                minutes(entry.estimate_minutes).as_str(),
+                minutes(entry.actual_minutes).as_str(),
+                if entry.archived { "yes" } else { "no" },
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+pub fn render(entries: &[CompletionEntry], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(entries).map_err(|e| e.to_string()),
+        ExportFormat::Csv => render_csv(entries),
+        ExportFormat::Markdown => Err("The completion log can be exported as CSV or JSON".to_string()),
+    }
+}
+
+// Days and times in the log are shown at `tz_offset_minutes` east of UTC, the range itself
+// is absolute
+#[tauri::command]
+pub async fn export_completion_log(
+    app: AppHandle,
+    start_ms: i64,
+    end_ms: i64,
+    format: ExportFormat,
+    tz_offset_minutes: i32,
+) -> Result<String, String> {
+    if end_ms <= start_ms {
+        return Err("end_ms must be after start_ms".to_string());
+    }
+    let conn = db::connect(&app)?;
+    render(&entries(&conn, start_ms, end_ms, tz_offset_minutes)?, format)
+}
@@ -0,0 +1,43 @@
+use crate::{db, export};
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCount {
+    pub list_id: String,
+    pub list_name: String,
+    pub incomplete: i64,
+    pub total: i64,
+}
+
+// Reads the `list_counts` table the task triggers keep up to date (migration 11), so this
+// never scans `tasks`. Every live list is included, the inbox always, hidden lists only
+// with `include_hidden`.
+pub fn list_counts(conn: &Connection, include_hidden: bool) -> rusqlite::Result<Vec<ListCount>> {
+    export::ensure_inbox(conn)?;
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT l.id, l.name, COALESCE(c.incomplete, 0), COALESCE(c.total, 0)
+        FROM lists l LEFT JOIN list_counts c ON c.list_id = l.id
+        WHERE l.deleted_at IS NULL AND (?1 OR l.hidden = 0)
+        ORDER BY l."order", l.name
+        "#,
+    )?;
+    let rows = stmt.query_map([include_hidden], |row| {
+        Ok(ListCount {
+            list_id: row.get(0)?,
+            list_name: row.get(1)?,
+            incomplete: row.get(2)?,
+            total: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+#[tauri::command]
+pub async fn get_list_counts(app: AppHandle, include_hidden: Option<bool>) -> Result<Vec<ListCount>, String> {
+    let conn = db::connect(&app)?;
+    list_counts(&conn, include_hidden.unwrap_or(false)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,117 @@
+use crate::tasks::{self, NewTask};
+use crate::{dates, db, journal, list_defaults};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+// Header names of the CSV columns to read, matched case-insensitively
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    pub title: String,
+    pub due_date: Option<String>,
+    pub priority: Option<String>,
+    pub tags: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub imported: usize,
+    // Rows without a title
+    pub skipped: usize,
+    // Rows imported without a due date because it couldn't be read
+    pub invalid_dates: usize,
+}
+
+fn normalize_header(header: &str) -> String {
+    header.trim_start_matches('\u{feff}').trim().to_lowercase()
+}
+
+fn column_index(headers: &csv::StringRecord, name: &str) -> Result<usize, String> {
+    let wanted = normalize_header(name);
+    headers
+        .iter()
+        .position(|header| normalize_header(header) == wanted)
+        .ok_or_else(|| format!("Column '{}' not found in the CSV header", name))
+}
+
+fn optional_column(headers: &csv::StringRecord, name: &Option<String>) -> Result<Option<usize>, String> {
+    name.as_deref()
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| column_index(headers, name))
+        .transpose()
+}
+
+// Spreadsheet cells hold tags separated by commas, semicolons or pipes, with or without `#`
+fn split_tags(cell: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in cell.split([',', ';', '|']) {
+        let tag = tag.trim().trim_start_matches('#').trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+#[tauri::command]
+pub async fn import_csv(
+    app: AppHandle,
+    path: String,
+    list_id: String,
+    mapping: ColumnMapping,
+) -> Result<ImportSummary, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(&path)
+        .map_err(|e| format!("Failed to open '{}': {}", path, e))?;
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let title_col = column_index(&headers, &mapping.title)?;
+    let due_col = optional_column(&headers, &mapping.due_date)?;
+    let priority_col = optional_column(&headers, &mapping.priority)?;
+    let tags_col = optional_column(&headers, &mapping.tags)?;
+
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_csv").map_err(|e| e.to_string())?;
+    let list_name = tasks::list_name(&tx, &list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+    let defaults = list_defaults::load(&tx, &list_id)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+
+    let mut summary = ImportSummary::default();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let cell = |col: Option<usize>| col.and_then(|col| record.get(col)).unwrap_or("");
+
+        let title = cell(Some(title_col));
+        if title.is_empty() {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let due_text = cell(due_col);
+        let due_date = dates::parse_due_date(due_text);
+        if due_date.is_none() && !due_text.is_empty() {
+            summary.invalid_dates += 1;
+        }
+
+        let mut task = NewTask {
+            title: title.to_string(),
+            due_date,
+            priority: tasks::parse_priority(cell(priority_col)),
+            tags: split_tags(cell(tags_col)),
+            ..Default::default()
+        };
+        defaults.apply(&mut task);
+        tasks::insert(&tx, &list_id, &list_name, &task).map_err(|e| e.to_string())?;
+        summary.imported += 1;
+    }
+
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+
+// Due dates are stored as epoch millis produced by the frontend from local wall-clock
+// times, so calendar arithmetic is done on local naive datetimes and converted back.
+
+pub fn now_ms() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+pub fn to_local_naive(ms: i64) -> Option<NaiveDateTime> {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|dt| dt.naive_local())
+}
+
+pub fn from_local_naive(naive: NaiveDateTime) -> i64 {
+    // A wall-clock time skipped by a DST jump does not exist, use the first valid instant after it
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(naive + TimeDelta::hours(1))).earliest())
+        .map(|dt| dt.timestamp_millis())
+        .unwrap_or_else(|| naive.and_utc().timestamp_millis())
+}
+
+pub fn local_date(ms: i64) -> Option<NaiveDate> {
+    to_local_naive(ms).map(|dt| dt.date())
+}
+
+pub fn local_midnight(date: NaiveDate) -> i64 {
+    from_local_naive(date.and_time(NaiveTime::MIN))
+}
+
+// Start of the day containing `now_ms` for a clock `utc_offset_minutes` east of UTC
+pub fn day_start_at_offset(now_ms: i64, utc_offset_minutes: i32) -> Result<i64, String> {
+    let zone = FixedOffset::east_opt(utc_offset_minutes * 60)
+        .ok_or_else(|| format!("Invalid UTC offset: {} minutes", utc_offset_minutes))?;
+    let now = zone
+        .timestamp_millis_opt(now_ms)
+        .single()
+        .ok_or_else(|| format!("Invalid time {}", now_ms))?;
+    let midnight = now.date_naive().and_time(NaiveTime::MIN);
+    zone.from_local_datetime(&midnight)
+        .single()
+        .map(|dt| dt.timestamp_millis())
+        .ok_or_else(|| format!("Invalid time {}", now_ms))
+}
+
+const DATETIME_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y", "%Y/%m/%d"];
+
+// Parse a due date typed by a person or written by another app. Timestamps with an
+// offset are taken as is, anything else is local time and a bare date means its midnight.
+pub fn parse_due_date(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some(dt.timestamp_millis());
+    }
+    for format in DATETIME_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(from_local_naive(naive));
+        }
+    }
+    for format in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            return Some(local_midnight(date));
+        }
+    }
+    None
+}
+
+// Bucket a task by its due date relative to the local "today" of `now_ms`.
+// Completed tasks never count as overdue and fall back to `nodate`, like the frontend.
+pub fn group_category(due_date: Option<i64>, completed: bool, now_ms: i64) -> &'static str {
+    if completed {
+        return "nodate";
+    }
+    let (Some(due), Some(today)) = (due_date.and_then(local_date), local_date(now_ms)) else {
+        return "nodate";
+    };
+
+    let days = (due - today).num_days();
+    if days < 0 {
+        "overdue"
+    } else if days == 0 {
+        "today"
+    } else if days == 1 {
+        "tomorrow"
+    } else {
+        "upcoming"
+    }
+}
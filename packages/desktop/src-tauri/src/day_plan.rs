@@ -0,0 +1,280 @@
+use crate::ai::{self, AiError};
+use crate::{dates, db};
+use chrono::TimeDelta;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use tauri::AppHandle;
+
+// What a task without an estimate is planned with, in minutes
+const DEFAULT_ESTIMATE_MINUTES: i64 = 30;
+
+const PLAN_SYSTEM_PROMPT: &str = "You plan a work day from a list of tasks. Pick the tasks that matter most \
+and order them the way they should be done, keeping the total estimate within the capacity. Weigh \
+priority (1 high, 2 medium, 3 low), overdue tasks and due times. Answer with a JSON object only, no \
+prose and no code fence: {\"order\": [task ids, first task first], \"rationale\": \"two or three \
+sentences on why\"}.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedTask {
+    pub task_id: String,
+    pub title: String,
+    pub list_name: String,
+    pub priority: Option<i64>,
+    pub due_date: Option<i64>,
+    pub estimate_minutes: Option<i64>,
+    pub overdue: bool,
+}
+
+impl PlannedTask {
+    fn minutes(&self) -> i64 {
+        self.estimate_minutes.filter(|m| *m > 0).unwrap_or(DEFAULT_ESTIMATE_MINUTES)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlanSource {
+    Ai,
+    // The deterministic ranking of `rank_locally`
+    Local,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayPlan {
+    // In the order to work on them
+    pub tasks: Vec<PlannedTask>,
+    // Due today or overdue but not in the plan
+    pub left_out: Vec<PlannedTask>,
+    pub capacity_minutes: i64,
+    // Tasks without an estimate count as `DEFAULT_ESTIMATE_MINUTES`
+    pub planned_minutes: i64,
+    pub rationale: String,
+    pub source: PlanSource,
+    // Why the AI wasn't used, for a `Local` plan of a day with tasks
+    pub fallback_reason: Option<String>,
+    pub created_at: i64,
+}
+
+// Incomplete live tasks due before the end of `now_ms`'s local day
+pub fn candidates(conn: &Connection, now_ms: i64) -> Result<Vec<PlannedTask>, String> {
+    let today = dates::local_date(now_ms).ok_or("Invalid local time")?;
+    let today_start = dates::local_midnight(today);
+    let day_end = today
+        .checked_add_signed(TimeDelta::days(1))
+        .map(dates::local_midnight)
+        .ok_or("Invalid local time")?;
+    let mut stmt = conn
+        .prepare(
+            r#"SELECT id, title, list_name, priority, due_date, estimate_minutes FROM tasks
+               WHERE completed = 0 AND due_date IS NOT NULL AND due_date < ?1
+               AND deleted_at IS NULL AND list_name != 'Trash'
+               ORDER BY due_date, "order""#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([day_end], |row| {
+            let due_date: Option<i64> = row.get(4)?;
+            Ok(PlannedTask {
+                task_id: row.get(0)?,
+                title: row.get(1)?,
+                list_name: row.get(2)?,
+                priority: row.get(3)?,
+                due_date,
+                estimate_minutes: row.get(5)?,
+                overdue: due_date.is_some_and(|due| due < today_start),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// Take tasks in the given order while they fit, a task that doesn't fit is left out and
+// the next one tried
+fn fill(
+    ordered: Vec<PlannedTask>,
+    rest: Vec<PlannedTask>,
+    capacity_minutes: i64,
+) -> (Vec<PlannedTask>, Vec<PlannedTask>, i64) {
+    let mut planned = Vec::new();
+    let mut left_out = Vec::new();
+    let mut minutes = 0;
+    for task in ordered {
+        if minutes + task.minutes() <= capacity_minutes {
+            minutes += task.minutes();
+            planned.push(task);
+        } else {
+            left_out.push(task);
+        }
+    }
+    left_out.extend(rest);
+    (planned, left_out, minutes)
+}
+
+// Overdue first, then by priority (none counts as lowest), due date and the shorter task
+pub fn rank_locally(mut tasks: Vec<PlannedTask>) -> Vec<PlannedTask> {
+    tasks.sort_by_key(|task| (!task.overdue, task.priority.unwrap_or(4), task.due_date, task.minutes()));
+    tasks
+}
+
+fn local_plan(tasks: Vec<PlannedTask>, capacity_minutes: i64, reason: Option<String>, now_ms: i64) -> DayPlan {
+    let total = tasks.len();
+    let (planned, left_out, minutes) = fill(rank_locally(tasks), Vec::new(), capacity_minutes);
+    let rationale = if total == 0 {
+        "Nothing is due today or overdue.".to_string()
+    } else {
+        format!(
+            "Overdue tasks first, then by priority and due time. {} of {} tasks fit in {} minutes.",
+            planned.len(),
+            total,
+            capacity_minutes
+        )
+    };
+    DayPlan {
+        tasks: planned,
+        left_out,
+        capacity_minutes,
+        planned_minutes: minutes,
+        rationale,
+        source: PlanSource::Local,
+        fallback_reason: reason,
+        created_at: now_ms,
+    }
+}
+
+fn plan_prompt(tasks: &[PlannedTask], capacity_minutes: i64, now_ms: i64) -> String {
+    let now = dates::to_local_naive(now_ms)
+        .map(|now| now.format("%Y-%m-%d %H:%M (%A)").to_string())
+        .unwrap_or_default();
+    let tasks: Vec<Value> = tasks
+        .iter()
+        .map(|task| {
+            json!({
+                "id": task.task_id,
+                "title": task.title,
+                "priority": task.priority,
+                "estimateMinutes": task.estimate_minutes,
+                "due": task.due_date.and_then(dates::to_local_naive).map(|due| due.format("%Y-%m-%d %H:%M").to_string()),
+                "overdue": task.overdue,
+            })
+        })
+        .collect();
+    format!(
+        "It is {}. Capacity: {} minutes. Tasks without an estimate take about {} minutes.\n\nTasks:\n{}",
+        now,
+        capacity_minutes,
+        DEFAULT_ESTIMATE_MINUTES,
+        Value::Array(tasks)
+    )
+}
+
+// The model's order as tasks, unknown and repeated ids dropped. `None` when the reply
+// doesn't name a single candidate.
+fn ai_order(reply: &str, tasks: &[PlannedTask]) -> Option<(Vec<PlannedTask>, Vec<PlannedTask>, String)> {
+    let start = reply.find('{')?;
+    let end = reply.rfind('}')?;
+    if end < start {
+        return None;
+    }
+    let reply: Value = serde_json::from_str(&reply[start..=end]).ok()?;
+    let mut seen = HashSet::new();
+    let ordered: Vec<PlannedTask> = reply
+        .get("order")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .filter(|id| seen.insert(id.to_string()))
+        .filter_map(|id| tasks.iter().find(|task| task.task_id == id).cloned())
+        .collect();
+    if ordered.is_empty() {
+        return None;
+    }
+    let rest = tasks.iter().filter(|task| !seen.contains(&task.task_id)).cloned().collect();
+    let rationale = reply.get("rationale").and_then(Value::as_str).unwrap_or("").trim().to_string();
+    Some((ordered, rest, rationale))
+}
+
+fn reason(e: &AiError) -> String {
+    match e {
+        AiError::MissingApiKey => "No API key is configured".to_string(),
+        AiError::MissingModel => "No model is configured".to_string(),
+        AiError::UnsupportedProvider(provider) => format!("Unsupported provider: {}", provider),
+        AiError::Network(message) | AiError::Provider(message) | AiError::Storage(message) => message.clone(),
+    }
+}
+
+fn save(conn: &Connection, plan: &DayPlan) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO day_plans (id, plan, created_at) VALUES (1, ?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET plan = excluded.plan, created_at = excluded.created_at",
+        params![serde_json::to_string(plan).map_err(|e| e.to_string())?, plan.created_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn last(conn: &Connection) -> Result<Option<DayPlan>, String> {
+    let plan: Option<String> = conn
+        .query_row("SELECT plan FROM day_plans WHERE id = 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    // A plan from an older build that no longer reads is as good as none
+    Ok(plan.and_then(|plan| serde_json::from_str(&plan).ok()))
+}
+
+// Order what's due today or overdue into a plan that fits in `capacity_minutes`. The AI
+// picks and orders the tasks, when it isn't configured or its answer can't be used the
+// plan falls back to `rank_locally`. Either way the plan is kept as the last one.
+#[tauri::command]
+pub async fn plan_my_day(app: AppHandle, capacity_minutes: i64, now_ms: i64) -> Result<DayPlan, String> {
+    if capacity_minutes <= 0 {
+        return Err("capacity_minutes must be positive".to_string());
+    }
+    let (tasks, config) = {
+        let conn = db::connect(&app)?;
+        (candidates(&conn, now_ms)?, ai::load_config(&conn))
+    };
+
+    let plan = match config {
+        Err(e) => local_plan(tasks, capacity_minutes, Some(reason(&e)), now_ms),
+        // Nothing to ask about
+        Ok(_) if tasks.is_empty() => local_plan(tasks, capacity_minutes, None, now_ms),
+        Ok(config) => {
+            let prompt = plan_prompt(&tasks, capacity_minutes, now_ms);
+            match ai::complete(&config, PLAN_SYSTEM_PROMPT, &prompt).await {
+                Err(e) => local_plan(tasks, capacity_minutes, Some(reason(&e)), now_ms),
+                Ok(reply) => match ai_order(&reply, &tasks) {
+                    None => {
+                        let reason = "The AI's answer couldn't be read".to_string();
+                        local_plan(tasks, capacity_minutes, Some(reason), now_ms)
+                    }
+                    Some((ordered, rest, rationale)) => {
+                        // The model's arithmetic isn't trusted, the capacity is enforced here
+                        let (planned, left_out, minutes) = fill(ordered, rest, capacity_minutes);
+                        DayPlan {
+                            tasks: planned,
+                            left_out,
+                            capacity_minutes,
+                            planned_minutes: minutes,
+                            rationale,
+                            source: PlanSource::Ai,
+                            fallback_reason: None,
+                            created_at: now_ms,
+                        }
+                    }
+                },
+            }
+        }
+    };
+    save(&*db::connect(&app)?, &plan)?;
+    Ok(plan)
+}
+
+#[tauri::command]
+pub async fn get_day_plan(app: AppHandle) -> Result<Option<DayPlan>, String> {
+    let conn = db::connect(&app)?;
+    last(&conn)
+}
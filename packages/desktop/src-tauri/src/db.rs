@@ -0,0 +1,64 @@
+use crate::{query_log, relocate};
+use r2d2_sqlite::SqliteConnectionManager;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+// The sql plugin resolves `sqlite:tada.db` against the app config directory, or opens the
+// absolute path of a relocated database (`relocate::plugin_url`), native commands open the
+// very same file.
+pub const DB_FILE: &str = "tada.db";
+
+// Enough for the reminder thread, which keeps one connection, plus a few commands at once
+const POOL_SIZE: u32 = 8;
+
+pub type PooledConnection = r2d2::PooledConnection<SqliteConnectionManager>;
+
+// Pool of native connections, kept in Tauri state next to the sql plugin's own pool
+pub struct Db(r2d2::Pool<SqliteConnectionManager>);
+
+pub fn db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(relocate::database_dir(&dir)?.join(DB_FILE))
+}
+
+// Two pools share tada.db: the sql plugin's (used by the frontend) and this one. Every
+// native connection is set up with
+// - journal_mode = WAL: readers no longer block the writer and vice versa. The mode is
+//   stored in the database file, so the plugin's connections switch to WAL as well.
+// - busy_timeout = 5000: there is still only one writer at a time, a connection that
+//   finds the write lock taken waits up to 5s instead of failing with `database is locked`.
+// - synchronous = NORMAL: the usual pairing with WAL, a commit may be lost on power loss
+//   but the database can't be corrupted.
+// - foreign_keys = ON: SQLite doesn't enforce them unless asked, per connection.
+// Keep write transactions short, a long one holds up the frontend's writes.
+// With query logging on (see `query_log::wanted`) each connection also times its statements.
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let path = db_path(app)?;
+    if query_log::wanted(&path) {
+        query_log::enable(query_log::log_path(app)?);
+    }
+    let manager = SqliteConnectionManager::file(path).with_init(|conn| {
+        query_log::install(conn);
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA busy_timeout = 5000;
+             PRAGMA synchronous = NORMAL;
+             PRAGMA foreign_keys = ON;",
+        )
+    });
+    let pool = r2d2::Pool::builder()
+        .max_size(POOL_SIZE)
+        .min_idle(Some(1))
+        .build(manager)
+        .map_err(|e| format!("Failed to open the database: {}", e))?;
+    app.manage(Db(pool));
+    Ok(())
+}
+
+// Borrow a connection from the pool, it goes back when dropped
+pub fn connect(app: &AppHandle) -> Result<PooledConnection, String> {
+    let db = app
+        .try_state::<Db>()
+        .ok_or_else(|| "Database is not initialized".to_string())?;
+    db.0.get().map_err(|e| e.to_string())
+}
@@ -0,0 +1,343 @@
+use crate::reminders::ReminderScheduler;
+use crate::tasks::{self, Task};
+use crate::{dates, db, journal, tray};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateTask {
+    pub id: String,
+    pub title: String,
+    pub due_date: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub list_id: Option<String>,
+    // Oldest task first, it's the natural one to keep
+    pub tasks: Vec<DuplicateTask>,
+}
+
+// Case and runs of whitespace don't make two titles different
+pub fn normalize_title(title: &str) -> String {
+    title.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+// 1.0 for equal strings, down to 0.0 for strings that share nothing
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+// Open live tasks whose normalized titles match within the same list. Exact matches are
+// bucketed by a hash map in one pass; with a threshold below 1.0 the distinct titles of a
+// list are also compared pairwise and joined when their similarity reaches it. Instances of
+// a recurring series share their title on purpose and are left out.
+pub fn find(conn: &Connection, list_id: Option<&str>, threshold: f64) -> Result<Vec<DuplicateGroup>, String> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("Threshold must be between 0 and 1, got {}", threshold));
+    }
+    let candidates: Vec<(Option<String>, DuplicateTask)> = {
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT list_id, id, title, due_date, created_at FROM tasks
+                WHERE completed = 0 AND deleted_at IS NULL AND list_name != 'Trash'
+                  AND recurrence IS NULL AND recurrence_parent_id IS NULL
+                  AND (?1 IS NULL OR list_id = ?1)
+                ORDER BY created_at, id
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([list_id], |row| {
+                Ok((
+                    row.get(0)?,
+                    DuplicateTask {
+                        id: row.get(1)?,
+                        title: row.get(2)?,
+                        due_date: row.get(3)?,
+                        created_at: row.get(4)?,
+                    },
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    // Exact buckets per list, in the order their first task was created
+    let mut buckets: Vec<(Option<String>, String, Vec<DuplicateTask>)> = Vec::new();
+    let mut index: HashMap<(Option<String>, String), usize> = HashMap::new();
+    for (list, task) in candidates {
+        let key = (list, normalize_title(&task.title));
+        if key.1.is_empty() {
+            continue;
+        }
+        match index.get(&key) {
+            Some(&i) => buckets[i].2.push(task),
+            None => {
+                index.insert(key.clone(), buckets.len());
+                buckets.push((key.0, key.1, vec![task]));
+            }
+        }
+    }
+
+    let mut parents: Vec<usize> = (0..buckets.len()).collect();
+    if threshold < 1.0 {
+        let titles: Vec<Vec<char>> = buckets.iter().map(|(_, title, _)| title.chars().collect()).collect();
+        for i in 0..buckets.len() {
+            for j in (i + 1)..buckets.len() {
+                if buckets[i].0 != buckets[j].0 {
+                    continue;
+                }
+                let (a, b) = (&titles[i], &titles[j]);
+                // The length difference alone is a lower bound on the distance
+                let longest = a.len().max(b.len()) as f64;
+                if 1.0 - a.len().abs_diff(b.len()) as f64 / longest < threshold {
+                    continue;
+                }
+                if similarity(a, b) >= threshold {
+                    let (ri, rj) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                    if ri != rj {
+                        parents[rj.max(ri)] = ri.min(rj);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    for (i, bucket) in buckets.iter_mut().enumerate() {
+        let root = find_root(&mut parents, i);
+        let tasks = std::mem::take(&mut bucket.2);
+        match group_of_root.get(&root) {
+            Some(&g) => groups[g].tasks.extend(tasks),
+            None => {
+                group_of_root.insert(root, groups.len());
+                groups.push(DuplicateGroup { list_id: bucket.0.clone(), tasks });
+            }
+        }
+    }
+    groups.retain(|group| group.tasks.len() > 1);
+    for group in &mut groups {
+        group.tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+    }
+    Ok(groups)
+}
+
+fn live_task(conn: &Connection, id: &str) -> Result<Task, String> {
+    let deleted_at: Option<Option<i64>> = conn
+        .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match deleted_at {
+        None => return Err(format!("Task {} not found", id)),
+        Some(Some(_)) => return Err(format!("Task {} is in the trash", id)),
+        Some(None) => {}
+    }
+    tasks::get(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))
+}
+
+fn next_subtask_order(conn: &Connection, task_id: &str) -> rusqlite::Result<i64> {
+    conn.query_row(
+        r#"SELECT COALESCE(MAX("order"), 0) + 1 FROM subtasks WHERE parent_id = ?1"#,
+        [task_id],
+        |row| row.get(0),
+    )
+}
+
+// Hand `id`'s live subtasks (appended from `order` on), attachments, focus sessions and
+// comments to `keep_id` and move `id` to the trash
+fn absorb(conn: &Connection, keep_id: &str, id: &str, order: &mut i64, now: i64) -> rusqlite::Result<()> {
+    let children: Vec<String> = {
+        let mut stmt = conn.prepare(r#"SELECT id FROM subtasks WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY "order""#)?;
+        let rows = stmt.query_map([id], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    for child in &children {
+        conn.execute(
+            r#"UPDATE subtasks SET parent_id = ?1, "order" = ?2, updated_at = ?3 WHERE id = ?4"#,
+            params![keep_id, *order, now, child],
+        )?;
+        *order += 1;
+    }
+    conn.execute("UPDATE attachments SET task_id = ?1 WHERE task_id = ?2", params![keep_id, id])?;
+    conn.execute("UPDATE focus_sessions SET task_id = ?1 WHERE task_id = ?2", params![keep_id, id])?;
+    conn.execute("UPDATE task_comments SET task_id = ?1 WHERE task_id = ?2", params![keep_id, id])?;
+    conn.execute(
+        "UPDATE tasks SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2",
+        params![now, id],
+    )?;
+    Ok(())
+}
+
+// Move the live subtasks of `remove_ids` to the end of `keep_id`'s and send the removed
+// tasks to the trash. Attachments, focus sessions and comments move along, otherwise
+// purging the trash would take them with it; none of them is journaled, so they stay on the
+// kept task when the merge is undone.
+pub fn merge(conn: &mut Connection, keep_id: &str, remove_ids: &[String]) -> Result<Task, String> {
+    let mut remove_ids: Vec<&str> = remove_ids.iter().map(String::as_str).filter(|id| *id != keep_id).collect();
+    remove_ids.sort_unstable();
+    remove_ids.dedup();
+    if remove_ids.is_empty() {
+        return Err("No tasks to merge".to_string());
+    }
+
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "merge_duplicate_tasks").map_err(|e| e.to_string())?;
+    live_task(&tx, keep_id)?;
+    for id in &remove_ids {
+        live_task(&tx, id)?;
+    }
+
+    let mut order = next_subtask_order(&tx, keep_id).map_err(|e| e.to_string())?;
+    for id in &remove_ids {
+        absorb(&tx, keep_id, id, &mut order, now).map_err(|e| e.to_string())?;
+    }
+
+    let kept = live_task(&tx, keep_id)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(kept)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    // Notes of both tasks one after the other, the earlier of the two due dates
+    Combine,
+    // The primary's notes and due date
+    KeepPrimary,
+}
+
+// Fold `secondary_id` into `primary_id`: tags are joined, notes and due date follow the
+// strategy, the subtasks move over like in `merge` and the secondary goes to the trash.
+// Everything else (title, list, priority, completion) is the primary's.
+pub fn merge_pair(
+    conn: &mut Connection,
+    primary_id: &str,
+    secondary_id: &str,
+    strategy: MergeStrategy,
+) -> Result<Task, String> {
+    if primary_id == secondary_id {
+        return Err("A task can't be merged into itself".to_string());
+    }
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "merge_tasks").map_err(|e| e.to_string())?;
+    let primary = live_task(&tx, primary_id)?;
+    let secondary = live_task(&tx, secondary_id)?;
+
+    let tags = tasks::normalize_tags(primary.tags.iter().chain(&secondary.tags).cloned().collect());
+    let tags = if tags.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&tags).map_err(|e| e.to_string())?)
+    };
+    let (content, due_date) = match strategy {
+        MergeStrategy::KeepPrimary => (primary.content.clone(), primary.due_date),
+        MergeStrategy::Combine => {
+            let notes: Vec<&str> = [&primary.content, &secondary.content]
+                .into_iter()
+                .filter_map(|c| c.as_deref().map(str::trim).filter(|c| !c.is_empty()))
+                .collect();
+            let content = (!notes.is_empty()).then(|| notes.join("\n\n"));
+            let due_date = [primary.due_date, secondary.due_date].into_iter().flatten().min();
+            (content, due_date)
+        }
+    };
+    tx.execute(
+        "UPDATE tasks SET content = ?1, tags = ?2, due_date = ?3, group_category = ?4, updated_at = ?5 WHERE id = ?6",
+        params![
+            content,
+            tags,
+            due_date,
+            dates::group_category(due_date, primary.completed, now),
+            now,
+            primary_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut order = next_subtask_order(&tx, primary_id).map_err(|e| e.to_string())?;
+    absorb(&tx, primary_id, secondary_id, &mut order, now).map_err(|e| e.to_string())?;
+
+    let merged = live_task(&tx, primary_id)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(merged)
+}
+
+#[tauri::command]
+pub async fn find_duplicate_tasks(
+    app: AppHandle,
+    list_id: Option<String>,
+    threshold: f64,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let conn = db::connect(&app)?;
+    find(&conn, list_id.as_deref(), threshold)
+}
+
+#[tauri::command]
+pub async fn merge_duplicate_tasks(app: AppHandle, keep_id: String, remove_ids: Vec<String>) -> Result<Task, String> {
+    let kept = {
+        let mut conn = db::connect(&app)?;
+        merge(&mut conn, &keep_id, &remove_ids)?
+    };
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(kept)
+}
+
+#[tauri::command]
+pub async fn merge_tasks(
+    app: AppHandle,
+    primary_id: String,
+    secondary_id: String,
+    strategy: MergeStrategy,
+) -> Result<Task, String> {
+    let merged = {
+        let mut conn = db::connect(&app)?;
+        merge_pair(&mut conn, &primary_id, &secondary_id, strategy)?
+    };
+    app.state::<ReminderScheduler>().wake();
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(merged)
+}
@@ -0,0 +1,127 @@
+use crate::{db, tasks};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+pub const NAVIGATE_TASK_EVENT: &str = "navigate-task";
+pub const NAVIGATE_LIST_EVENT: &str = "navigate-list";
+pub const DEEP_LINK_ERROR_EVENT: &str = "deep-link-error";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Navigation {
+    pub task_id: Option<String>,
+    pub list_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeepLinkError {
+    url: String,
+    message: String,
+}
+
+// A link the app was launched with arrives before the frontend listens for events, so it
+// is parked here until the frontend asks for it
+#[derive(Default)]
+pub struct PendingNavigation(Mutex<Option<Navigation>>);
+
+// tada://task/<id> and tada://list/<id>. The id is checked against live rows so a stale
+// link reports an error instead of opening an empty view.
+pub fn resolve(app: &AppHandle, url: &Url) -> Result<Navigation, String> {
+    if url.scheme() != "tada" {
+        return Err(format!("Unsupported link '{}'", url));
+    }
+    let id = url
+        .path_segments()
+        .and_then(|mut segments| segments.next())
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| format!("Link '{}' has no id", url))?;
+
+    let conn = db::connect(app)?;
+    match url.host_str() {
+        Some("task") => {
+            let task = tasks::get(&conn, id)
+                .map_err(|e| e.to_string())?
+                .filter(|_| is_live_task(&conn, id))
+                .ok_or_else(|| format!("Task {} not found", id))?;
+            Ok(Navigation {
+                list_id: task.list_id.clone().unwrap_or_default(),
+                task_id: Some(task.id),
+            })
+        }
+        Some("list") => {
+            tasks::list_name(&conn, id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("List {} not found", id))?;
+            Ok(Navigation {
+                task_id: None,
+                list_id: id.to_string(),
+            })
+        }
+        _ => Err(format!("Unsupported link '{}'", url)),
+    }
+}
+
+fn is_live_task(conn: &rusqlite::Connection, id: &str) -> bool {
+    conn.query_row("SELECT deleted_at IS NULL FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+        .unwrap_or(false)
+}
+
+pub fn handle(app: &AppHandle, url: &Url, at_launch: bool) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+
+    match resolve(app, url) {
+        Ok(navigation) if at_launch => {
+            *app.state::<PendingNavigation>().0.lock().unwrap_or_else(|e| e.into_inner()) = Some(navigation);
+        }
+        Ok(navigation) => {
+            let event = if navigation.task_id.is_some() { NAVIGATE_TASK_EVENT } else { NAVIGATE_LIST_EVENT };
+            if let Err(e) = app.emit(event, navigation) {
+                eprintln!("Failed to emit {}: {}", event, e);
+            }
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            let _ = app.emit(DEEP_LINK_ERROR_EVENT, DeepLinkError { url: url.to_string(), message });
+        }
+    }
+}
+
+// Links opened while the app runs come through `on_open_url`; with the single-instance
+// plugin that includes links that started a second instance, which exits right away.
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Linux and Windows only know the scheme once it is registered at runtime
+    #[cfg(any(windows, target_os = "linux"))]
+    app.deep_link().register_all().map_err(|e| e.to_string())?;
+
+    let app_handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle(&app_handle, &url, false);
+        }
+    });
+
+    if let Some(urls) = app.deep_link().get_current().map_err(|e| e.to_string())? {
+        for url in urls {
+            handle(app, &url, true);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn take_pending_navigation(app: AppHandle) -> Result<Option<Navigation>, String> {
+    Ok(app
+        .state::<PendingNavigation>()
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take())
+}
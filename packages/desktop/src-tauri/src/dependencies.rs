@@ -0,0 +1,161 @@
+use crate::change_feed::ChangeEvent;
+use crate::tasks::{Task, TASK_COLUMNS};
+use crate::{dates, db};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter};
+
+pub const UNBLOCKED_EVENT: &str = "unblocked";
+// How long before its outbox row a completion may have been stamped: the frontend sets
+// `completed_at` itself before the write reaches the database
+const COMPLETION_SLACK_MS: i64 = 5_000;
+
+// A dependency only blocks while it is open: completed, trashed or deleted tasks don't
+const OPEN_DEPENDENCY: &str = "dep.completed = 0 AND dep.deleted_at IS NULL AND dep.list_name != 'Trash'";
+
+fn live_task(conn: &Connection, id: &str) -> Result<(), String> {
+    let found = conn
+        .query_row("SELECT 1 FROM tasks WHERE id = ?1 AND deleted_at IS NULL", [id], |_| Ok(()))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    found.ok_or_else(|| format!("Task {} not found", id))
+}
+
+// Whether `from` already depends on `to`, directly or through other tasks
+fn reaches(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "WITH RECURSIVE reach(id) AS (
+             SELECT ?1
+             UNION
+             SELECT d.depends_on_id FROM task_dependencies d JOIN reach r ON d.task_id = r.id
+         )
+         SELECT EXISTS (SELECT 1 FROM reach WHERE id = ?2)",
+        params![from, to],
+        |row| row.get(0),
+    )
+}
+
+// Refuses a dependency that would close a cycle, the tasks in it could never be started
+pub fn add(conn: &Connection, task_id: &str, depends_on_id: &str) -> Result<(), String> {
+    if task_id == depends_on_id {
+        return Err("A task can't depend on itself".to_string());
+    }
+    live_task(conn, task_id)?;
+    live_task(conn, depends_on_id)?;
+    if reaches(conn, depends_on_id, task_id).map_err(|e| e.to_string())? {
+        return Err(format!(
+            "Task {} already depends on {}, this would create a cycle",
+            depends_on_id, task_id
+        ));
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id, created_at) VALUES (?1, ?2, ?3)",
+        params![task_id, depends_on_id, dates::now_ms()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Returns whether there was such a dependency
+pub fn remove(conn: &Connection, task_id: &str, depends_on_id: &str) -> rusqlite::Result<bool> {
+    conn.execute(
+        "DELETE FROM task_dependencies WHERE task_id = ?1 AND depends_on_id = ?2",
+        params![task_id, depends_on_id],
+    )
+    .map(|removed| removed > 0)
+}
+
+// Incomplete tasks waiting on at least one open dependency
+pub fn blocked(conn: &Connection) -> rusqlite::Result<Vec<Task>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT {} FROM tasks t
+           WHERE t.completed = 0 AND t.deleted_at IS NULL AND t.list_name != 'Trash'
+             AND EXISTS (SELECT 1 FROM task_dependencies d JOIN tasks dep ON dep.id = d.depends_on_id
+                         WHERE d.task_id = t.id AND {})
+           ORDER BY t.due_date IS NULL, t.due_date, t."order""#,
+        TASK_COLUMNS, OPEN_DEPENDENCY
+    ))?;
+    let rows = stmt.query_map([], Task::from_row)?;
+    rows.collect()
+}
+
+// Incomplete tasks depending on `completed_id` that have no open dependency left, if it was
+// completed in [since, until]. A task that was already done before isn't unblocking
+// anything new.
+pub fn unblocked_by(conn: &Connection, completed_id: &str, since: i64, until: i64) -> rusqlite::Result<Vec<String>> {
+    let done: bool = conn
+        .query_row(
+            "SELECT completed = 1 AND completed_at BETWEEN ?2 AND ?3 FROM tasks WHERE id = ?1",
+            params![completed_id, since, until],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(false);
+    if !done {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.id FROM task_dependencies d0 JOIN tasks t ON t.id = d0.task_id
+         WHERE d0.depends_on_id = ?1 AND t.completed = 0 AND t.deleted_at IS NULL
+           AND NOT EXISTS (SELECT 1 FROM task_dependencies d JOIN tasks dep ON dep.id = d.depends_on_id
+                           WHERE d.task_id = t.id AND {})",
+        OPEN_DEPENDENCY
+    ))?;
+    let rows = stmt.query_map([completed_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+fn is_installed(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'task_dependencies')",
+        [],
+        |row| row.get(0),
+    )
+}
+
+// Called by the change feed with every batch of changes, so completions made by the
+// frontend through the sql plugin are seen as well. Emits `unblocked` with the ids of the
+// tasks whose last open dependency was completed by one of the updates, later edits of a
+// done task don't count.
+pub fn emit_unblocked(app: &AppHandle, conn: &Connection, events: &[ChangeEvent]) -> rusqlite::Result<()> {
+    // Task id -> first and last of its updates in the batch
+    let mut updated: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+    for event in events.iter().filter(|event| event.table == "tasks" && event.op == "update") {
+        let window = updated.entry(event.id.as_str()).or_insert((event.updated_at, event.updated_at));
+        window.0 = window.0.min(event.updated_at);
+        window.1 = window.1.max(event.updated_at);
+    }
+    if updated.is_empty() || !is_installed(conn)? {
+        return Ok(());
+    }
+    let mut unblocked = Vec::new();
+    for (id, (first, last)) in updated {
+        for task_id in unblocked_by(conn, id, first - COMPLETION_SLACK_MS, last)? {
+            if !unblocked.contains(&task_id) {
+                unblocked.push(task_id);
+            }
+        }
+    }
+    if !unblocked.is_empty() {
+        let _ = app.emit(UNBLOCKED_EVENT, &unblocked);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_dependency(app: AppHandle, task_id: String, depends_on_id: String) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    add(&conn, &task_id, &depends_on_id)
+}
+
+#[tauri::command]
+pub async fn remove_dependency(app: AppHandle, task_id: String, depends_on_id: String) -> Result<bool, String> {
+    let conn = db::connect(&app)?;
+    remove(&conn, &task_id, &depends_on_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_blocked_tasks(app: AppHandle) -> Result<Vec<Task>, String> {
+    let conn = db::connect(&app)?;
+    blocked(&conn).map_err(|e| e.to_string())
+}
@@ -0,0 +1,62 @@
+use crate::db;
+use crate::tasks::{self, Subtask, Task, SUBTASK_COLUMNS};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDetail {
+    pub task: Task,
+    // Live subtasks by "order"
+    pub subtasks: Vec<Subtask>,
+    // Completed subtasks / all subtasks, `None` without subtasks
+    pub completion: Option<f64>,
+    // `completion` as a whole percentage, to compare with `task.complete_percentage`
+    pub subtask_percentage: Option<i64>,
+    // The stored `complete_percentage` is set and differs from `subtask_percentage`
+    pub percentage_mismatch: bool,
+}
+
+pub fn detail(conn: &Connection, id: &str) -> rusqlite::Result<Option<TaskDetail>> {
+    let deleted_at: Option<Option<i64>> = conn
+        .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+        .optional()?;
+    if !matches!(deleted_at, Some(None)) {
+        return Ok(None);
+    }
+    let Some(task) = tasks::get(conn, id)? else {
+        return Ok(None);
+    };
+
+    let subtasks: Vec<Subtask> = {
+        let mut stmt = conn.prepare(&format!(
+            r#"SELECT {} FROM subtasks WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY "order""#,
+            SUBTASK_COLUMNS
+        ))?;
+        let rows = stmt.query_map([id], Subtask::from_row)?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let done = subtasks.iter().filter(|s| s.completed).count();
+    let completion = (!subtasks.is_empty()).then(|| done as f64 / subtasks.len() as f64);
+    let subtask_percentage = completion.map(|c| (c * 100.0).round() as i64);
+    let percentage_mismatch = match (task.complete_percentage, subtask_percentage) {
+        (Some(stored), Some(derived)) => stored != derived,
+        _ => false,
+    };
+    Ok(Some(TaskDetail {
+        task,
+        subtasks,
+        completion,
+        subtask_percentage,
+        percentage_mismatch,
+    }))
+}
+
+// A missing or deleted task is `None`, not an error
+#[tauri::command]
+pub async fn get_task_detail(app: AppHandle, id: String) -> Result<Option<TaskDetail>, String> {
+    let conn = db::connect(&app)?;
+    detail(&conn, &id).map_err(|e| e.to_string())
+}
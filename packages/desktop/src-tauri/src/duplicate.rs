@@ -0,0 +1,78 @@
+use crate::tasks::{self, Task};
+use crate::{dates, db, journal};
+use rusqlite::{params, Connection};
+use tauri::AppHandle;
+use uuid::Uuid;
+
+const COPY_SUFFIX: &str = " (copy)";
+
+// Copy a task and its live subtasks to the end of the same list, returns the copy. The
+// copy starts out open, and so do its subtasks. The reminder offset comes along, but not
+// the recurrence: a second series of the same rule would generate every instance twice.
+pub fn duplicate(conn: &mut Connection, id: &str) -> Result<Task, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "duplicate_task").map_err(|e| e.to_string())?;
+    let original = tasks::get(&tx, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))?;
+    let deleted_at: Option<i64> = tx
+        .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if deleted_at.is_some() || original.list_name == "Trash" {
+        return Err(format!("Task {} is in the trash", id));
+    }
+
+    let copy_id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+    let order = tasks::next_order(&tx, original.list_id.as_deref()).map_err(|e| e.to_string())?;
+    tx.execute(
+        r#"
+        INSERT INTO tasks (id, title, completed, due_date, list_id, list_name, content, "order",
+                           created_at, updated_at, tags, priority, group_category, reminder_offset_minutes)
+        SELECT ?1, title || ?2, 0, due_date, list_id, list_name, content, ?3,
+               ?4, ?4, tags, priority, ?5, reminder_offset_minutes
+        FROM tasks WHERE id = ?6
+        "#,
+        params![
+            copy_id,
+            COPY_SUFFIX,
+            order,
+            now,
+            dates::group_category(original.due_date, false, now),
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let subtask_ids: Vec<String> = {
+        let mut stmt = tx
+            .prepare(r#"SELECT id FROM subtasks WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY "order""#)
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([id], |row| row.get(0)).map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    for subtask_id in &subtask_ids {
+        tx.execute(
+            r#"
+            INSERT INTO subtasks (id, parent_id, title, completed, due_date, "order", created_at, updated_at)
+            SELECT ?1, ?2, title, 0, due_date, "order", ?3, ?3
+            FROM subtasks WHERE id = ?4
+            "#,
+            params![Uuid::new_v4().to_string(), copy_id, now, subtask_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let copy = tasks::get(&tx, &copy_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", copy_id))?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(copy)
+}
+
+#[tauri::command]
+pub async fn duplicate_task(app: AppHandle, id: String) -> Result<Task, String> {
+    let mut conn = db::connect(&app)?;
+    duplicate(&mut conn, &id)
+}
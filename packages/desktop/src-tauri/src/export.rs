@@ -0,0 +1,336 @@
+use crate::{dates, db, journal};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection, OptionalExtension, Params};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+// Bump when the document layout changes in a way older importers can't read
+pub const SCHEMA_VERSION: i64 = 1;
+
+pub const INBOX_LIST_ID: &str = "inbox-default";
+
+pub type Row = Map<String, Value>;
+
+// Rows are exported column-for-column with their SQL column names, so columns added by
+// later migrations travel along without touching this module.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportDocument {
+    pub schema: i64,
+    #[serde(default)]
+    pub app_version: String,
+    #[serde(default)]
+    pub exported_at: i64,
+    #[serde(default)]
+    pub lists: Vec<Row>,
+    #[serde(default)]
+    pub tasks: Vec<Row>,
+    #[serde(default)]
+    pub subtasks: Vec<Row>,
+    #[serde(default)]
+    pub summaries: Vec<Row>,
+    #[serde(default)]
+    pub echo_reports: Vec<Row>,
+    #[serde(default)]
+    pub settings: Vec<Row>,
+    #[serde(default)]
+    pub archived_tasks: Vec<Row>,
+}
+
+impl ExportDocument {
+    // Tables in dependency order, parents before children
+    pub fn tables(&self) -> [(&'static str, &Vec<Row>); 7] {
+        [
+            ("lists", &self.lists),
+            ("tasks", &self.tasks),
+            ("subtasks", &self.subtasks),
+            ("summaries", &self.summaries),
+            ("echo_reports", &self.echo_reports),
+            ("settings", &self.settings),
+            ("archived_tasks", &self.archived_tasks),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    Replace,
+    Merge,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportStats {
+    pub inserted: usize,
+    pub updated: usize,
+    // Rows kept as they were because the local copy is at least as new
+    pub unchanged: usize,
+    // Tasks whose list was missing and were moved to the inbox
+    pub reassigned: usize,
+    // Subtasks dropped because their parent task is missing
+    pub orphaned: usize,
+}
+
+pub(crate) fn key_column(table: &str) -> &'static str {
+    if table == "settings" {
+        "key"
+    } else {
+        "id"
+    }
+}
+
+pub fn table_columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info(\"{}\")", table))?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    rows.collect()
+}
+
+fn sql_to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::Number(i.into()),
+        ValueRef::Real(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        ValueRef::Text(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(blob) => Value::String(STANDARD.encode(blob)),
+    }
+}
+
+pub(crate) fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => n
+            .as_i64()
+            .map(SqlValue::Integer)
+            .or_else(|| n.as_f64().map(SqlValue::Real))
+            .unwrap_or(SqlValue::Null),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        // JSON columns (tags, task_ids, ...) are stored as text
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+pub fn dump_table(conn: &Connection, table: &str) -> rusqlite::Result<Vec<Row>> {
+    dump_rows(conn, &format!("SELECT * FROM \"{}\"", table), [])
+}
+
+// Rows of any query, keyed by column name
+pub(crate) fn dump_rows(conn: &Connection, sql: &str, params: impl Params) -> rusqlite::Result<Vec<Row>> {
+    let mut stmt = conn.prepare(sql)?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let rows = stmt.query_map(params, |row| {
+        let mut map = Map::new();
+        for (i, name) in names.iter().enumerate() {
+            map.insert(name.clone(), sql_to_json(row.get_ref(i)?));
+        }
+        Ok(map)
+    })?;
+    rows.collect()
+}
+
+pub fn collect_document(conn: &Connection, app_version: String) -> Result<ExportDocument, String> {
+    let dump = |table: &str| dump_table(conn, table).map_err(|e| e.to_string());
+    Ok(ExportDocument {
+        schema: SCHEMA_VERSION,
+        app_version,
+        exported_at: dates::now_ms(),
+        lists: dump("lists")?,
+        tasks: dump("tasks")?,
+        subtasks: dump("subtasks")?,
+        summaries: dump("summaries")?,
+        echo_reports: dump("echo_reports")?,
+        settings: dump("settings")?,
+        archived_tasks: dump("archived_tasks")?,
+    })
+}
+
+pub fn read_document(path: &Path) -> Result<ExportDocument, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    parse_document(&raw)
+}
+
+pub fn parse_document(raw: &str) -> Result<ExportDocument, String> {
+    let document: ExportDocument = serde_json::from_str(raw).map_err(|e| format!("Invalid export file: {}", e))?;
+    if document.schema > SCHEMA_VERSION {
+        return Err(format!(
+            "Export file uses format {} but this version of Tada only reads up to {}",
+            document.schema, SCHEMA_VERSION
+        ));
+    }
+    Ok(document)
+}
+
+pub fn write_file_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    let staging = path.with_extension(format!("tmp-{}", dates::now_ms()));
+    fs::write(&staging, contents).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    fs::rename(&staging, path).map_err(|e| {
+        let _ = fs::remove_file(&staging);
+        format!("Failed to write '{}': {}", path.display(), e)
+    })
+}
+
+pub(crate) fn ensure_inbox(conn: &Connection) -> rusqlite::Result<String> {
+    conn.execute(
+        r#"INSERT OR IGNORE INTO lists (id, name, icon, "order") VALUES (?1, 'Inbox', 'inbox', 1)"#,
+        [INBOX_LIST_ID],
+    )?;
+    conn.query_row("SELECT name FROM lists WHERE id = ?1", [INBOX_LIST_ID], |row| row.get(0))
+}
+
+// Insert a row, keeping only the keys that are columns of `table`
+pub(crate) fn insert_row(conn: &Connection, table: &str, columns: &[String], row: &Row) -> rusqlite::Result<usize> {
+    let used: Vec<&String> = columns.iter().filter(|c| row.contains_key(c.as_str())).collect();
+    let column_list = used.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ");
+    let placeholders = (1..=used.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ");
+    let values = used.iter().map(|c| json_to_sql(&row[c.as_str()]));
+    conn.execute(
+        &format!("INSERT INTO \"{}\" ({}) VALUES ({})", table, column_list, placeholders),
+        params_from_iter(values),
+    )
+}
+
+// Point tasks at an existing list (falling back to the inbox) and drop subtasks whose
+// parent is gone. Returns false when the row must be skipped.
+fn fix_references(conn: &Connection, table: &str, row: &mut Row, stats: &mut ImportStats) -> Result<bool, String> {
+    match table {
+        "tasks" => {
+            let Some(list_id) = row.get("list_id").and_then(Value::as_str) else {
+                return Ok(true);
+            };
+            let exists: Option<i64> = conn
+                .query_row("SELECT 1 FROM lists WHERE id = ?1", [list_id], |r| r.get(0))
+                .optional()
+                .map_err(|e| e.to_string())?;
+            if exists.is_none() {
+                let inbox_name = ensure_inbox(conn).map_err(|e| e.to_string())?;
+                row.insert("list_id".into(), Value::String(INBOX_LIST_ID.into()));
+                row.insert("list_name".into(), Value::String(inbox_name));
+                stats.reassigned += 1;
+            }
+            Ok(true)
+        }
+        "subtasks" => {
+            let parent_id = row.get("parent_id").and_then(Value::as_str).unwrap_or_default();
+            let exists: Option<i64> = conn
+                .query_row("SELECT 1 FROM tasks WHERE id = ?1", [parent_id], |r| r.get(0))
+                .optional()
+                .map_err(|e| e.to_string())?;
+            if exists.is_none() {
+                stats.orphaned += 1;
+                return Ok(false);
+            }
+            Ok(true)
+        }
+        _ => Ok(true),
+    }
+}
+
+// Apply an export document inside the caller's transaction. In merge mode rows are
+// reconciled by primary key and the copy with the newer `updated_at` wins; tables without
+// `updated_at` only gain rows that are missing locally.
+pub fn apply_document(conn: &Connection, document: &ExportDocument, mode: ImportMode) -> Result<ImportStats, String> {
+    let mut stats = ImportStats::default();
+
+    if mode == ImportMode::Replace {
+        for (table, _) in document.tables().iter().rev() {
+            conn.execute(&format!("DELETE FROM \"{}\"", table), [])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    for (table, rows) in document.tables() {
+        let columns = table_columns(conn, table).map_err(|e| e.to_string())?;
+        let key = key_column(table);
+        let has_updated_at = columns.iter().any(|c| c == "updated_at");
+
+        for original in rows {
+            let Some(id) = original.get(key).and_then(Value::as_str) else {
+                return Err(format!("A row in '{}' has no '{}'", table, key));
+            };
+            let mut row = original.clone();
+            if !fix_references(conn, table, &mut row, &mut stats)? {
+                continue;
+            }
+
+            let existing: Option<Option<i64>> = conn
+                .query_row(
+                    &format!(
+                        "SELECT {} FROM \"{}\" WHERE \"{}\" = ?1",
+                        if has_updated_at { "updated_at" } else { "NULL" },
+                        table,
+                        key
+                    ),
+                    [id],
+                    |r| r.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+
+            let is_update = match existing {
+                None => false,
+                Some(local_updated_at) => {
+                    let incoming = row.get("updated_at").and_then(Value::as_i64);
+                    match (incoming, local_updated_at) {
+                        (Some(incoming), Some(local)) if incoming > local => true,
+                        _ => {
+                            stats.unchanged += 1;
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let used: Vec<&String> = columns.iter().filter(|c| row.contains_key(c.as_str())).collect();
+            let column_list = used.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ");
+            let placeholders = (1..=used.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ");
+            let mut sql = format!("INSERT INTO \"{}\" ({}) VALUES ({})", table, column_list, placeholders);
+            if is_update {
+                let assignments = used
+                    .iter()
+                    .map(|c| format!("\"{0}\" = excluded.\"{0}\"", c))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(&format!(" ON CONFLICT(\"{}\") DO UPDATE SET {}", key, assignments));
+            }
+
+            let values = used.iter().map(|c| json_to_sql(&row[c.as_str()]));
+            conn.execute(&sql, params_from_iter(values))
+                .map_err(|e| format!("Failed to import a row into '{}': {}", table, e))?;
+
+            if is_update {
+                stats.updated += 1;
+            } else {
+                stats.inserted += 1;
+            }
+        }
+    }
+
+    ensure_inbox(conn).map_err(|e| e.to_string())?;
+    Ok(stats)
+}
+
+#[tauri::command]
+pub async fn export_all(app: AppHandle, path: String) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    let document = collect_document(&conn, app.package_info().version.to_string())?;
+    let json = serde_json::to_string_pretty(&document).map_err(|e| e.to_string())?;
+    write_file_atomic(Path::new(&path), &json)
+}
+
+// The whole import runs in one transaction, a malformed file leaves the database untouched
+#[tauri::command]
+pub async fn import_all(app: AppHandle, path: String, mode: ImportMode) -> Result<ImportStats, String> {
+    let document = read_document(Path::new(&path))?;
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_all").map_err(|e| e.to_string())?;
+    let stats = apply_document(&tx, &document, mode)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(stats)
+}
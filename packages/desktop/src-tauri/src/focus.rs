@@ -0,0 +1,94 @@
+use crate::tasks::{Task, TASK_COLUMNS};
+use crate::{dates, db, lists, settings};
+use rusqlite::Connection;
+use serde_json::Value;
+use std::cmp::Ordering;
+use tauri::AppHandle;
+
+const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+// Past this many days late a task doesn't become any more urgent
+const MAX_OVERDUE_DAYS: f64 = 14.0;
+
+// `preferences.focusWeights`, any weight left out keeps its default
+#[derive(Debug, Clone, Copy)]
+pub struct FocusWeights {
+    pub overdue: f64,
+    pub priority: f64,
+    pub due_soon: f64,
+}
+
+impl Default for FocusWeights {
+    fn default() -> Self {
+        Self {
+            overdue: 10.0,
+            priority: 5.0,
+            due_soon: 8.0,
+        }
+    }
+}
+
+impl FocusWeights {
+    pub fn load(conn: &Connection) -> Self {
+        let mut weights = Self::default();
+        let configured = settings::read(conn, "preferences")
+            .ok()
+            .flatten()
+            .and_then(|p| p.get("focusWeights").cloned());
+        if let Some(configured) = configured {
+            let weight = |key: &str| configured.get(key).and_then(Value::as_f64).filter(|w| *w >= 0.0);
+            weights.overdue = weight("overdue").unwrap_or(weights.overdue);
+            weights.priority = weight("priority").unwrap_or(weights.priority);
+            weights.due_soon = weight("dueSoon").unwrap_or(weights.due_soon);
+        }
+        weights
+    }
+}
+
+// Higher is more urgent. Overdue tasks gain one step per day late (capped), upcoming ones
+// fade with the days left (1 today, 1/2 tomorrow, ...), and priority 1-3 adds 3-1 steps.
+// A task without due date or priority scores 0.
+pub fn score(task: &Task, weights: &FocusWeights, now_ms: i64) -> f64 {
+    let priority = match task.priority {
+        Some(p @ 1..=3) => (4 - p) as f64,
+        _ => 0.0,
+    };
+    let due = match task.due_date {
+        Some(due) if due < now_ms => {
+            let late = ((now_ms - due) as f64 / DAY_MS).min(MAX_OVERDUE_DAYS);
+            weights.overdue * (1.0 + late)
+        }
+        Some(due) => weights.due_soon / (1.0 + (due - now_ms) as f64 / DAY_MS),
+        None => 0.0,
+    };
+    weights.priority * priority + due
+}
+
+// Tasks of hidden lists only with `include_hidden`
+pub fn focus_tasks(conn: &Connection, limit: i64, now_ms: i64, include_hidden: bool) -> rusqlite::Result<Vec<Task>> {
+    let weights = FocusWeights::load(conn);
+    let mut tasks: Vec<(f64, Task)> = {
+        let mut stmt = conn.prepare(&format!(
+            r#"SELECT {} FROM tasks WHERE completed = 0 AND deleted_at IS NULL AND list_name != 'Trash'{}
+               ORDER BY "order""#,
+            TASK_COLUMNS,
+            lists::hidden_filter(include_hidden)
+        ))?;
+        let rows = stmt.query_map([], Task::from_row)?;
+        rows.map(|task| task.map(|task| (score(&task, &weights, now_ms), task)))
+            .collect::<Result<_, _>>()?
+    };
+    // Equal scores keep the manual order
+    tasks.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    Ok(tasks
+        .into_iter()
+        .take(limit.max(0) as usize)
+        .map(|(_, task)| task)
+        .collect())
+}
+
+// Open tasks ranked by `score`, most urgent first
+#[tauri::command]
+pub async fn get_focus_tasks(app: AppHandle, limit: i64, include_hidden: Option<bool>) -> Result<Vec<Task>, String> {
+    let conn = db::connect(&app)?;
+    focus_tasks(&conn, limit, dates::now_ms(), include_hidden.unwrap_or(false)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,167 @@
+use crate::{dates, db};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+// How often a running session records that the app is still alive. A session left open by
+// a crash is closed at its last heartbeat, so at most this much focus time is lost.
+const HEARTBEAT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+    pub id: String,
+    pub task_id: String,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub duration_seconds: Option<i64>,
+}
+
+impl FocusSession {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+            duration_seconds: row.get("duration_seconds")?,
+        })
+    }
+}
+
+fn get(conn: &Connection, id: &str) -> rusqlite::Result<Option<FocusSession>> {
+    conn.query_row("SELECT * FROM focus_sessions WHERE id = ?1", [id], FocusSession::from_row)
+        .optional()
+}
+
+// Startup jobs may run before the frontend applied the migration creating the table
+fn is_installed(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'focus_sessions'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+// Only one session runs at a time, the partial unique index from migration 14 backs this up
+pub fn start(conn: &Connection, task_id: &str) -> Result<FocusSession, String> {
+    let live: Option<Option<i64>> = conn
+        .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", [task_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match live {
+        None => return Err(format!("Task {} not found", task_id)),
+        Some(Some(_)) => return Err(format!("Task {} is in the trash", task_id)),
+        Some(None) => {}
+    }
+    let running: Option<String> = conn
+        .query_row("SELECT task_id FROM focus_sessions WHERE ended_at IS NULL", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(running) = running {
+        return Err(format!("A focus session is already running for task {}", running));
+    }
+
+    let now = dates::now_ms();
+    let session = FocusSession {
+        id: Uuid::new_v4().to_string(),
+        task_id: task_id.to_string(),
+        started_at: now,
+        ended_at: None,
+        duration_seconds: None,
+    };
+    conn.execute(
+        "INSERT INTO focus_sessions (id, task_id, started_at, last_seen_at) VALUES (?1, ?2, ?3, ?3)",
+        params![session.id, session.task_id, session.started_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(session)
+}
+
+pub fn stop(conn: &Connection, id: &str) -> Result<FocusSession, String> {
+    let Some(session) = get(conn, id).map_err(|e| e.to_string())? else {
+        return Err(format!("Focus session {} not found", id));
+    };
+    if session.ended_at.is_some() {
+        return Err(format!("Focus session {} has already ended", id));
+    }
+    let now = dates::now_ms().max(session.started_at);
+    conn.execute(
+        "UPDATE focus_sessions SET ended_at = ?1, last_seen_at = ?1, duration_seconds = (?1 - started_at) / 1000
+         WHERE id = ?2",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    get(conn, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Focus session {} not found", id))
+}
+
+// Ended sessions plus however long a running one has gone so far
+pub fn total_seconds(conn: &Connection, task_id: &str, now_ms: i64) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(COALESCE(duration_seconds, MAX(?2 - started_at, 0) / 1000)), 0)
+         FROM focus_sessions WHERE task_id = ?1",
+        params![task_id, now_ms],
+        |row| row.get(0),
+    )
+}
+
+// Record that the running session (if any) is still going
+pub fn heartbeat(conn: &Connection, now_ms: i64) -> rusqlite::Result<usize> {
+    if !is_installed(conn)? {
+        return Ok(0);
+    }
+    conn.execute(
+        "UPDATE focus_sessions SET last_seen_at = ?1 WHERE ended_at IS NULL",
+        [now_ms],
+    )
+}
+
+// End sessions the app never got to stop (it quit or crashed while one was running) at the
+// last heartbeat they recorded. Runs on startup before any new session can begin.
+pub fn close_dangling(conn: &Connection) -> rusqlite::Result<usize> {
+    if !is_installed(conn)? {
+        return Ok(0);
+    }
+    conn.execute(
+        "UPDATE focus_sessions
+         SET ended_at = MAX(last_seen_at, started_at),
+             duration_seconds = (MAX(last_seen_at, started_at) - started_at) / 1000
+         WHERE ended_at IS NULL",
+        [],
+    )
+}
+
+pub fn start_heartbeat(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(HEARTBEAT);
+        if let Err(e) = db::connect(&app).and_then(|conn| {
+            heartbeat(&conn, dates::now_ms()).map_err(|e| e.to_string())
+        }) {
+            eprintln!("Failed to record focus session heartbeat: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn start_focus(app: AppHandle, task_id: String) -> Result<FocusSession, String> {
+    let conn = db::connect(&app)?;
+    start(&conn, &task_id)
+}
+
+#[tauri::command]
+pub async fn stop_focus(app: AppHandle, session_id: String) -> Result<FocusSession, String> {
+    let conn = db::connect(&app)?;
+    stop(&conn, &session_id)
+}
+
+#[tauri::command]
+pub async fn get_focus_time(app: AppHandle, task_id: String) -> Result<i64, String> {
+    let conn = db::connect(&app)?;
+    total_seconds(&conn, &task_id, dates::now_ms()).map_err(|e| e.to_string())
+}
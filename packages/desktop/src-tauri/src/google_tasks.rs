@@ -0,0 +1,261 @@
+use crate::tasks::{self, NewSubtask, NewTask};
+use crate::{dates, db, journal};
+use chrono::{DateTime, NaiveTime};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    // Lists created for task lists without a list of the same name
+    pub lists: usize,
+    pub tasks: usize,
+    pub subtasks: usize,
+    // Tasks imported without a due date because it couldn't be read
+    pub invalid_dates: usize,
+    // Tasks that were left out, with the reason
+    pub skipped: Vec<String>,
+}
+
+struct TaskList {
+    key: String,
+    title: String,
+}
+
+struct Item {
+    key: String,
+    list: String,
+    parent: Option<String>,
+    title: String,
+    notes: Option<String>,
+    due_date: Option<i64>,
+    completed_at: Option<i64>,
+    deleted: bool,
+    // Google's ordering key, zero-padded so it sorts as text
+    position: String,
+}
+
+#[derive(Default)]
+struct Export {
+    lists: Vec<TaskList>,
+    items: Vec<Item>,
+    invalid_dates: usize,
+}
+
+fn text(value: &Value, field: &str) -> Option<String> {
+    value.get(field).and_then(Value::as_str).map(str::trim).filter(|s| !s.is_empty()).map(String::from)
+}
+
+// Google Tasks keeps only the day of a due date and writes it as midnight UTC, which is
+// that day's local midnight here. Other timestamps are taken as they are.
+fn due_date(text: &str) -> Option<i64> {
+    match DateTime::parse_from_rfc3339(text) {
+        Ok(dt) if dt.offset().local_minus_utc() == 0 && dt.time() == NaiveTime::MIN => {
+            Some(dates::local_midnight(dt.date_naive()))
+        }
+        Ok(dt) => Some(dt.timestamp_millis()),
+        Err(_) => dates::parse_due_date(text),
+    }
+}
+
+fn read_items(export: &mut Export, list: &str, items: &[Value]) {
+    for (index, item) in items.iter().enumerate() {
+        let key = text(item, "id").unwrap_or_else(|| format!("{}:{}", list, index));
+        let due_text = text(item, "due");
+        let due = due_text.as_deref().and_then(due_date);
+        if due_text.is_some() && due.is_none() {
+            export.invalid_dates += 1;
+        }
+        let completed = text(item, "status").is_some_and(|status| status.eq_ignore_ascii_case("completed"));
+        let completed_at = completed.then(|| {
+            text(item, "completed")
+                .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                .map(|dt| dt.timestamp_millis())
+                .unwrap_or_else(dates::now_ms)
+        });
+        export.items.push(Item {
+            key,
+            list: list.to_string(),
+            parent: text(item, "parent"),
+            title: text(item, "title").unwrap_or_default(),
+            notes: text(item, "notes"),
+            due_date: due,
+            completed_at,
+            deleted: item.get("deleted").and_then(Value::as_bool).unwrap_or(false),
+            position: text(item, "position").unwrap_or_else(|| format!("{:020}", index)),
+        });
+    }
+}
+
+fn is_task_list(value: &Value) -> bool {
+    value.get("kind").and_then(Value::as_str) == Some("tasks#taskList") || value.get("items").is_some_and(Value::is_array)
+}
+
+// A Takeout export (task lists holding their tasks), the API's answer for one list
+// (`items` of tasks) or a bare array of tasks. The last two become one list named after
+// the file.
+fn read_export(raw: &str, file_stem: &str) -> Result<Export, String> {
+    let root: Value = serde_json::from_str(raw).map_err(|e| format!("Not a Google Tasks JSON export: {}", e))?;
+    let entries = match &root {
+        Value::Array(entries) => entries.as_slice(),
+        _ => root
+            .get("items")
+            .or_else(|| root.get("tasks"))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .ok_or("Not a Google Tasks JSON export: no task lists or tasks found")?,
+    };
+
+    let mut export = Export::default();
+    if entries.iter().any(is_task_list) {
+        for (index, entry) in entries.iter().filter(|entry| is_task_list(entry)).enumerate() {
+            let key = text(entry, "id").unwrap_or_else(|| format!("list:{}", index));
+            let title = text(entry, "title").unwrap_or_else(|| "Google Tasks".to_string());
+            let items = entry.get("items").and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]);
+            read_items(&mut export, &key, items);
+            export.lists.push(TaskList { key, title });
+        }
+    } else {
+        let key = "file".to_string();
+        read_items(&mut export, &key, entries);
+        export.lists.push(TaskList {
+            key,
+            title: file_stem.to_string(),
+        });
+    }
+    Ok(export)
+}
+
+// Live list with this name, or a new one
+fn list_for(conn: &Connection, title: &str, summary: &mut ImportSummary) -> rusqlite::Result<(String, String)> {
+    let existing: Option<String> = conn
+        .query_row(
+            r#"SELECT id FROM lists WHERE name = ?1 AND deleted_at IS NULL ORDER BY "order" LIMIT 1"#,
+            [title],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let id = match existing {
+        Some(id) => id,
+        None => {
+            summary.lists += 1;
+            tasks::insert_list(conn, title)?
+        }
+    };
+    Ok((id, title.to_string()))
+}
+
+// Follow parents up to the top-level task, `None` if the chain breaks or loops
+fn root_of<'a>(item: &'a Item, by_key: &HashMap<&str, &'a Item>) -> Option<&'a Item> {
+    let mut current = item;
+    for _ in 0..by_key.len() {
+        match &current.parent {
+            None => return Some(current),
+            Some(parent) => current = by_key.get(parent.as_str())?,
+        }
+    }
+    None
+}
+
+fn apply(conn: &Connection, mut export: Export, list_id: Option<&str>) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary {
+        invalid_dates: export.invalid_dates,
+        ..Default::default()
+    };
+    export.items.sort_by(|a, b| a.position.cmp(&b.position));
+
+    let target = match list_id {
+        Some(list_id) => {
+            let name = tasks::list_name(conn, list_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("List {} not found", list_id))?;
+            Some((list_id.to_string(), name))
+        }
+        None => None,
+    };
+    let mut lists: HashMap<&str, (String, String)> = HashMap::new();
+    for list in &export.lists {
+        let list_ref = match &target {
+            Some(target) => target.clone(),
+            None => list_for(conn, &list.title, &mut summary).map_err(|e| e.to_string())?,
+        };
+        lists.insert(&list.key, list_ref);
+    }
+
+    for item in export.items.iter().filter(|item| item.deleted) {
+        summary.skipped.push(format!("'{}' was deleted in Google Tasks", item.title));
+    }
+    let live: Vec<&Item> = export.items.iter().filter(|item| !item.deleted).collect();
+    let by_key: HashMap<&str, &Item> = live.iter().map(|item| (item.key.as_str(), *item)).collect();
+    // Google Tasks id -> id of the task created for it
+    let mut task_ids: HashMap<&str, String> = HashMap::new();
+
+    for item in live.iter().filter(|item| item.parent.is_none()) {
+        if item.title.is_empty() {
+            summary.skipped.push(format!("Task {} has no title", item.key));
+            continue;
+        }
+        let Some((list_id, list_name)) = lists.get(item.list.as_str()) else {
+            continue;
+        };
+        let task = NewTask {
+            title: item.title.clone(),
+            content: item.notes.clone(),
+            due_date: item.due_date,
+            completed_at: item.completed_at,
+            ..Default::default()
+        };
+        let id = tasks::insert(conn, list_id, list_name, &task).map_err(|e| e.to_string())?;
+        task_ids.insert(&item.key, id);
+        summary.tasks += 1;
+    }
+
+    // Subtasks don't nest, deeper tasks hang off their top-level task
+    for item in live.iter().filter(|item| item.parent.is_some()) {
+        let parent_id = root_of(item, &by_key).and_then(|root| task_ids.get(root.key.as_str()));
+        let Some(parent_id) = parent_id else {
+            summary.skipped.push(format!("The parent of subtask '{}' isn't in the export", item.title));
+            continue;
+        };
+        if item.title.is_empty() {
+            summary.skipped.push(format!("Task {} has no title", item.key));
+            continue;
+        }
+        let subtask = NewSubtask {
+            title: item.title.clone(),
+            due_date: item.due_date,
+            completed_at: item.completed_at,
+        };
+        tasks::insert_subtask(conn, parent_id, &subtask).map_err(|e| e.to_string())?;
+        summary.subtasks += 1;
+        if item.notes.is_some() {
+            summary.skipped.push(format!("The notes of subtask '{}', subtasks have no notes", item.title));
+        }
+    }
+    Ok(summary)
+}
+
+// Import a Google Tasks export. Task lists become lists (an existing list of the same name
+// is reused) unless `list_id` is given, which takes every task. Tasks with a `parent`
+// become subtasks of it.
+#[tauri::command]
+pub async fn import_google_tasks(app: AppHandle, path: String, list_id: Option<String>) -> Result<ImportSummary, String> {
+    let path = Path::new(&path);
+    let raw = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let file_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Google Tasks");
+    let export = read_export(&raw, file_stem)?;
+    let list_id = list_id.as_deref().map(str::trim).filter(|id| !id.is_empty());
+
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_google_tasks").map_err(|e| e.to_string())?;
+    let summary = apply(&tx, export, list_id)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
@@ -0,0 +1,180 @@
+use crate::tasks::{Task, TASK_COLUMNS};
+use crate::{dates, db, lists, tray};
+use chrono::{FixedOffset, TimeDelta, TimeZone};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+pub const GROUPS_CHANGED_EVENT: &str = "groups-changed";
+
+// Waking up at least this often catches midnights missed while the machine slept or the
+// clock was changed
+const MAX_SLEEP: Duration = Duration::from_secs(3600);
+
+// id, due_date, completed, group_category, rolled_over_at
+type GroupRow = (String, Option<i64>, bool, String, Option<i64>);
+
+// Bring every task's `group_category` up to date for the local day of `now_ms`, returns
+// how many tasks moved to another bucket. `updated_at` is left alone: the bucket is
+// derived from the due date and not an edit of the task.
+pub fn recompute(conn: &mut Connection, now_ms: i64) -> rusqlite::Result<usize> {
+    let tx = conn.transaction()?;
+    let tasks: Vec<GroupRow> = {
+        let mut stmt = tx.prepare(
+            "SELECT id, due_date, completed, group_category, rolled_over_at FROM tasks WHERE deleted_at IS NULL",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let today_start = dates::local_date(now_ms).map(dates::local_midnight);
+
+    let mut changed = 0;
+    {
+        let mut stmt = tx.prepare("UPDATE tasks SET group_category = ?1 WHERE id = ?2")?;
+        for (id, due_date, completed, current, rolled_over_at) in &tasks {
+            let mut category = dates::group_category(*due_date, *completed, now_ms);
+            // Carried over to today by `rollover::rollover` without moving the date
+            if category == "overdue" && rolled_over_at.zip(today_start).is_some_and(|(at, start)| at >= start) {
+                category = "today";
+            }
+            if category != current.as_str() {
+                stmt.execute(params![category, id])?;
+                changed += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(changed)
+}
+
+fn refresh(app: &AppHandle) -> Result<usize, String> {
+    let changed = {
+        let mut conn = db::connect(app)?;
+        recompute(&mut conn, dates::now_ms()).map_err(|e| e.to_string())?
+    };
+    if changed > 0 {
+        let _ = app.emit(GROUPS_CHANGED_EVENT, changed);
+    }
+    // The tray counts today's tasks, which changes at midnight whether or not a bucket did
+    tray::refresh(app)?;
+    Ok(changed)
+}
+
+fn until_next_midnight(now_ms: i64) -> Duration {
+    let next = dates::local_date(now_ms)
+        .map(|today| dates::local_midnight(today + TimeDelta::days(1)))
+        .unwrap_or(now_ms);
+    // A second late so the new day has surely begun
+    Duration::from_millis((next - now_ms).max(0) as u64 + 1000).min(MAX_SLEEP)
+}
+
+// Recompute the buckets whenever a new local day starts
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut day = dates::local_date(dates::now_ms());
+        loop {
+            thread::sleep(until_next_midnight(dates::now_ms()));
+            let today = dates::local_date(dates::now_ms());
+            if today == day {
+                continue;
+            }
+            day = today;
+            if let Err(e) = refresh(&app) {
+                eprintln!("Failed to recompute task groups: {}", e);
+            }
+        }
+    });
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupedTasks {
+    // Dated buckets soonest due first, `nodate` in list order, `completed` most recently
+    // completed first
+    pub overdue: Vec<Task>,
+    pub today: Vec<Task>,
+    pub tomorrow: Vec<Task>,
+    pub upcoming: Vec<Task>,
+    pub nodate: Vec<Task>,
+    pub completed: Vec<Task>,
+}
+
+// Live tasks of a list (of every list with `None`, hidden ones only with `include_hidden`)
+// bucketed like `recompute` does, but for the day of `now_ms` at `tz_offset_minutes` east
+// of UTC and without writing the buckets back. Each task's `group_category` is the bucket
+// it landed in.
+pub fn grouped(
+    conn: &Connection,
+    list_id: Option<&str>,
+    now_ms: i64,
+    tz_offset_minutes: i32,
+    include_hidden: bool,
+) -> Result<GroupedTasks, String> {
+    let zone = FixedOffset::east_opt(tz_offset_minutes * 60)
+        .ok_or_else(|| format!("Invalid UTC offset: {} minutes", tz_offset_minutes))?;
+    let local_date = |ms: i64| zone.timestamp_millis_opt(ms).single().map(|dt| dt.date_naive());
+    let today = local_date(now_ms).ok_or_else(|| format!("Invalid time {}", now_ms))?;
+    let today_start = dates::day_start_at_offset(now_ms, tz_offset_minutes)?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"SELECT {}, rolled_over_at FROM tasks
+               WHERE deleted_at IS NULL AND list_name != 'Trash' AND (?1 IS NULL OR list_id = ?1){}
+               ORDER BY due_date IS NULL, due_date, "order", id"#,
+            TASK_COLUMNS,
+            // A hidden list opened on its own still shows its tasks
+            lists::hidden_filter(include_hidden || list_id.is_some())
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([list_id], |row| Ok((Task::from_row(row)?, row.get::<_, Option<i64>>("rolled_over_at")?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut groups = GroupedTasks::default();
+    for row in rows {
+        let (mut task, rolled_over_at) = row.map_err(|e| e.to_string())?;
+        if task.completed {
+            groups.completed.push(task);
+            continue;
+        }
+        let days = task.due_date.and_then(local_date).map(|due| (due - today).num_days());
+        let (category, bucket) = match days {
+            None => ("nodate", &mut groups.nodate),
+            // Carried over to today by `rollover::rollover` without moving the date
+            Some(d) if d < 0 && rolled_over_at.is_some_and(|at| at >= today_start) => ("today", &mut groups.today),
+            Some(d) if d < 0 => ("overdue", &mut groups.overdue),
+            Some(0) => ("today", &mut groups.today),
+            Some(1) => ("tomorrow", &mut groups.tomorrow),
+            Some(_) => ("upcoming", &mut groups.upcoming),
+        };
+        task.group_category = category.to_string();
+        bucket.push(task);
+    }
+    groups.nodate.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.id.cmp(&b.id)));
+    groups
+        .completed
+        .sort_by(|a, b| b.completed_at.cmp(&a.completed_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(groups)
+}
+
+#[tauri::command]
+pub async fn recompute_groups(app: AppHandle, now_ms: i64) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    recompute(&mut conn, now_ms).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_grouped_tasks(
+    app: AppHandle,
+    list_id: Option<String>,
+    now_ms: i64,
+    tz_offset_minutes: i32,
+    include_hidden: Option<bool>,
+) -> Result<GroupedTasks, String> {
+    let conn = db::connect(&app)?;
+    grouped(&conn, list_id.as_deref(), now_ms, tz_offset_minutes, include_hidden.unwrap_or(false))
+}
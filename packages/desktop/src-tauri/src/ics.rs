@@ -0,0 +1,155 @@
+use crate::tasks::{Subtask, Task, SUBTASK_COLUMNS, TASK_COLUMNS};
+use crate::{dates, db, export};
+use chrono::{TimeZone, Utc};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+// RFC 5545 limits content lines to 75 octets, excluding the CRLF
+const MAX_LINE_OCTETS: usize = 75;
+
+fn format_utc(ms: i64) -> Option<String> {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .map(|dt| dt.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Append a content line, folding it onto continuation lines that start with a space
+fn push_line(out: &mut String, line: &str) {
+    let mut octets = 0;
+    for ch in line.chars() {
+        let len = ch.len_utf8();
+        if octets + len > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        out.push(ch);
+        octets += len;
+    }
+    out.push_str("\r\n");
+}
+
+// Our priorities go 1 (high) to 3 (low); iCalendar uses 1 (high) to 9 (low)
+fn ical_priority(priority: i64) -> Option<u8> {
+    match priority {
+        1 => Some(1),
+        2 => Some(5),
+        3 => Some(9),
+        _ => None,
+    }
+}
+
+fn push_todo(out: &mut String, task: &Task, subtasks: &[Subtask], stamp: &str) {
+    push_line(out, "BEGIN:VTODO");
+    push_line(out, &format!("UID:{}@tada", task.id));
+    push_line(out, &format!("DTSTAMP:{}", stamp));
+    if let Some(created) = format_utc(task.created_at) {
+        push_line(out, &format!("CREATED:{}", created));
+    }
+    if let Some(modified) = format_utc(task.updated_at) {
+        push_line(out, &format!("LAST-MODIFIED:{}", modified));
+    }
+    push_line(out, &format!("SUMMARY:{}", escape_text(&task.title)));
+
+    let mut description = task.content.clone().unwrap_or_default();
+    if !subtasks.is_empty() {
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        let lines: Vec<String> = subtasks
+            .iter()
+            .map(|s| format!("[{}] {}", if s.completed { "x" } else { " " }, s.title))
+            .collect();
+        description.push_str(&lines.join("\n"));
+    }
+    if !description.is_empty() {
+        push_line(out, &format!("DESCRIPTION:{}", escape_text(&description)));
+    }
+
+    // Tasks only carry a due date, which doubles as the start
+    if let Some(due) = task.due_date.and_then(format_utc) {
+        push_line(out, &format!("DTSTART:{}", due));
+        push_line(out, &format!("DUE:{}", due));
+    }
+
+    if task.completed {
+        push_line(out, "STATUS:COMPLETED");
+        if let Some(completed) = task.completed_at.and_then(format_utc) {
+            push_line(out, &format!("COMPLETED:{}", completed));
+        }
+    } else {
+        push_line(out, "STATUS:NEEDS-ACTION");
+    }
+
+    let percent = if task.completed {
+        Some(100)
+    } else {
+        task.complete_percentage
+    };
+    if let Some(percent) = percent {
+        push_line(out, &format!("PERCENT-COMPLETE:{}", percent.clamp(0, 100)));
+    }
+    if let Some(priority) = task.priority.and_then(ical_priority) {
+        push_line(out, &format!("PRIORITY:{}", priority));
+    }
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|t| escape_text(t)).collect();
+        push_line(out, &format!("CATEGORIES:{}", tags.join(",")));
+    }
+    push_line(out, "END:VTODO");
+}
+
+pub fn build_calendar(conn: &Connection, list_id: Option<&str>) -> Result<(String, usize), String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"SELECT {} FROM tasks WHERE (?1 IS NULL OR list_id = ?1) AND list_name != 'Trash' AND deleted_at IS NULL ORDER BY "order""#,
+            TASK_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let tasks = stmt
+        .query_map([list_id], Task::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(r#"SELECT {} FROM subtasks WHERE deleted_at IS NULL ORDER BY parent_id, "order""#, SUBTASK_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let mut subtasks: HashMap<String, Vec<Subtask>> = HashMap::new();
+    for subtask in stmt.query_map([], Subtask::from_row).map_err(|e| e.to_string())? {
+        let subtask = subtask.map_err(|e| e.to_string())?;
+        subtasks.entry(subtask.parent_id.clone()).or_default().push(subtask);
+    }
+
+    let stamp = format_utc(dates::now_ms()).unwrap_or_default();
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//Tada//Tada Tasks//EN");
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    for task in &tasks {
+        let children = subtasks.get(&task.id).map(Vec::as_slice).unwrap_or_default();
+        push_todo(&mut out, task, children, &stamp);
+    }
+    push_line(&mut out, "END:VCALENDAR");
+
+    Ok((out, tasks.len()))
+}
+
+#[tauri::command]
+pub async fn export_ics(app: AppHandle, list_id: Option<String>, path: String) -> Result<usize, String> {
+    let conn = db::connect(&app)?;
+    let (calendar, count) = build_calendar(&conn, list_id.as_deref())?;
+    export::write_file_atomic(Path::new(&path), &calendar)?;
+    Ok(count)
+}
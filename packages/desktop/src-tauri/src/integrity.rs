@@ -0,0 +1,347 @@
+use crate::export::{self, INBOX_LIST_ID};
+use crate::reminders::ReminderScheduler;
+use crate::{dates, db, journal, organize, tasks, tray};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    pub parent: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateOrder {
+    pub list_id: Option<String>,
+    pub order: i64,
+    pub task_ids: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    #[serde(default)]
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    // Live tasks without a live list (and not in the trash)
+    #[serde(default)]
+    pub orphaned_tasks: Vec<String>,
+    // Subtasks whose parent is gone, or live subtasks of a trashed parent
+    #[serde(default)]
+    pub orphaned_subtasks: Vec<String>,
+    #[serde(default)]
+    pub duplicate_orders: Vec<DuplicateOrder>,
+    // Tasks flagged completed without a completion time
+    #[serde(default)]
+    pub missing_completed_at: Vec<String>,
+}
+
+impl IntegrityReport {
+    pub fn issue_count(&self) -> usize {
+        self.foreign_key_violations.len()
+            + self.orphaned_tasks.len()
+            + self.orphaned_subtasks.len()
+            + self.duplicate_orders.len()
+            + self.missing_completed_at.len()
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairResult {
+    pub reassigned_tasks: usize,
+    pub fixed_subtasks: usize,
+    pub backfilled_completed_at: usize,
+    pub resequenced_lists: usize,
+}
+
+const ORPHANED_TASK: &str = "t.deleted_at IS NULL AND t.list_name != 'Trash' \
+     AND NOT EXISTS (SELECT 1 FROM lists l WHERE l.id = t.list_id AND l.deleted_at IS NULL)";
+const ORPHANED_SUBTASK: &str = "NOT EXISTS (SELECT 1 FROM tasks t WHERE t.id = s.parent_id) \
+     OR (s.deleted_at IS NULL AND EXISTS (SELECT 1 FROM tasks t WHERE t.id = s.parent_id AND t.deleted_at IS NOT NULL))";
+const MISSING_COMPLETED_AT: &str = "completed = 1 AND completed_at IS NULL";
+
+fn ids(conn: &Connection, sql: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+pub fn check(conn: &Connection) -> rusqlite::Result<IntegrityReport> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let foreign_key_violations = stmt
+        .query_map([], |row| {
+            Ok(ForeignKeyViolation {
+                table: row.get(0)?,
+                rowid: row.get(1)?,
+                parent: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT list_id, "order", json_group_array(id)
+        FROM tasks
+        WHERE deleted_at IS NULL
+        GROUP BY list_id, "order"
+        HAVING COUNT(*) > 1
+        ORDER BY list_id, "order"
+        "#,
+    )?;
+    let duplicate_orders = stmt
+        .query_map([], |row| {
+            let raw: String = row.get(2)?;
+            Ok(DuplicateOrder {
+                list_id: row.get(0)?,
+                order: row.get(1)?,
+                task_ids: serde_json::from_str(&raw).unwrap_or_default(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(IntegrityReport {
+        foreign_key_violations,
+        orphaned_tasks: ids(conn, &format!("SELECT t.id FROM tasks t WHERE {}", ORPHANED_TASK))?,
+        orphaned_subtasks: ids(conn, &format!("SELECT s.id FROM subtasks s WHERE {}", ORPHANED_SUBTASK))?,
+        duplicate_orders,
+        missing_completed_at: ids(conn, &format!("SELECT id FROM tasks WHERE {}", MISSING_COMPLETED_AT))?,
+    })
+}
+
+// Give a list's tasks consecutive orders, keeping their current sequence
+fn resequence(conn: &Connection, list_id: Option<&str>, now: i64) -> rusqlite::Result<()> {
+    let ids: Vec<String> = {
+        let mut stmt = conn.prepare(r#"SELECT id FROM tasks WHERE list_id IS ?1 ORDER BY "order", created_at, id"#)?;
+        let rows = stmt.query_map([list_id], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut stmt = conn.prepare(r#"UPDATE tasks SET "order" = ?1, updated_at = ?2 WHERE id = ?3 AND "order" != ?1"#)?;
+    for (index, id) in ids.iter().enumerate() {
+        stmt.execute(params![index as i64 + 1, now, id])?;
+    }
+    Ok(())
+}
+
+// Fix what `report` lists. Every entry is checked again first, so a stale report can't
+// touch rows that are fine by now.
+pub fn repair(conn: &mut Connection, report: &IntegrityReport) -> rusqlite::Result<RepairResult> {
+    let now = dates::now_ms();
+    let mut result = RepairResult::default();
+    let tx = conn.transaction()?;
+    journal::begin(&tx, "repair_integrity")?;
+    let inbox_name = export::ensure_inbox(&tx)?;
+
+    // Foreign key violations in tasks and subtasks are the same orphans as below
+    let mut orphaned_tasks = report.orphaned_tasks.clone();
+    let mut orphaned_subtasks = report.orphaned_subtasks.clone();
+    for violation in &report.foreign_key_violations {
+        let Some(rowid) = violation.rowid else { continue };
+        let table = match violation.table.as_str() {
+            "tasks" => &mut orphaned_tasks,
+            "subtasks" => &mut orphaned_subtasks,
+            _ => continue,
+        };
+        let id: Option<String> = tx
+            .query_row(&format!("SELECT id FROM \"{}\" WHERE rowid = ?1", violation.table), [rowid], |row| row.get(0))
+            .optional()?;
+        table.extend(id);
+    }
+
+    for id in &orphaned_tasks {
+        result.reassigned_tasks += tx.execute(
+            &format!(
+                r#"UPDATE tasks AS t SET list_id = ?1, list_name = ?2, "order" = (SELECT COALESCE(MAX("order"), 0) + 1 FROM tasks WHERE list_id = ?1), updated_at = ?3
+                   WHERE t.id = ?4 AND ({})"#,
+                ORPHANED_TASK
+            ),
+            params![INBOX_LIST_ID, inbox_name, now, id],
+        )?;
+    }
+
+    for id in &orphaned_subtasks {
+        let parent_deleted_at: Option<Option<i64>> = tx
+            .query_row(
+                "SELECT t.deleted_at FROM subtasks s JOIN tasks t ON t.id = s.parent_id WHERE s.id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        result.fixed_subtasks += match parent_deleted_at {
+            // The parent is gone for good
+            None => tx.execute(
+                &format!("DELETE FROM subtasks AS s WHERE s.id = ?1 AND ({})", ORPHANED_SUBTASK),
+                [id],
+            )?,
+            // Follow the parent into the trash
+            Some(Some(deleted_at)) => tx.execute(
+                "UPDATE subtasks SET deleted_at = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+                params![deleted_at, now, id],
+            )?,
+            Some(None) => 0,
+        };
+    }
+
+    // The last edit is the best guess for when the task was completed
+    for id in &report.missing_completed_at {
+        result.backfilled_completed_at += tx.execute(
+            &format!("UPDATE tasks SET completed_at = updated_at WHERE id = ?1 AND {}", MISSING_COMPLETED_AT),
+            [id],
+        )?;
+    }
+
+    let mut lists: Vec<Option<&str>> = report.duplicate_orders.iter().map(|d| d.list_id.as_deref()).collect();
+    lists.sort();
+    lists.dedup();
+    for list_id in lists {
+        resequence(&tx, list_id, now)?;
+        result.resequenced_lists += 1;
+    }
+
+    journal::end(&tx)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkProblem {
+    // No row with the parent's id at all
+    MissingParent,
+    // The parent is in the trash, the subtask isn't
+    TrashedParent,
+    // The parent id names another subtask, subtasks don't nest
+    ParentIsSubtask,
+    SelfReference,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLink {
+    pub subtask_id: String,
+    pub title: String,
+    pub parent_id: String,
+    pub problem: LinkProblem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FixAction {
+    // Make them tasks at the end of the Inbox
+    Promote,
+    Delete,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkFixResult {
+    // Ids of the tasks the promoted subtasks became
+    pub promoted: Vec<String>,
+    pub deleted: usize,
+}
+
+// Live subtasks only, trashed ones of a missing parent are left to `repair`
+pub fn broken_links(conn: &Connection) -> rusqlite::Result<Vec<BrokenLink>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, title, parent_id, problem FROM (
+            SELECT s.id, s.title, s.parent_id, s."order",
+                CASE
+                    WHEN s.parent_id = s.id THEN 'selfReference'
+                    WHEN EXISTS (SELECT 1 FROM tasks t WHERE t.id = s.parent_id AND t.deleted_at IS NOT NULL) THEN 'trashedParent'
+                    WHEN EXISTS (SELECT 1 FROM tasks t WHERE t.id = s.parent_id) THEN NULL
+                    WHEN EXISTS (SELECT 1 FROM subtasks p WHERE p.id = s.parent_id) THEN 'parentIsSubtask'
+                    ELSE 'missingParent'
+                END AS problem
+            FROM subtasks s
+            WHERE s.deleted_at IS NULL
+        )
+        WHERE problem IS NOT NULL
+        ORDER BY parent_id, "order"
+        "#,
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let problem = match row.get::<_, String>(3)?.as_str() {
+            "selfReference" => LinkProblem::SelfReference,
+            "trashedParent" => LinkProblem::TrashedParent,
+            "parentIsSubtask" => LinkProblem::ParentIsSubtask,
+            _ => LinkProblem::MissingParent,
+        };
+        Ok(BrokenLink {
+            subtask_id: row.get(0)?,
+            title: row.get(1)?,
+            parent_id: row.get(2)?,
+            problem,
+        })
+    })?;
+    rows.collect()
+}
+
+// Fix every link `broken_links` finds right now. Promoted subtasks keep their title, due
+// date and completion.
+pub fn fix_links(conn: &mut Connection, action: FixAction) -> Result<LinkFixResult, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "fix_subtask_links").map_err(|e| e.to_string())?;
+    let mut result = LinkFixResult::default();
+    let broken = broken_links(&tx).map_err(|e| e.to_string())?;
+    let inbox_name = match action {
+        FixAction::Promote if !broken.is_empty() => export::ensure_inbox(&tx).map_err(|e| e.to_string())?,
+        _ => String::new(),
+    };
+    for link in &broken {
+        match action {
+            FixAction::Promote => {
+                let subtask = tasks::get_subtask(&tx, &link.subtask_id)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Subtask {} not found", link.subtask_id))?;
+                let id = organize::replace_with_task(&tx, &subtask, Some(INBOX_LIST_ID), &inbox_name)
+                    .map_err(|e| e.to_string())?;
+                result.promoted.push(id);
+            }
+            FixAction::Delete => {
+                result.deleted += tx
+                    .execute("DELETE FROM subtasks WHERE id = ?1", [&link.subtask_id])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn check_integrity(app: AppHandle) -> Result<IntegrityReport, String> {
+    let conn = db::connect(&app)?;
+    check(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn repair_integrity(app: AppHandle, report: IntegrityReport) -> Result<RepairResult, String> {
+    let mut conn = db::connect(&app)?;
+    repair(&mut conn, &report).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn validate_subtask_links(app: AppHandle) -> Result<Vec<BrokenLink>, String> {
+    let conn = db::connect(&app)?;
+    broken_links(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn fix_subtask_links(app: AppHandle, action: FixAction) -> Result<LinkFixResult, String> {
+    let result = {
+        let mut conn = db::connect(&app)?;
+        fix_links(&mut conn, action)?
+    };
+    if !result.promoted.is_empty() {
+        app.state::<ReminderScheduler>().wake();
+        if let Err(e) = tray::refresh(&app) {
+            eprintln!("Failed to refresh tray: {}", e);
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,334 @@
+use crate::export::{self, Row};
+use crate::{dates, db, settings};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+// Every journaled write goes through triggers, so edits made by the frontend through the
+// sql plugin are covered as well as native commands. A row's before and after state is
+// stored as JSON of all its columns.
+const JOURNALED_TABLES: &[&str] = &["lists", "tasks", "subtasks"];
+// Columns that change on their own (bookkeeping, derived buckets, reminder state). An
+// update touching nothing else isn't something the user would want to undo.
+const IGNORED_COLUMNS: &[&str] = &["updated_at", "group_category", "reminded_at", "rolled_over_at"];
+const DEFAULT_UNDO_DEPTH: i64 = 100;
+
+// SQLite keeps 'now' fixed for the whole statement, so the rows of one statement
+// (`DELETE FROM subtasks WHERE parent_id = ?`) share their group
+const NOW_MS: &str = "CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationDescription {
+    pub kind: String,
+    pub entity_ids: Vec<String>,
+    pub created_at: i64,
+}
+
+struct Entry {
+    id: i64,
+    kind: String,
+    table: String,
+    entity_id: String,
+    before: Option<Row>,
+    after: Option<Row>,
+    created_at: i64,
+}
+
+fn is_installed(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'journal_state'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+fn set_state(conn: &Connection, group: Option<&str>, kind: Option<&str>, paused: bool) -> rusqlite::Result<()> {
+    // Startup jobs may run before the frontend applied the migration creating the journal
+    if !is_installed(conn)? {
+        return Ok(());
+    }
+    conn.execute(
+        "UPDATE journal_state SET open_group = ?1, open_kind = ?2, paused = ?3 WHERE id = 1",
+        params![group, kind, paused],
+    )?;
+    Ok(())
+}
+
+// Record everything written until `end` as a single undo unit. Both have to run inside
+// the same transaction, a rollback then also discards the open unit.
+pub fn begin(conn: &Connection, kind: &str) -> rusqlite::Result<()> {
+    set_state(conn, Some(&Uuid::new_v4().to_string()), Some(kind), false)
+}
+
+// Keep the writes until `end` out of the journal (background jobs, replays)
+pub fn pause(conn: &Connection) -> rusqlite::Result<()> {
+    set_state(conn, None, None, true)
+}
+
+pub fn end(conn: &Connection) -> rusqlite::Result<()> {
+    set_state(conn, None, None, false)
+}
+
+fn json_of(columns: &[String], prefix: &str) -> String {
+    let pairs: Vec<String> = columns
+        .iter()
+        .map(|c| format!("'{0}', {1}.\"{0}\"", c, prefix))
+        .collect();
+    format!("json_object({})", pairs.join(", "))
+}
+
+fn trigger_sql(table: &str, columns: &[String]) -> String {
+    let group = format!("COALESCE((SELECT open_group FROM journal_state), 'statement-' || {})", NOW_MS);
+    let kind = |op: &str| format!("COALESCE((SELECT open_kind FROM journal_state), '{}.{}')", table, op);
+    let active = "(SELECT paused FROM journal_state) = 0";
+    let changed: Vec<String> = columns
+        .iter()
+        .filter(|c| !IGNORED_COLUMNS.contains(&c.as_str()))
+        .map(|c| format!("old.\"{0}\" IS NOT new.\"{0}\"", c))
+        .collect();
+    let log = |op: &str, entity: &str, before: &str, after: &str| {
+        format!(
+            "-- A new change makes the undone units unreachable
+             DELETE FROM operations_log WHERE undone = 1;
+             INSERT INTO operations_log (group_id, kind, entity_table, entity_id, before_json, after_json, created_at)
+             VALUES ({}, {}, '{}', {}, {}, {}, {});",
+            group,
+            kind(op),
+            table,
+            entity,
+            before,
+            after,
+            NOW_MS
+        )
+    };
+
+    // The insert trigger runs before the row is written, so for INSERT OR REPLACE (which
+    // doesn't fire delete triggers) the row being replaced is still there to read
+    format!(
+        r#"
+        CREATE TRIGGER journal_{table}_insert BEFORE INSERT ON {table} WHEN {active} BEGIN
+            {insert}
+        END;
+        CREATE TRIGGER journal_{table}_update AFTER UPDATE ON {table} WHEN {active} AND ({changed}) BEGIN
+            {update}
+        END;
+        CREATE TRIGGER journal_{table}_delete AFTER DELETE ON {table} WHEN {active} BEGIN
+            {delete}
+        END;
+        "#,
+        table = table,
+        active = active,
+        changed = changed.join(" OR "),
+        insert = log(
+            "insert",
+            "new.id",
+            &format!("(SELECT {} FROM {} AS t WHERE t.id = new.id)", json_of(columns, "t"), table),
+            &json_of(columns, "new"),
+        ),
+        update = log("update", "new.id", &json_of(columns, "old"), &json_of(columns, "new")),
+        delete = log("delete", "old.id", &json_of(columns, "old"), "NULL"),
+    )
+}
+
+// The triggers name every column, so SQLite refuses to drop a column while they exist.
+// `install` brings them back.
+pub fn drop_triggers(conn: &Connection) -> rusqlite::Result<()> {
+    for table in JOURNALED_TABLES {
+        conn.execute_batch(&format!(
+            "DROP TRIGGER IF EXISTS journal_{0}_insert;
+             DROP TRIGGER IF EXISTS journal_{0}_update;
+             DROP TRIGGER IF EXISTS journal_{0}_delete;",
+            table
+        ))?;
+    }
+    Ok(())
+}
+
+// (Re)create the journal triggers from the current columns of each table, so columns
+// added by later migrations are journaled without touching the triggers by hand
+pub fn install(conn: &mut Connection) -> rusqlite::Result<bool> {
+    if !is_installed(conn)? {
+        return Ok(false);
+    }
+    let tx = conn.transaction()?;
+    drop_triggers(&tx)?;
+    for table in JOURNALED_TABLES {
+        let columns = export::table_columns(&tx, table)?;
+        tx.execute_batch(&trigger_sql(table, &columns))?;
+    }
+    // A unit left open by a crashed build would swallow every later write
+    tx.execute("UPDATE journal_state SET open_group = NULL, open_kind = NULL, paused = 0", [])?;
+    prune(&tx, undo_depth(&tx))?;
+    tx.commit()?;
+    Ok(true)
+}
+
+// `preferences.undoDepth`, how many units are kept
+fn undo_depth(conn: &Connection) -> i64 {
+    settings::read(conn, "preferences")
+        .ok()
+        .flatten()
+        .and_then(|p| p.get("undoDepth").and_then(Value::as_i64))
+        .filter(|depth| *depth >= 0)
+        .unwrap_or(DEFAULT_UNDO_DEPTH)
+}
+
+fn prune(conn: &Connection, depth: i64) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM operations_log WHERE group_id IN (
+             SELECT group_id FROM operations_log GROUP BY group_id ORDER BY MAX(id) DESC LIMIT -1 OFFSET ?1
+         )",
+        [depth],
+    )
+}
+
+fn parse_row(raw: Option<String>) -> Option<Row> {
+    raw.and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+fn load_entries(conn: &Connection, group_id: &str) -> rusqlite::Result<Vec<Entry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, entity_table, entity_id, before_json, after_json, created_at
+         FROM operations_log WHERE group_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map([group_id], |row| {
+        Ok(Entry {
+            id: row.get(0)?,
+            kind: row.get(1)?,
+            table: row.get(2)?,
+            entity_id: row.get(3)?,
+            before: parse_row(row.get(4)?),
+            after: parse_row(row.get(5)?),
+            created_at: row.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+// Bring a row to `state`, `None` meaning it must not exist. `updated_at` is set to now
+// rather than restored, so sync treats the reverted row as the newest version.
+fn apply_state(conn: &Connection, table: &str, id: &str, state: Option<&Row>, now: i64) -> Result<(), String> {
+    if !JOURNALED_TABLES.contains(&table) {
+        return Err(format!("'{}' is not journaled", table));
+    }
+    let Some(state) = state else {
+        conn.execute(&format!("DELETE FROM \"{}\" WHERE id = ?1", table), [id])
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    };
+
+    let columns = export::table_columns(conn, table).map_err(|e| e.to_string())?;
+    let mut used: Vec<&str> = Vec::new();
+    let mut values = Vec::new();
+    for column in &columns {
+        if column == "updated_at" {
+            used.push(column.as_str());
+            values.push(rusqlite::types::Value::Integer(now));
+        } else if let Some(value) = state.get(column.as_str()) {
+            used.push(column.as_str());
+            values.push(export::json_to_sql(value));
+        }
+    }
+
+    let exists = conn
+        .query_row(&format!("SELECT 1 FROM \"{}\" WHERE id = ?1", table), [id], |_| Ok(()))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .is_some();
+    let sql = if exists {
+        let assignments: Vec<String> = used.iter().enumerate().map(|(i, c)| format!("\"{}\" = ?{}", c, i + 1)).collect();
+        values.push(rusqlite::types::Value::Text(id.to_string()));
+        format!("UPDATE \"{}\" SET {} WHERE id = ?{}", table, assignments.join(", "), values.len())
+    } else {
+        let placeholders: Vec<String> = (1..=used.len()).map(|i| format!("?{}", i)).collect();
+        let column_list: Vec<String> = used.iter().map(|c| format!("\"{}\"", c)).collect();
+        format!("INSERT INTO \"{}\" ({}) VALUES ({})", table, column_list.join(", "), placeholders.join(", "))
+    };
+    conn.execute(&sql, params_from_iter(values))
+        .map_err(|e| format!("Failed to restore {} {}: {}", table, id, e))?;
+    Ok(())
+}
+
+fn describe(entries: &[Entry]) -> OperationDescription {
+    let mut entity_ids: Vec<String> = Vec::new();
+    for entry in entries {
+        if !entity_ids.contains(&entry.entity_id) {
+            entity_ids.push(entry.entity_id.clone());
+        }
+    }
+    OperationDescription {
+        kind: entries.first().map(|e| e.kind.clone()).unwrap_or_default(),
+        entity_ids,
+        created_at: entries.iter().map(|e| e.created_at).max().unwrap_or(0),
+    }
+}
+
+// Undo the newest unit that is still applied, or with `redo` replay the oldest undone one
+fn step(conn: &mut Connection, redo: bool) -> Result<Option<OperationDescription>, String> {
+    if !is_installed(conn).map_err(|e| e.to_string())? {
+        return Ok(None);
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let group_id: Option<String> = tx
+        .query_row(
+            if redo {
+                "SELECT group_id FROM operations_log WHERE undone = 1 GROUP BY group_id ORDER BY MAX(id) LIMIT 1"
+            } else {
+                "SELECT group_id FROM operations_log WHERE undone = 0 GROUP BY group_id ORDER BY MAX(id) DESC LIMIT 1"
+            },
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(group_id) = group_id else {
+        return Ok(None);
+    };
+
+    let mut entries = load_entries(&tx, &group_id).map_err(|e| e.to_string())?;
+    let now = dates::now_ms();
+    pause(&tx).map_err(|e| e.to_string())?;
+    if redo {
+        for entry in &entries {
+            apply_state(&tx, &entry.table, &entry.entity_id, entry.after.as_ref(), now)?;
+        }
+    } else {
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.id));
+        for entry in &entries {
+            apply_state(&tx, &entry.table, &entry.entity_id, entry.before.as_ref(), now)?;
+        }
+    }
+    end(&tx).map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "UPDATE operations_log SET undone = ?1 WHERE group_id = ?2",
+        params![!redo, group_id],
+    )
+    .map_err(|e| e.to_string())?;
+    prune(&tx, undo_depth(&tx)).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(Some(describe(&entries)))
+}
+
+// Called by the frontend once its migrations ran, the journal table may be new
+#[tauri::command]
+pub async fn install_journal(app: AppHandle) -> Result<bool, String> {
+    let mut conn = db::connect(&app)?;
+    install(&mut conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn undo_last(app: AppHandle) -> Result<Option<OperationDescription>, String> {
+    let mut conn = db::connect(&app)?;
+    step(&mut conn, false)
+}
+
+#[tauri::command]
+pub async fn redo_last(app: AppHandle) -> Result<Option<OperationDescription>, String> {
+    let mut conn = db::connect(&app)?;
+    step(&mut conn, true)
+}
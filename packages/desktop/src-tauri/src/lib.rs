@@ -1,10 +1,74 @@
+mod ai;
+mod archive;
+mod attachments;
+mod backup;
+mod bulk_add;
+mod change_feed;
+mod comments;
+mod completion;
+mod completion_log;
+mod counts;
+mod csv_import;
+mod dates;
+mod day_plan;
+mod db;
+mod dedupe;
+mod deep_link;
+mod dependencies;
+mod detail;
+mod duplicate;
+mod export;
+mod focus;
+mod focus_sessions;
+mod google_tasks;
+mod groups;
+mod ics;
+mod integrity;
+mod journal;
+mod list_defaults;
+mod lists;
+mod local_api;
+mod maintenance;
+mod markdown;
+mod markdown_import;
+mod natural_dates;
+mod opml_import;
+mod organize;
+mod priority;
+mod query;
+mod query_log;
+mod quick_add;
+mod recurrence;
+mod relocate;
+mod reminders;
+mod report;
+mod reset;
+mod rollover;
+mod rollback;
+mod search;
+mod secrets;
+mod settings;
+mod share;
+mod smart_views;
+mod snooze;
+mod sort_keys;
+mod stats;
+mod sync;
+mod tag_export;
+mod tags;
+mod task_versions;
+mod tasks;
+mod templates;
+mod ticktick;
+mod time_tracking;
+mod todoist;
+mod trash;
+mod tray;
+mod updater;
+mod working_hours;
+
 use tauri_plugin_sql::{Migration, MigrationKind};
-use tauri::{
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    Manager, WindowEvent,
-    image::Image,
-};
+use tauri::{Manager, WindowEvent};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 // Define the application status to track whether a real exit operation is being performed
@@ -12,9 +76,10 @@ struct AppState {
     is_quitting: AtomicBool,
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    let migrations = vec![
+// Every Up migration is followed by the Down migration that reverts it. The sql plugin
+// only ever applies Ups, Downs are run by `rollback::migrate_to_version`.
+fn migrations() -> Vec<Migration> {
+    vec![
         Migration {
             version: 1,
             description: "create_initial_tables",
@@ -100,6 +165,18 @@ pub fn run() {
             "#,
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 1,
+            description: "drop_initial_tables",
+            sql: r#"
+                DROP TABLE IF EXISTS subtasks;
+                DROP TABLE IF EXISTS tasks;
+                DROP TABLE IF EXISTS lists;
+                DROP TABLE IF EXISTS summaries;
+                DROP TABLE IF EXISTS settings;
+            "#,
+            kind: MigrationKind::Down,
+        },
         Migration {
             version: 2,
             description: "add_echo_reports",
@@ -117,68 +194,1246 @@ pub fn run() {
                 CREATE INDEX IF NOT EXISTS idx_echo_reports_created_at ON echo_reports(created_at);
             "#,
             kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "drop_echo_reports",
+            sql: r#"
+                DROP TABLE IF EXISTS echo_reports;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 3,
+            description: "add_tasks_fts",
+            sql: r#"
+                -- Full-text index over task titles and content. It keeps its own copy of the
+                -- text keyed by task id, because the implicit rowid of `tasks` is not stable.
+                CREATE VIRTUAL TABLE IF NOT EXISTS tasks_fts USING fts5(
+                    task_id UNINDEXED,
+                    title,
+                    content,
+                    tokenize = 'unicode61 remove_diacritics 2'
+                );
+
+                INSERT INTO tasks_fts (task_id, title, content)
+                SELECT id, title, COALESCE(content, '') FROM tasks;
+
+                -- The frontend writes with INSERT OR REPLACE, which does not fire delete
+                -- triggers, so the insert trigger clears any stale row first.
+                CREATE TRIGGER IF NOT EXISTS tasks_fts_after_insert AFTER INSERT ON tasks BEGIN
+                    DELETE FROM tasks_fts WHERE task_id = new.id;
+                    INSERT INTO tasks_fts (task_id, title, content)
+                    VALUES (new.id, new.title, COALESCE(new.content, ''));
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS tasks_fts_after_update AFTER UPDATE OF id, title, content ON tasks BEGIN
+                    DELETE FROM tasks_fts WHERE task_id = old.id;
+                    INSERT INTO tasks_fts (task_id, title, content)
+                    VALUES (new.id, new.title, COALESCE(new.content, ''));
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS tasks_fts_after_delete AFTER DELETE ON tasks BEGIN
+                    DELETE FROM tasks_fts WHERE task_id = old.id;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "drop_tasks_fts",
+            sql: r#"
+                DROP TRIGGER IF EXISTS tasks_fts_after_insert;
+                DROP TRIGGER IF EXISTS tasks_fts_after_update;
+                DROP TRIGGER IF EXISTS tasks_fts_after_delete;
+                DROP TABLE IF EXISTS tasks_fts;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 4,
+            description: "add_task_recurrence",
+            sql: r#"
+                -- RFC 5545 RRULE of a recurring task; generated instances keep it NULL
+                ALTER TABLE tasks ADD COLUMN recurrence TEXT;
+                -- Recurring task an instance was generated from
+                ALTER TABLE tasks ADD COLUMN recurrence_parent_id TEXT;
+
+                CREATE INDEX IF NOT EXISTS idx_tasks_recurrence_parent_id ON tasks(recurrence_parent_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "drop_task_recurrence",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_tasks_recurrence_parent_id;
+                ALTER TABLE tasks DROP COLUMN recurrence_parent_id;
+                ALTER TABLE tasks DROP COLUMN recurrence;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 5,
+            description: "add_secrets",
+            sql: r#"
+                -- Encrypted values (base64 nonce + AES-GCM ciphertext), the key lives in the OS keyring
+                CREATE TABLE IF NOT EXISTS secrets (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL,
+                    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now') * 1000)
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "drop_secrets",
+            sql: r#"
+                DROP TABLE IF EXISTS secrets;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 6,
+            description: "add_task_reminders",
+            sql: r#"
+                -- Minutes before due_date to notify, NULL means no reminder
+                ALTER TABLE tasks ADD COLUMN reminder_offset_minutes INTEGER;
+                -- When the reminder was shown (or skipped), so it never fires twice
+                ALTER TABLE tasks ADD COLUMN reminded_at INTEGER;
+
+                -- Moving the due date or the offset re-arms the reminder
+                CREATE TRIGGER IF NOT EXISTS tasks_rearm_reminder
+                AFTER UPDATE OF due_date, reminder_offset_minutes ON tasks
+                WHEN old.due_date IS NOT new.due_date
+                  OR old.reminder_offset_minutes IS NOT new.reminder_offset_minutes
+                BEGIN
+                    UPDATE tasks SET reminded_at = NULL WHERE id = new.id;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "drop_task_reminders",
+            sql: r#"
+                DROP TRIGGER IF EXISTS tasks_rearm_reminder;
+                ALTER TABLE tasks DROP COLUMN reminded_at;
+                ALTER TABLE tasks DROP COLUMN reminder_offset_minutes;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 7,
+            description: "add_soft_delete",
+            sql: r#"
+                -- When the row was moved to the trash, NULL for live rows
+                ALTER TABLE lists ADD COLUMN deleted_at INTEGER;
+                ALTER TABLE tasks ADD COLUMN deleted_at INTEGER;
+                ALTER TABLE subtasks ADD COLUMN deleted_at INTEGER;
+
+                CREATE INDEX IF NOT EXISTS idx_lists_deleted_at ON lists(deleted_at);
+                CREATE INDEX IF NOT EXISTS idx_tasks_deleted_at ON tasks(deleted_at);
+                CREATE INDEX IF NOT EXISTS idx_subtasks_deleted_at ON subtasks(deleted_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "drop_soft_delete",
+            sql: r#"
+                -- Older builds know nothing about the trash, so trashed rows go for good
+                -- instead of coming back to life
+                DELETE FROM subtasks WHERE deleted_at IS NOT NULL;
+                DELETE FROM tasks WHERE deleted_at IS NOT NULL;
+                DELETE FROM lists WHERE deleted_at IS NOT NULL;
+
+                DROP INDEX IF EXISTS idx_lists_deleted_at;
+                DROP INDEX IF EXISTS idx_tasks_deleted_at;
+                DROP INDEX IF EXISTS idx_subtasks_deleted_at;
+                ALTER TABLE lists DROP COLUMN deleted_at;
+                ALTER TABLE tasks DROP COLUMN deleted_at;
+                ALTER TABLE subtasks DROP COLUMN deleted_at;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 8,
+            description: "add_operations_log",
+            sql: r#"
+                -- Undo journal, one row per changed row. Rows sharing a group_id are undone
+                -- together. The triggers filling it are created by `journal::install`.
+                CREATE TABLE IF NOT EXISTS operations_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    group_id TEXT NOT NULL,
+                    kind TEXT NOT NULL,
+                    entity_table TEXT NOT NULL,
+                    entity_id TEXT NOT NULL,
+                    before_json TEXT, -- NULL when the row was created
+                    after_json TEXT, -- NULL when the row was deleted
+                    undone INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_operations_log_group_id ON operations_log(group_id);
+                CREATE INDEX IF NOT EXISTS idx_operations_log_undone ON operations_log(undone);
+
+                -- Single row read by the triggers: the unit native commands are recording,
+                -- and whether journaling is paused
+                CREATE TABLE IF NOT EXISTS journal_state (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    open_group TEXT,
+                    open_kind TEXT,
+                    paused INTEGER NOT NULL DEFAULT 0
+                );
+
+                INSERT OR IGNORE INTO journal_state (id) VALUES (1);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "drop_operations_log",
+            sql: r#"
+                DROP TRIGGER IF EXISTS journal_lists_insert;
+                DROP TRIGGER IF EXISTS journal_lists_update;
+                DROP TRIGGER IF EXISTS journal_lists_delete;
+                DROP TRIGGER IF EXISTS journal_tasks_insert;
+                DROP TRIGGER IF EXISTS journal_tasks_update;
+                DROP TRIGGER IF EXISTS journal_tasks_delete;
+                DROP TRIGGER IF EXISTS journal_subtasks_insert;
+                DROP TRIGGER IF EXISTS journal_subtasks_update;
+                DROP TRIGGER IF EXISTS journal_subtasks_delete;
+                DROP TABLE IF EXISTS journal_state;
+                DROP TABLE IF EXISTS operations_log;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 9,
+            description: "add_archived_tasks",
+            sql: r#"
+                -- Completed tasks moved out of `tasks` by `archive::archive`, with the
+                -- columns of `tasks` and their subtasks serialized alongside
+                CREATE TABLE IF NOT EXISTS archived_tasks (
+                    id TEXT PRIMARY KEY,
+                    title TEXT NOT NULL,
+                    completed INTEGER NOT NULL DEFAULT 0,
+                    completed_at INTEGER,
+                    complete_percentage INTEGER,
+                    due_date INTEGER,
+                    list_id TEXT,
+                    list_name TEXT NOT NULL,
+                    content TEXT,
+                    "order" INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    updated_at INTEGER NOT NULL,
+                    tags TEXT, -- JSON array
+                    priority INTEGER,
+                    group_category TEXT NOT NULL DEFAULT 'nodate',
+                    recurrence TEXT,
+                    recurrence_parent_id TEXT,
+                    reminder_offset_minutes INTEGER,
+                    reminded_at INTEGER,
+                    archived_at INTEGER NOT NULL,
+                    subtasks TEXT NOT NULL DEFAULT '[]' -- JSON array of subtask rows
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_archived_tasks_list_id ON archived_tasks(list_id);
+                CREATE INDEX IF NOT EXISTS idx_archived_tasks_completed_at ON archived_tasks(completed_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "drop_archived_tasks",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_archived_tasks_completed_at;
+                DROP INDEX IF EXISTS idx_archived_tasks_list_id;
+                DROP TABLE IF EXISTS archived_tasks;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 10,
+            description: "add_list_defaults",
+            sql: r#"
+                -- JSON object of `list_defaults::ListDefaults`, NULL means no defaults
+                ALTER TABLE lists ADD COLUMN defaults TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "drop_list_defaults",
+            sql: r#"
+                ALTER TABLE lists DROP COLUMN defaults;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 11,
+            description: "add_list_counts",
+            sql: r#"
+                -- Per-list task counts for the sidebar badges, kept current by the triggers
+                -- below. Trashed and soft-deleted tasks don't count.
+                CREATE TABLE IF NOT EXISTS list_counts (
+                    list_id TEXT PRIMARY KEY,
+                    total INTEGER NOT NULL DEFAULT 0,
+                    incomplete INTEGER NOT NULL DEFAULT 0
+                );
+
+                INSERT OR REPLACE INTO list_counts (list_id, total, incomplete)
+                SELECT list_id, COUNT(*), COALESCE(SUM(completed = 0), 0) FROM tasks
+                WHERE list_id IS NOT NULL AND deleted_at IS NULL AND list_name != 'Trash'
+                GROUP BY list_id;
+
+                CREATE TRIGGER IF NOT EXISTS list_counts_insert
+                AFTER INSERT ON tasks
+                BEGIN
+                    INSERT OR REPLACE INTO list_counts (list_id, total, incomplete)
+                    SELECT new.list_id,
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = new.list_id
+                              AND deleted_at IS NULL AND list_name != 'Trash'),
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = new.list_id AND completed = 0
+                              AND deleted_at IS NULL AND list_name != 'Trash')
+                    WHERE new.list_id IS NOT NULL;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS list_counts_update
+                AFTER UPDATE OF list_id, list_name, completed, deleted_at ON tasks
+                BEGIN
+                    INSERT OR REPLACE INTO list_counts (list_id, total, incomplete)
+                    SELECT old.list_id,
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = old.list_id
+                              AND deleted_at IS NULL AND list_name != 'Trash'),
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = old.list_id AND completed = 0
+                              AND deleted_at IS NULL AND list_name != 'Trash')
+                    WHERE old.list_id IS NOT NULL;
+                    INSERT OR REPLACE INTO list_counts (list_id, total, incomplete)
+                    SELECT new.list_id,
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = new.list_id
+                              AND deleted_at IS NULL AND list_name != 'Trash'),
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = new.list_id AND completed = 0
+                              AND deleted_at IS NULL AND list_name != 'Trash')
+                    WHERE new.list_id IS NOT NULL;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS list_counts_delete
+                AFTER DELETE ON tasks
+                BEGIN
+                    INSERT OR REPLACE INTO list_counts (list_id, total, incomplete)
+                    SELECT old.list_id,
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = old.list_id
+                              AND deleted_at IS NULL AND list_name != 'Trash'),
+                           (SELECT COUNT(*) FROM tasks WHERE list_id = old.list_id AND completed = 0
+                              AND deleted_at IS NULL AND list_name != 'Trash')
+                    WHERE old.list_id IS NOT NULL;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "drop_list_counts",
+            sql: r#"
+                DROP TRIGGER IF EXISTS list_counts_delete;
+                DROP TRIGGER IF EXISTS list_counts_update;
+                DROP TRIGGER IF EXISTS list_counts_insert;
+                DROP TABLE IF EXISTS list_counts;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 12,
+            description: "add_attachments",
+            sql: r#"
+                -- Files copied into the app's attachments directory, `stored_path` is
+                -- relative to it
+                CREATE TABLE IF NOT EXISTS attachments (
+                    id TEXT PRIMARY KEY,
+                    task_id TEXT NOT NULL,
+                    filename TEXT NOT NULL,
+                    stored_path TEXT NOT NULL,
+                    mime TEXT,
+                    size INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_attachments_task_id ON attachments(task_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "drop_attachments",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_attachments_task_id;
+                DROP TABLE IF EXISTS attachments;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 13,
+            description: "add_task_rollover",
+            sql: r#"
+                -- Start of the day an overdue task was carried over to "today" with its date
+                -- kept (`preferences.rolloverMode` = keep-date)
+                ALTER TABLE tasks ADD COLUMN rolled_over_at INTEGER;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "drop_task_rollover",
+            sql: r#"
+                ALTER TABLE tasks DROP COLUMN rolled_over_at;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 14,
+            description: "add_focus_sessions",
+            sql: r#"
+                -- `last_seen_at` is a heartbeat of the running session, one the app never
+                -- stopped is closed there on the next startup. There's no foreign key so
+                -- archiving a task keeps its sessions, the triggers drop them once the task
+                -- is gone from both tables.
+                CREATE TABLE IF NOT EXISTS focus_sessions (
+                    id TEXT PRIMARY KEY,
+                    task_id TEXT NOT NULL,
+                    started_at INTEGER NOT NULL,
+                    ended_at INTEGER,
+                    duration_seconds INTEGER,
+                    last_seen_at INTEGER NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_focus_sessions_task_id ON focus_sessions(task_id);
+                -- At most one open session
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_focus_sessions_open
+                    ON focus_sessions((ended_at IS NULL)) WHERE ended_at IS NULL;
+
+                CREATE TRIGGER IF NOT EXISTS focus_sessions_task_deleted AFTER DELETE ON tasks
+                WHEN NOT EXISTS (SELECT 1 FROM archived_tasks WHERE id = old.id) BEGIN
+                    DELETE FROM focus_sessions WHERE task_id = old.id;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS focus_sessions_archived_deleted AFTER DELETE ON archived_tasks
+                WHEN NOT EXISTS (SELECT 1 FROM tasks WHERE id = old.id) BEGIN
+                    DELETE FROM focus_sessions WHERE task_id = old.id;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "drop_focus_sessions",
+            sql: r#"
+                DROP TRIGGER IF EXISTS focus_sessions_archived_deleted;
+                DROP TRIGGER IF EXISTS focus_sessions_task_deleted;
+                DROP INDEX IF EXISTS idx_focus_sessions_open;
+                DROP INDEX IF EXISTS idx_focus_sessions_task_id;
+                DROP TABLE IF EXISTS focus_sessions;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 15,
+            description: "add_task_sort_keys",
+            sql: r#"
+                -- Fractional ordering keys (see sort_keys.rs), compared as plain strings.
+                -- Existing tasks get their rank within the list, in "order".
+                ALTER TABLE tasks ADD COLUMN sort_key TEXT;
+
+                UPDATE tasks SET sort_key = printf('%08d', ranked.rank) || 'i'
+                FROM (
+                    SELECT id, ROW_NUMBER() OVER (
+                        PARTITION BY list_id ORDER BY "order", created_at, id
+                    ) AS rank
+                    FROM tasks
+                ) AS ranked
+                WHERE ranked.id = tasks.id;
+
+                CREATE INDEX IF NOT EXISTS idx_tasks_list_sort_key ON tasks(list_id, sort_key);
+
+                -- The frontend only writes "order". A task it moves loses its key, the next
+                -- native insert into the list sorts it back in by "order".
+                CREATE TRIGGER IF NOT EXISTS tasks_sort_key_stale
+                AFTER UPDATE OF "order", list_id ON tasks
+                WHEN (new."order" IS NOT old."order" OR new.list_id IS NOT old.list_id)
+                     AND new.sort_key IS old.sort_key
+                BEGIN
+                    UPDATE tasks SET sort_key = NULL WHERE id = new.id;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "drop_task_sort_keys",
+            sql: r#"
+                DROP TRIGGER IF EXISTS tasks_sort_key_stale;
+                DROP INDEX IF EXISTS idx_tasks_list_sort_key;
+                ALTER TABLE tasks DROP COLUMN sort_key;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 16,
+            description: "create_outbox",
+            sql: r#"
+                -- Change feed for sync consumers (see change_feed.rs). The triggers write it
+                -- in the same transaction as the change itself, whoever made it. Consumers
+                -- delete what they have handled with `ack_outbox`.
+                CREATE TABLE IF NOT EXISTS outbox (
+                    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                    entity_table TEXT NOT NULL,
+                    entity_id TEXT NOT NULL,
+                    op TEXT NOT NULL,
+                    updated_at INTEGER NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_outbox_updated_at ON outbox(updated_at);
+
+                CREATE TRIGGER IF NOT EXISTS outbox_lists_insert AFTER INSERT ON lists BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('lists', new.id, 'insert', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_lists_update AFTER UPDATE ON lists BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('lists', new.id, 'update', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_lists_delete AFTER DELETE ON lists BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('lists', old.id, 'delete', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_tasks_insert AFTER INSERT ON tasks BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('tasks', new.id, 'insert', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_tasks_update AFTER UPDATE ON tasks BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('tasks', new.id, 'update', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_tasks_delete AFTER DELETE ON tasks BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('tasks', old.id, 'delete', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_subtasks_insert AFTER INSERT ON subtasks BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('subtasks', new.id, 'insert', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_subtasks_update AFTER UPDATE ON subtasks BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('subtasks', new.id, 'update', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS outbox_subtasks_delete AFTER DELETE ON subtasks BEGIN
+                    INSERT INTO outbox (entity_table, entity_id, op, updated_at)
+                    VALUES ('subtasks', old.id, 'delete', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "drop_outbox",
+            sql: r#"
+                DROP TRIGGER IF EXISTS outbox_lists_insert;
+                DROP TRIGGER IF EXISTS outbox_lists_update;
+                DROP TRIGGER IF EXISTS outbox_lists_delete;
+                DROP TRIGGER IF EXISTS outbox_tasks_insert;
+                DROP TRIGGER IF EXISTS outbox_tasks_update;
+                DROP TRIGGER IF EXISTS outbox_tasks_delete;
+                DROP TRIGGER IF EXISTS outbox_subtasks_insert;
+                DROP TRIGGER IF EXISTS outbox_subtasks_update;
+                DROP TRIGGER IF EXISTS outbox_subtasks_delete;
+                DROP TABLE IF EXISTS outbox;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 17,
+            description: "add_list_pinned",
+            sql: r#"
+                -- Pinned lists come first in the sidebar, "order" stays the manual order
+                -- within both groups (see lists.rs)
+                ALTER TABLE lists ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "drop_list_pinned",
+            sql: r#"
+                ALTER TABLE lists DROP COLUMN pinned;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 18,
+            description: "create_task_dependencies",
+            sql: r#"
+                -- `task_id` is blocked until `depends_on_id` is completed (see dependencies.rs)
+                CREATE TABLE IF NOT EXISTS task_dependencies (
+                    task_id TEXT NOT NULL,
+                    depends_on_id TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    PRIMARY KEY (task_id, depends_on_id),
+                    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE,
+                    FOREIGN KEY (depends_on_id) REFERENCES tasks (id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_task_dependencies_depends_on_id ON task_dependencies(depends_on_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "drop_task_dependencies",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_task_dependencies_depends_on_id;
+                DROP TABLE IF EXISTS task_dependencies;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 19,
+            description: "add_task_time_tracking",
+            sql: r#"
+                -- Planned and spent minutes (see time_tracking.rs). The archive gets them too
+                -- so archived tasks still count towards estimation accuracy.
+                ALTER TABLE tasks ADD COLUMN estimate_minutes INTEGER;
+                ALTER TABLE tasks ADD COLUMN actual_minutes INTEGER;
+                ALTER TABLE archived_tasks ADD COLUMN estimate_minutes INTEGER;
+                ALTER TABLE archived_tasks ADD COLUMN actual_minutes INTEGER;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "drop_task_time_tracking",
+            sql: r#"
+                ALTER TABLE archived_tasks DROP COLUMN actual_minutes;
+                ALTER TABLE archived_tasks DROP COLUMN estimate_minutes;
+                ALTER TABLE tasks DROP COLUMN actual_minutes;
+                ALTER TABLE tasks DROP COLUMN estimate_minutes;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 20,
+            description: "create_settings_history",
+            sql: r#"
+                -- Earlier values of each settings row (see settings::history), filled by
+                -- triggers so the frontend's own writes are recorded too. The newest 50 per
+                -- key are kept, a stored AI key is blanked like in the row itself.
+                CREATE TABLE IF NOT EXISTS settings_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    key TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    -- When the value was written and when it was replaced or deleted
+                    updated_at INTEGER NOT NULL,
+                    replaced_at INTEGER NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_settings_history_key ON settings_history(key, id);
+
+                -- INSERT OR REPLACE doesn't fire delete triggers, the row it replaces is read
+                -- before the insert
+                CREATE TRIGGER IF NOT EXISTS settings_history_insert BEFORE INSERT ON settings
+                WHEN EXISTS (SELECT 1 FROM settings WHERE key = new.key AND value IS NOT new.value)
+                BEGIN
+                    INSERT INTO settings_history (key, value, updated_at, replaced_at)
+                    SELECT key,
+                           CASE WHEN key = 'ai' AND json_valid(value) THEN json_set(value, '$.apiKey', '') ELSE value END,
+                           updated_at, CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)
+                    FROM settings WHERE key = new.key;
+                    DELETE FROM settings_history WHERE key = new.key AND id NOT IN (
+                        SELECT id FROM settings_history WHERE key = new.key ORDER BY id DESC LIMIT 50
+                    );
+                END;
+                CREATE TRIGGER IF NOT EXISTS settings_history_update AFTER UPDATE OF value ON settings
+                WHEN old.value IS NOT new.value
+                BEGIN
+                    INSERT INTO settings_history (key, value, updated_at, replaced_at)
+                    VALUES (
+                        old.key,
+                        CASE WHEN old.key = 'ai' AND json_valid(old.value) THEN json_set(old.value, '$.apiKey', '') ELSE old.value END,
+                        old.updated_at,
+                        CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)
+                    );
+                    DELETE FROM settings_history WHERE key = old.key AND id NOT IN (
+                        SELECT id FROM settings_history WHERE key = old.key ORDER BY id DESC LIMIT 50
+                    );
+                END;
+                CREATE TRIGGER IF NOT EXISTS settings_history_delete AFTER DELETE ON settings
+                BEGIN
+                    INSERT INTO settings_history (key, value, updated_at, replaced_at)
+                    VALUES (
+                        old.key,
+                        CASE WHEN old.key = 'ai' AND json_valid(old.value) THEN json_set(old.value, '$.apiKey', '') ELSE old.value END,
+                        old.updated_at,
+                        CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)
+                    );
+                    DELETE FROM settings_history WHERE key = old.key AND id NOT IN (
+                        SELECT id FROM settings_history WHERE key = old.key ORDER BY id DESC LIMIT 50
+                    );
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "drop_settings_history",
+            sql: r#"
+                DROP TRIGGER IF EXISTS settings_history_delete;
+                DROP TRIGGER IF EXISTS settings_history_update;
+                DROP TRIGGER IF EXISTS settings_history_insert;
+                DROP INDEX IF EXISTS idx_settings_history_key;
+                DROP TABLE IF EXISTS settings_history;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 21,
+            description: "add_tasks_updated_at_index",
+            sql: r#"
+                -- Stale tasks are found by how long ago they were last touched (smart_views.rs)
+                CREATE INDEX IF NOT EXISTS idx_tasks_updated_at ON tasks(updated_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "drop_tasks_updated_at_index",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_tasks_updated_at;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 22,
+            description: "create_templates",
+            sql: r#"
+                -- Lists saved for reuse (templates.rs). The tasks and their subtasks are a JSON
+                -- array, due dates stored as offsets from the day the template is used.
+                CREATE TABLE IF NOT EXISTS templates (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    list_name TEXT NOT NULL,
+                    icon TEXT,
+                    color TEXT,
+                    tasks TEXT NOT NULL DEFAULT '[]',
+                    created_at INTEGER NOT NULL,
+                    updated_at INTEGER NOT NULL
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "drop_templates",
+            sql: r#"
+                DROP TABLE IF EXISTS templates;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 23,
+            description: "create_task_comments",
+            sql: r#"
+                -- Notes on a task and a timeline of what happened to it (comments.rs). `kind`
+                -- is 'comment' for what the user wrote, the triggers below add the others
+                -- with the old and new value as JSON in `body`, for the frontend's writes too.
+                CREATE TABLE IF NOT EXISTS task_comments (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL,
+                    kind TEXT NOT NULL DEFAULT 'comment',
+                    body TEXT NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_task_comments_task_id ON task_comments(task_id, created_at);
+
+                CREATE TRIGGER IF NOT EXISTS task_comments_completed AFTER UPDATE OF completed ON tasks
+                WHEN old.completed IS NOT new.completed AND new.deleted_at IS NULL
+                BEGIN
+                    INSERT INTO task_comments (task_id, kind, body, created_at)
+                    VALUES (new.id, CASE WHEN new.completed = 1 THEN 'completed' ELSE 'reopened' END, '{}',
+                            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS task_comments_moved AFTER UPDATE OF list_id ON tasks
+                WHEN old.list_id IS NOT new.list_id AND new.deleted_at IS NULL
+                BEGIN
+                    INSERT INTO task_comments (task_id, kind, body, created_at)
+                    VALUES (new.id, 'moved', json_object('from', old.list_name, 'to', new.list_name),
+                            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+                CREATE TRIGGER IF NOT EXISTS task_comments_due_date AFTER UPDATE OF due_date ON tasks
+                WHEN old.due_date IS NOT new.due_date AND new.deleted_at IS NULL
+                BEGIN
+                    INSERT INTO task_comments (task_id, kind, body, created_at)
+                    VALUES (new.id, 'dueDateChanged', json_object('from', old.due_date, 'to', new.due_date),
+                            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "drop_task_comments",
+            sql: r#"
+                DROP TRIGGER IF EXISTS task_comments_due_date;
+                DROP TRIGGER IF EXISTS task_comments_moved;
+                DROP TRIGGER IF EXISTS task_comments_completed;
+                DROP INDEX IF EXISTS idx_task_comments_task_id;
+                DROP TABLE IF EXISTS task_comments;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 24,
+            description: "create_day_plans",
+            sql: r#"
+                -- The last plan from `plan_my_day` (day_plan.rs) as JSON, a single row
+                CREATE TABLE IF NOT EXISTS day_plans (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    plan TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 24,
+            description: "drop_day_plans",
+            sql: "DROP TABLE IF EXISTS day_plans;",
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 25,
+            description: "create_task_versions",
+            sql: r#"
+                -- Earlier title, content and due date of each task (task_versions.rs), filled
+                -- by a trigger so the frontend's writes are recorded too. The newest
+                -- `preferences.taskVersionLimit` (default 20) per task are kept.
+                CREATE TABLE IF NOT EXISTS task_versions (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    task_id TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    content TEXT,
+                    due_date INTEGER,
+                    -- When these values were replaced
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_task_versions_task_id ON task_versions(task_id, id);
+
+                CREATE TRIGGER IF NOT EXISTS task_versions_update AFTER UPDATE OF title, content, due_date ON tasks
+                WHEN old.title IS NOT new.title OR old.content IS NOT new.content OR old.due_date IS NOT new.due_date
+                BEGIN
+                    INSERT INTO task_versions (task_id, title, content, due_date, created_at)
+                    VALUES (old.id, old.title, old.content, old.due_date,
+                            CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+                    DELETE FROM task_versions WHERE task_id = old.id AND id NOT IN (
+                        SELECT id FROM task_versions WHERE task_id = old.id ORDER BY id DESC
+                        LIMIT MAX(1, COALESCE((
+                            SELECT CAST(json_extract(value, '$.taskVersionLimit') AS INTEGER)
+                            FROM settings WHERE key = 'preferences' AND json_valid(value)
+                        ), 20))
+                    );
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 25,
+            description: "drop_task_versions",
+            sql: r#"
+                DROP TRIGGER IF EXISTS task_versions_update;
+                DROP INDEX IF EXISTS idx_task_versions_task_id;
+                DROP TABLE IF EXISTS task_versions;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 26,
+            description: "add_list_hidden",
+            sql: r#"
+                -- Hidden lists keep their tasks but are left out of the sidebar, counts and
+                -- smart views (see lists.rs), unlike a deleted list they never reach the trash
+                ALTER TABLE lists ADD COLUMN hidden INTEGER NOT NULL DEFAULT 0;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 26,
+            description: "drop_list_hidden",
+            sql: r#"
+                ALTER TABLE lists DROP COLUMN hidden;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 27,
+            description: "add_archived_task_comments",
+            sql: r#"
+                -- Comments and timeline of an archived task, deleting it from `tasks` would
+                -- cascade to them. `archive::unarchive` puts them back.
+                ALTER TABLE archived_tasks ADD COLUMN comments TEXT NOT NULL DEFAULT '[]'; -- JSON array of task_comments rows
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 27,
+            description: "drop_archived_task_comments",
+            sql: r#"
+                ALTER TABLE archived_tasks DROP COLUMN comments;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 28,
+            description: "add_archived_task_versions",
+            sql: r#"
+                -- Earlier versions of an archived task, kept for the same reason as its comments
+                ALTER TABLE archived_tasks ADD COLUMN versions TEXT NOT NULL DEFAULT '[]'; -- JSON array of task_versions rows
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 28,
+            description: "drop_archived_task_versions",
+            sql: r#"
+                ALTER TABLE archived_tasks DROP COLUMN versions;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 29,
+            description: "add_recurrence_materialized_until",
+            sql: r#"
+                -- Due date of the last occurrence generated for a series, kept on its head so
+                -- postponing an instance doesn't move where `recurrence::materialize` resumes.
+                -- Existing series start from their latest instance.
+                ALTER TABLE tasks ADD COLUMN recurrence_materialized_until INTEGER;
+                UPDATE tasks SET recurrence_materialized_until = (
+                    SELECT MAX(due_date) FROM tasks i WHERE i.recurrence_parent_id = tasks.id
+                )
+                WHERE recurrence IS NOT NULL AND recurrence_parent_id IS NULL;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 29,
+            description: "drop_recurrence_materialized_until",
+            sql: r#"
+                ALTER TABLE tasks DROP COLUMN recurrence_materialized_until;
+            "#,
+            kind: MigrationKind::Down,
+        },
+    ]
+}
+
+// Highest schema version this build knows how to migrate to
+pub(crate) fn latest_schema_version() -> i64 {
+    migrations()
+        .iter()
+        .filter(|m| matches!(m.kind, MigrationKind::Up))
+        .map(|m| m.version)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // Swap a staged restore in and carry out a staged move before anything opens the
+    // database, then have the sql plugin preload (and migrate) it where it now lives. The
+    // URL isn't known until now, so it goes into the config rather than tauri.conf.json.
+    let mut context = tauri::generate_context!();
+    let url = match relocate::prepare(context.config()) {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
-    ];
+    };
+    context
+        .config_mut()
+        .plugins
+        .0
+        .insert("sql".to_string(), serde_json::json!({ "preload": [url] }));
+
+    let builder = tauri::Builder::default();
 
-    tauri::Builder::default()
+    // A second launch (e.g. from a tada:// link) hands its arguments to the running
+    // instance and exits, the deep-link feature forwards the url to `on_open_url`
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }));
+
+    builder
         .manage(AppState {
             is_quitting: AtomicBool::new(false),
         })
+        .manage(reminders::ReminderScheduler::default())
+        .manage(deep_link::PendingNavigation::default())
+        .manage(local_api::LocalApi::default())
         .plugin(tauri_plugin_http::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_notification::init())
+        // The migrations are keyed by the URL the frontend loads (`get_database_url`)
         .plugin(
             tauri_plugin_sql::Builder::default()
-                .add_migrations("sqlite:tada.db", migrations)
+                .add_migrations(&url, migrations())
                 .build(),
         )
+        .invoke_handler(tauri::generate_handler![
+            search::search_tasks,
+            search::replace_in_tasks,
+            recurrence::materialize_recurrences,
+            secrets::set_secret,
+            secrets::get_secret,
+            backup::backup_database,
+            backup::restore_database,
+            backup::export_sqlite,
+            relocate::relocate_database,
+            relocate::get_database_location,
+            relocate::get_database_url,
+            maintenance::get_db_info,
+            maintenance::get_version_info,
+            maintenance::vacuum_database,
+            maintenance::analyze_database,
+            reset::factory_reset,
+            export::export_all,
+            export::import_all,
+            groups::recompute_groups,
+            groups::get_grouped_tasks,
+            ics::export_ics,
+            reminders::reschedule_reminders,
+            reminders::get_upcoming_reminders,
+            working_hours::next_available_time,
+            stats::get_statistics,
+            stats::get_completion_heatmap,
+            stats::get_burndown,
+            stats::current_streak,
+            stats::get_overdue_aging,
+            stats::get_weekly_score,
+            completion_log::export_completion_log,
+            trash::trash_task,
+            trash::restore_task,
+            trash::purge_trash,
+            trash::clear_completed,
+            rollback::migrate_to_version,
+            query_log::get_query_stats,
+            csv_import::import_csv,
+            organize::reorder_tasks,
+            organize::move_task,
+            organize::merge_lists,
+            organize::promote_subtask,
+            organize::demote_task,
+            organize::resequence_list,
+            organize::resequence_all,
+            sort_keys::insert_task_between,
+            sort_keys::rebalance_sort_keys,
+            settings::get_setting,
+            settings::set_setting,
+            settings::export_settings,
+            settings::get_settings_history,
+            counts::get_list_counts,
+            lists::get_lists,
+            lists::pin_list,
+            lists::unpin_list,
+            lists::hide_list,
+            lists::unhide_list,
+            lists::delete_list,
+            lists::get_sidebar_data,
+            dependencies::add_dependency,
+            dependencies::remove_dependency,
+            dependencies::get_blocked_tasks,
+            comments::add_comment,
+            comments::list_comments,
+            comments::delete_comment,
+            task_versions::get_task_versions,
+            task_versions::revert_task,
+            time_tracking::set_estimate,
+            time_tracking::log_time,
+            time_tracking::get_time_accuracy,
+            time_tracking::get_workload,
+            attachments::add_attachment,
+            attachments::list_attachments,
+            attachments::remove_attachment,
+            rollover::rollover_overdue,
+            sync::sync_webdav,
+            query::list_tasks_page,
+            query::query_tasks,
+            markdown::export_markdown,
+            tag_export::export_by_tag,
+            markdown_import::import_markdown,
+            opml_import::import_opml,
+            tags::list_tags,
+            tags::rename_tag,
+            tags::add_tag_to_tasks,
+            tags::remove_tag_from_tasks,
+            tags::sanitize_tags,
+            integrity::check_integrity,
+            integrity::repair_integrity,
+            integrity::validate_subtask_links,
+            integrity::fix_subtask_links,
+            ai::generate_summary,
+            ai::generate_summary_stream,
+            ai::extract_tasks_from_text,
+            ai::suggest_tags,
+            day_plan::plan_my_day,
+            day_plan::get_day_plan,
+            report::export_summary_report,
+            quick_add::quick_add_task,
+            quick_add::set_quick_add_shortcut,
+            bulk_add::create_tasks_bulk,
+            tray::refresh_tray,
+            deep_link::take_pending_navigation,
+            journal::install_journal,
+            journal::undo_last,
+            journal::redo_last,
+            change_feed::drain_outbox,
+            change_feed::ack_outbox,
+            updater::check_for_update,
+            updater::install_update,
+            duplicate::duplicate_task,
+            templates::save_as_template,
+            templates::get_templates,
+            templates::delete_template,
+            templates::instantiate_template,
+            dedupe::find_duplicate_tasks,
+            dedupe::merge_duplicate_tasks,
+            dedupe::merge_tasks,
+            natural_dates::parse_due_date,
+            google_tasks::import_google_tasks,
+            ticktick::import_ticktick,
+            todoist::import_todoist,
+            share::export_list,
+            share::import_list,
+            snooze::snooze_task,
+            snooze::snooze_overdue,
+            snooze::shift_due_dates,
+            snooze::assign_due_date,
+            priority::set_priority,
+            detail::get_task_detail,
+            archive::archive_completed,
+            archive::list_archived,
+            archive::unarchive_task,
+            focus::get_focus_tasks,
+            focus_sessions::start_focus,
+            focus_sessions::stop_focus,
+            focus_sessions::get_focus_time,
+            list_defaults::get_list_defaults,
+            list_defaults::set_list_defaults,
+            smart_views::get_smart_view,
+            smart_views::get_stale_tasks,
+            completion::complete_task,
+            completion::uncomplete_task,
+            completion::set_subtasks_completed,
+        ])
         .setup(|app| {
-            // Create a tray menu
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let show_i = MenuItem::with_id(app, "show", "Show Tada", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
-
-            let icon_bytes = include_bytes!("../icons/tray-icon.png");
-            let icon = Image::from_bytes(icon_bytes).expect("Failed to load tray icon");
-
-            // Build the tray icon
-            let tray_builder = TrayIconBuilder::with_id("tray")
-                .menu(&menu)
-                .icon(icon)
-                .show_menu_on_left_click(false)
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => {
-                        // User clicked the exit button of the tray
-                        let state = app.state::<AppState>();
-                        state.is_quitting.store(true, Ordering::Relaxed);
-                        app.exit(0);
-                    }
-                    "show" => {
-                        // User clicked "Display"
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                    _ => {}
-                })
-                .on_tray_icon_event(|tray, event| match event {
-                    // Left-click the tray icon on Windows/Linux to display the window
-                    TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        ..
-                    } => {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                    _ => {}
-                });
+            db::init(app.handle())?;
 
-            #[cfg(target_os = "macos")]
-            let tray_builder = tray_builder.icon_as_template(true);
+            // Move a plaintext AI API key from older versions into the secret store
+            if let Err(e) = db::connect(app.handle())
+                .and_then(|conn| secrets::migrate_plaintext_api_key(&conn))
+            {
+                eprintln!("Failed to migrate the AI API key: {}", e);
+            }
+
+            // Generate upcoming instances of recurring tasks before the frontend loads them,
+            // unless they are only added as the previous one is completed
+            if let Err(e) = db::connect(app.handle()).and_then(|mut conn| {
+                if !recurrence::spawns_on_complete(&conn) {
+                    recurrence::materialize(&mut conn, recurrence::STARTUP_HORIZON_DAYS)?;
+                }
+                Ok(())
+            }) {
+                eprintln!("Failed to materialize recurring tasks: {}", e);
+            }
+
+            // Drop trashed rows that are past the retention window
+            if let Err(e) = db::connect(app.handle()).and_then(|mut conn| {
+                let days = trash::retention_days(&conn);
+                trash::purge(&mut conn, &attachments::attachments_dir(app.handle())?, days)
+            }) {
+                eprintln!("Failed to purge the trash: {}", e);
+            }
+
+            // Only reported here, the frontend asks the user before calling `repair_integrity`
+            match db::connect(app.handle())
+                .and_then(|conn| integrity::check(&conn).map_err(|e| e.to_string()))
+            {
+                Ok(report) if report.issue_count() > 0 => {
+                    eprintln!("Integrity check found {} issues", report.issue_count())
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to check database integrity: {}", e),
+            }
+
+            // Buckets stored by an earlier day are stale by now
+            if let Err(e) = db::connect(app.handle())
+                .and_then(|mut conn| groups::recompute(&mut conn, dates::now_ms()).map_err(|e| e.to_string()))
+            {
+                eprintln!("Failed to recompute task groups: {}", e);
+            }
 
-            tray_builder.build(app)?;
+            // A session still open here was running when the app last quit or crashed
+            if let Err(e) = db::connect(app.handle())
+                .and_then(|conn| focus_sessions::close_dangling(&conn).map_err(|e| e.to_string()))
+            {
+                eprintln!("Failed to close dangling focus sessions: {}", e);
+            }
+
+            // Changes nobody picked up within the retention window
+            if let Err(e) = db::connect(app.handle())
+                .and_then(|conn| change_feed::prune(&conn, dates::now_ms()).map_err(|e| e.to_string()))
+            {
+                eprintln!("Failed to prune the outbox: {}", e);
+            }
+
+            reminders::start(app.handle().clone());
+            groups::start(app.handle().clone());
+            focus_sessions::start_heartbeat(app.handle().clone());
+            change_feed::start(app.handle().clone());
+            local_api::start(app.handle());
+
+            // System-wide shortcut that opens the quick add window
+            #[cfg(desktop)]
+            {
+                app.handle()
+                    .plugin(tauri_plugin_global_shortcut::Builder::new().build())?;
+                if let Err(e) = quick_add::init(app.handle()) {
+                    eprintln!("{}", e);
+                }
+            }
+
+            // The updater plugin can't start without a signing key, builds that don't ship
+            // one simply have no updates
+            #[cfg(desktop)]
+            {
+                if updater::is_configured(app.handle()) {
+                    app.handle()
+                        .plugin(tauri_plugin_updater::Builder::new().build())?;
+                    updater::check_on_startup(app.handle());
+                }
+            }
+
+            if let Err(e) = deep_link::init(app.handle()) {
+                eprintln!("Failed to set up deep links: {}", e);
+            }
+
+            tray::create(app)?;
+            if let Err(e) = tray::refresh(app.handle()) {
+                eprintln!("Failed to refresh the tray: {}", e);
+            }
 
             Ok(())
         })
@@ -195,8 +1450,7 @@ pub fn run() {
                 }
             }
         })
-        // .plugin(tauri_plugin_updater::Builder::new().build())
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("error while building tauri application")
         .run(|app_handle, event| match event {
             // To handle macOS, click the Dock icon to reopen the window
@@ -207,6 +1461,7 @@ pub fn run() {
                     let _ = window.set_focus();
                 }
             }
+            tauri::RunEvent::Exit => local_api::stop(app_handle),
             _ => {}
         });
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+use crate::tasks::{self, NewTask};
+use crate::{dates, db};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+// A week, the longest lead time the reminder picker offers
+const MAX_REMINDER_OFFSET_MINUTES: i64 = 7 * 24 * 60;
+
+// `lists.defaults`, applied to tasks created in the list by native commands
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDefaults {
+    #[serde(default)]
+    pub priority: Option<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub reminder_offset_minutes: Option<i64>,
+}
+
+impl ListDefaults {
+    pub fn validate(self) -> Result<Self, String> {
+        if let Some(priority) = self.priority && !(1..=3).contains(&priority) {
+            return Err(format!("Priority must be between 1 and 3, got {}", priority));
+        }
+        if let Some(offset) = self.reminder_offset_minutes && !(0..=MAX_REMINDER_OFFSET_MINUTES).contains(&offset) {
+            return Err(format!(
+                "Reminder offset must be between 0 and {} minutes, got {}",
+                MAX_REMINDER_OFFSET_MINUTES, offset
+            ));
+        }
+        Ok(Self {
+            tags: tasks::normalize_tags(self.tags),
+            ..self
+        })
+    }
+
+    // Fill in what the task doesn't set itself
+    pub fn apply(&self, task: &mut NewTask) {
+        if task.priority.is_none() {
+            task.priority = self.priority;
+        }
+        if task.tags.is_empty() {
+            task.tags = self.tags.clone();
+        }
+        if task.reminder_offset_minutes.is_none() {
+            task.reminder_offset_minutes = self.reminder_offset_minutes;
+        }
+    }
+}
+
+// Defaults of a live list. Unreadable JSON, or values that wouldn't pass `validate`, read
+// as no defaults.
+pub fn load(conn: &Connection, list_id: &str) -> rusqlite::Result<Option<ListDefaults>> {
+    let raw: Option<Option<String>> = conn
+        .query_row(
+            "SELECT defaults FROM lists WHERE id = ?1 AND deleted_at IS NULL",
+            [list_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    Ok(Some(
+        raw.and_then(|raw| serde_json::from_str::<ListDefaults>(&raw).ok())
+            .and_then(|defaults| defaults.validate().ok())
+            .unwrap_or_default(),
+    ))
+}
+
+#[tauri::command]
+pub async fn get_list_defaults(app: AppHandle, list_id: String) -> Result<ListDefaults, String> {
+    let conn = db::connect(&app)?;
+    load(&conn, &list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))
+}
+
+// Returns the defaults as stored, with tags cleaned up
+#[tauri::command]
+pub async fn set_list_defaults(app: AppHandle, list_id: String, defaults: ListDefaults) -> Result<ListDefaults, String> {
+    let defaults = defaults.validate()?;
+    let json = serde_json::to_string(&defaults).map_err(|e| e.to_string())?;
+    let conn = db::connect(&app)?;
+    let updated = conn
+        .execute(
+            "UPDATE lists SET defaults = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+            params![json, dates::now_ms(), list_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("List {} not found", list_id));
+    }
+    Ok(defaults)
+}
@@ -0,0 +1,214 @@
+use crate::export::{self, INBOX_LIST_ID};
+use crate::{dates, db, journal, tasks, tray};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct List {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub order: Option<i64>,
+    pub pinned: bool,
+    pub hidden: bool,
+}
+
+impl List {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            icon: row.get("icon")?,
+            color: row.get("color")?,
+            order: row.get("order")?,
+            pinned: row.get::<_, i64>("pinned")? != 0,
+            hidden: row.get::<_, i64>("hidden")? != 0,
+        })
+    }
+}
+
+// Condition to add to a task query that leaves out the tasks of hidden lists, unless
+// `include_hidden`. List-less tasks always stay.
+pub fn hidden_filter(include_hidden: bool) -> &'static str {
+    if include_hidden {
+        ""
+    } else {
+        " AND (list_id IS NULL OR list_id NOT IN (SELECT id FROM lists WHERE hidden = 1))"
+    }
+}
+
+// Live lists by "order", hidden ones only with `include_hidden`. With `grouped` the pinned
+// ones come first, each group keeps the manual order, so unpinning a list puts it back
+// where it was.
+pub fn all(conn: &Connection, grouped: bool, include_hidden: bool) -> rusqlite::Result<Vec<List>> {
+    export::ensure_inbox(conn)?;
+    let pinned_first = if grouped { "pinned DESC, " } else { "" };
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT id, name, icon, color, "order", pinned, hidden FROM lists
+           WHERE deleted_at IS NULL AND (?1 OR hidden = 0)
+           ORDER BY {}"order", name"#,
+        pinned_first
+    ))?;
+    let rows = stmt.query_map([include_hidden], List::from_row)?;
+    rows.collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidebarList {
+    #[serde(flatten)]
+    pub list: List,
+    pub incomplete: i64,
+    pub total: i64,
+}
+
+// Everything the sidebar shows in one query: the live lists that aren't hidden, pinned ones
+// first, with their counts from `list_counts` (see counts.rs). The inbox is always there,
+// empty or not.
+pub fn sidebar(conn: &Connection) -> rusqlite::Result<Vec<SidebarList>> {
+    export::ensure_inbox(conn)?;
+    let mut stmt = conn.prepare(
+        r#"SELECT l.id, l.name, l.icon, l.color, l."order", l.pinned, l.hidden,
+                  COALESCE(c.incomplete, 0) AS incomplete, COALESCE(c.total, 0) AS total
+           FROM lists l LEFT JOIN list_counts c ON c.list_id = l.id
+           WHERE l.deleted_at IS NULL AND l.hidden = 0
+           ORDER BY l.pinned DESC, l."order", l.name"#,
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SidebarList {
+            list: List::from_row(row)?,
+            incomplete: row.get("incomplete")?,
+            total: row.get("total")?,
+        })
+    })?;
+    rows.collect()
+}
+
+// Only the flag changes, "order" is left alone
+pub fn set_pinned(conn: &Connection, id: &str, pinned: bool) -> Result<(), String> {
+    let changed = conn
+        .execute(
+            "UPDATE lists SET pinned = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+            params![pinned, dates::now_ms(), id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("List {} not found", id));
+    }
+    Ok(())
+}
+
+// Hiding leaves the list and its tasks as they are, it only drops out of the sidebar,
+// counts and smart views until unhidden. The inbox catches new tasks and can't be hidden.
+pub fn set_hidden(conn: &Connection, id: &str, hidden: bool) -> Result<(), String> {
+    if hidden && id == INBOX_LIST_ID {
+        return Err("The inbox can't be hidden".to_string());
+    }
+    let changed = conn
+        .execute(
+            "UPDATE lists SET hidden = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+            params![hidden, dates::now_ms(), id],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("List {} not found", id));
+    }
+    Ok(())
+}
+
+// Move the list's tasks (trashed ones included, so a restore lands in the new list) and
+// move the list itself to the trash, like the sidebar does. Tasks left over in the old
+// 'Trash' list only lose their list id. Without `reassign_to` they go to the inbox.
+pub fn delete(conn: &mut Connection, id: &str, reassign_to: Option<&str>) -> Result<(), String> {
+    if id == INBOX_LIST_ID {
+        return Err("The inbox can't be deleted".to_string());
+    }
+    let target = reassign_to.unwrap_or(INBOX_LIST_ID);
+    if target == id {
+        return Err("Tasks can't be moved into the list being deleted".to_string());
+    }
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "delete_list").map_err(|e| e.to_string())?;
+    tasks::list_name(&tx, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", id))?;
+    if target == INBOX_LIST_ID {
+        export::ensure_inbox(&tx).map_err(|e| e.to_string())?;
+    }
+    let target_name = tasks::list_name(&tx, target)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", target))?;
+
+    tx.execute(
+        "UPDATE tasks SET list_id = CASE WHEN list_name = 'Trash' THEN NULL ELSE ?1 END,
+             list_name = CASE WHEN list_name = 'Trash' THEN list_name ELSE ?2 END, updated_at = ?3
+         WHERE list_id = ?4",
+        params![target, target_name, now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE lists SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_lists(app: AppHandle, grouped: bool, include_hidden: bool) -> Result<Vec<List>, String> {
+    let conn = db::connect(&app)?;
+    all(&conn, grouped, include_hidden).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn pin_list(app: AppHandle, id: String) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    set_pinned(&conn, &id, true)
+}
+
+#[tauri::command]
+pub async fn unpin_list(app: AppHandle, id: String) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    set_pinned(&conn, &id, false)
+}
+
+#[tauri::command]
+pub async fn hide_list(app: AppHandle, id: String) -> Result<(), String> {
+    db::connect(&app).and_then(|conn| set_hidden(&conn, &id, true))?;
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unhide_list(app: AppHandle, id: String) -> Result<(), String> {
+    db::connect(&app).and_then(|conn| set_hidden(&conn, &id, false))?;
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_list(app: AppHandle, id: String, reassign_to: Option<String>) -> Result<(), String> {
+    {
+        let mut conn = db::connect(&app)?;
+        delete(&mut conn, &id, reassign_to.as_deref())?;
+    }
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_sidebar_data(app: AppHandle) -> Result<Vec<SidebarList>, String> {
+    let conn = db::connect(&app)?;
+    sidebar(&conn).map_err(|e| e.to_string())
+}
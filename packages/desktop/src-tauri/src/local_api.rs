@@ -0,0 +1,272 @@
+use crate::settings::{self, Preferences};
+use crate::tasks::{Task, TASK_COLUMNS};
+use crate::{db, secrets};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+// Bearer token clients send, generated on the first start and read with `get_secret`
+pub const LOCAL_API_TOKEN: &str = "localApi.token";
+
+// How often the accept loop looks at the stop flag
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// A client gets this long to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+// Requests are a request line and a few headers, anything longer is refused
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+// `preferences.localApi`, read once on startup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 17345,
+        }
+    }
+}
+
+impl LocalApiSettings {
+    // Used by `Preferences::problems`
+    pub fn problems(&self) -> Vec<String> {
+        if self.port < 1024 {
+            return vec![format!("localApi.port must be 1024 or above, got {}", self.port)];
+        }
+        Vec::new()
+    }
+}
+
+// The running server, kept in Tauri state so `stop` can end it on exit
+#[derive(Default)]
+pub struct LocalApi {
+    stop: Arc<AtomicBool>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json(status: &'static str, body: String) -> Self {
+        Self { status, body }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Self::json(status, json!({ "error": message }).to_string())
+    }
+}
+
+fn token(app: &AppHandle) -> Result<String, String> {
+    let conn = db::connect(app)?;
+    if let Some(token) = secrets::load(&conn, LOCAL_API_TOKEN).filter(|token| !token.is_empty()) {
+        return Ok(token);
+    }
+    let token = Uuid::new_v4().simple().to_string();
+    secrets::store(&conn, LOCAL_API_TOKEN, &token)?;
+    Ok(token)
+}
+
+// Compares every byte so the time taken says nothing about how much of a guess was right
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Live tasks, of one list when `list` (an id or a name) is given
+fn tasks(app: &AppHandle, list: Option<&str>) -> Result<Vec<Task>, String> {
+    let conn = db::connect(app)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"SELECT {} FROM tasks
+               WHERE deleted_at IS NULL AND list_name != 'Trash' AND (?1 IS NULL OR list_id = ?1 OR list_name = ?1)
+               ORDER BY list_name, "order""#,
+            TASK_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([list], Task::from_row).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())
+}
+
+fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(&request).into_owned()))
+}
+
+fn respond(app: &AppHandle, expected_token: &str, request: &str) -> Response {
+    let mut lines = request.split("\r\n");
+    let mut request_line = lines.next().unwrap_or("").split(' ');
+    let method = request_line.next().unwrap_or("");
+    let target = request_line.next().unwrap_or("");
+    if method == "OPTIONS" {
+        return Response::json("204 No Content", String::new());
+    }
+    if method != "GET" {
+        return Response::error("405 Method Not Allowed", "Only GET is supported");
+    }
+
+    let authorized = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .filter_map(|(_, value)| value.trim().strip_prefix("Bearer "))
+        .any(|token| same_token(token.trim(), expected_token));
+    if !authorized {
+        return Response::error("401 Unauthorized", "Missing or wrong bearer token");
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/tasks" {
+        return Response::error("404 Not Found", "Unknown path, try /tasks");
+    }
+    let list = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == "list")
+        .map(|(_, value)| percent_decode(value))
+        .filter(|value| !value.is_empty());
+    match tasks(app, list.as_deref()).and_then(|tasks| serde_json::to_string(&tasks).map_err(|e| e.to_string())) {
+        Ok(body) => Response::json("200 OK", body),
+        Err(e) => {
+            eprintln!("Local API request failed: {}", e);
+            Response::error("500 Internal Server Error", "Failed to read the tasks")
+        }
+    }
+}
+
+fn handle(app: &AppHandle, expected_token: &str, mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let response = match read_request(&mut stream)? {
+        Some(request) => respond(app, expected_token, &request),
+        None => Response::error("413 Payload Too Large", "Request too large"),
+    };
+    // Browsers need the CORS headers for a dashboard page, the token still guards the data
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: Authorization\r\n\
+         Access-Control-Allow-Methods: GET, OPTIONS\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+// Serve `GET /tasks?list=<id or name>` on 127.0.0.1 when `preferences.localApi.enabled` is
+// set. Requests are answered one at a time from the native pool. Settings are read here
+// only, a change takes effect on the next launch.
+pub fn start(app: &AppHandle) {
+    let prefs = db::connect(app).and_then(|conn| settings::get_typed::<Preferences>(&conn).map_err(|e| e.to_string()));
+    let config = match prefs {
+        Ok(prefs) => prefs.local_api,
+        Err(e) => {
+            eprintln!("Failed to read the local API settings: {}", e);
+            return;
+        }
+    };
+    if !config.enabled {
+        return;
+    }
+    let expected_token = match token(app) {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("Local API not started, no token: {}", e);
+            return;
+        }
+    };
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, config.port)).and_then(|listener| {
+        listener.set_nonblocking(true)?;
+        Ok(listener)
+    }) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to start the local API on port {}: {}", config.port, e);
+            return;
+        }
+    };
+
+    let state = app.state::<LocalApi>();
+    let stop = state.stop.clone();
+    let app = app.clone();
+    let worker = thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(e) = handle(&app, &expected_token, stream) {
+                        eprintln!("Local API connection failed: {}", e);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => {
+                    eprintln!("Local API accept failed: {}", e);
+                    thread::sleep(POLL_INTERVAL);
+                }
+            }
+        }
+    });
+    if let Ok(mut thread) = state.thread.lock() {
+        *thread = Some(worker);
+    }
+}
+
+// Close the listener and wait for the request in flight, called on exit
+pub fn stop(app: &AppHandle) {
+    let Some(state) = app.try_state::<LocalApi>() else {
+        return;
+    };
+    state.stop.store(true, Ordering::Relaxed);
+    let worker = state.thread.lock().ok().and_then(|mut thread| thread.take());
+    if let Some(worker) = worker {
+        let _ = worker.join();
+    }
+}
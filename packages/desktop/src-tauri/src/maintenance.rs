@@ -0,0 +1,135 @@
+use crate::{db, rollback};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::AppHandle;
+
+// VACUUM rewrites the whole file, one at a time is plenty
+static MAINTENANCE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableInfo {
+    pub name: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbInfo {
+    // tada.db itself, the WAL holds writes that aren't checkpointed yet
+    pub file_size: u64,
+    pub wal_size: u64,
+    pub page_size: i64,
+    pub page_count: i64,
+    // Pages VACUUM would give back
+    pub freelist_count: i64,
+    pub tables: Vec<TableInfo>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub app_version: String,
+    // Last migration applied to this database, read from the plugin's `_sqlx_migrations`
+    pub schema_version: i64,
+    // Last migration this build ships, higher than `schema_version` only while migrating
+    // or after a failed migration
+    pub latest_schema_version: i64,
+    // Of the SQLite library compiled in
+    pub sqlite_version: String,
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn pragma(conn: &Connection, name: &str) -> rusqlite::Result<i64> {
+    conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))
+}
+
+// Row counts per table, leaving out SQLite's own tables and the full-text index's shadow tables
+pub fn info(conn: &Connection, db_path: &Path) -> rusqlite::Result<DbInfo> {
+    let names: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM pragma_table_list
+             WHERE schema = 'main' AND type IN ('table', 'virtual') AND name NOT LIKE 'sqlite_%'
+             ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut tables = Vec::with_capacity(names.len());
+    for name in names {
+        let rows = conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")), [], |row| {
+            row.get(0)
+        })?;
+        tables.push(TableInfo { name, rows });
+    }
+
+    let mut wal = db_path.as_os_str().to_owned();
+    wal.push("-wal");
+    Ok(DbInfo {
+        file_size: file_size(db_path),
+        wal_size: file_size(Path::new(&wal)),
+        page_size: pragma(conn, "page_size")?,
+        page_count: pragma(conn, "page_count")?,
+        freelist_count: pragma(conn, "freelist_count")?,
+        tables,
+    })
+}
+
+// Run one maintenance statement on a pooled connection of its own, off the async runtime.
+// VACUUM refuses to run inside a transaction and needs the write lock: a write that holds
+// it (the sql plugin's included) makes it wait out the busy timeout and fail with
+// `database is locked` rather than hang.
+async fn run(app: AppHandle, sql: &'static str) -> Result<DbInfo, String> {
+    if MAINTENANCE_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Database maintenance is already running".to_string());
+    }
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let conn = db::connect(&app)?;
+        if !conn.is_autocommit() {
+            return Err("The connection is inside a transaction".to_string());
+        }
+        conn.execute_batch(sql).map_err(|e| e.to_string())?;
+        info(&conn, &db::db_path(&app)?).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+    MAINTENANCE_RUNNING.store(false, Ordering::SeqCst);
+    result
+}
+
+#[tauri::command]
+pub async fn get_db_info(app: AppHandle) -> Result<DbInfo, String> {
+    let conn = db::connect(&app)?;
+    info(&conn, &db::db_path(&app)?).map_err(|e| e.to_string())
+}
+
+// What to paste into a bug report
+#[tauri::command]
+pub async fn get_version_info(app: AppHandle) -> Result<VersionInfo, String> {
+    let conn = db::connect(&app)?;
+    Ok(VersionInfo {
+        app_version: app.package_info().version.to_string(),
+        schema_version: rollback::applied_version(&conn).map_err(|e| e.to_string())?,
+        latest_schema_version: crate::latest_schema_version(),
+        sqlite_version: rusqlite::version().to_string(),
+    })
+}
+
+// In WAL mode the rewritten pages land in the WAL first, the checkpoint moves them into
+// tada.db so the file actually shrinks
+#[tauri::command]
+pub async fn vacuum_database(app: AppHandle) -> Result<DbInfo, String> {
+    run(app, "VACUUM; PRAGMA wal_checkpoint(TRUNCATE);").await
+}
+
+#[tauri::command]
+pub async fn analyze_database(app: AppHandle) -> Result<DbInfo, String> {
+    run(app, "ANALYZE; PRAGMA optimize;").await
+}
@@ -0,0 +1,106 @@
+use crate::tasks::{self, Subtask, Task, SUBTASK_COLUMNS, TASK_COLUMNS};
+use crate::{dates, db};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+// Characters that can start inline formatting anywhere in a line. Block markers such as
+// `-` or `1.` only matter at the start of a line, which is always the checkbox here.
+const SPECIAL_CHARS: &[char] = &['\\', '`', '*', '_', '[', ']', '(', ')', '<', '>', '#', '|', '~', '!'];
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if SPECIAL_CHARS.contains(&ch) {
+            out.push('\\');
+        }
+        // A line break would end the list item
+        out.push(if ch == '\n' || ch == '\r' { ' ' } else { ch });
+    }
+    out
+}
+
+pub(crate) fn priority_emoji(priority: Option<i64>) -> Option<&'static str> {
+    match priority {
+        Some(1) => Some("🔴"),
+        Some(2) => Some("🟡"),
+        Some(3) => Some("🔵"),
+        _ => None,
+    }
+}
+
+pub(crate) fn checklist_line(indent: &str, completed: bool, title: &str, extras: &[String]) -> String {
+    let title = escape(title.trim());
+    let mut line = if completed {
+        format!("{}- [x] ~~{}~~", indent, title)
+    } else {
+        format!("{}- [ ] {}", indent, title)
+    };
+    for extra in extras {
+        line.push(' ');
+        line.push_str(extra);
+    }
+    line
+}
+
+pub(crate) fn due_label(due_date: Option<i64>) -> Option<String> {
+    due_date
+        .and_then(dates::local_date)
+        .map(|date| format!("📅 {}", date.format("%Y-%m-%d")))
+}
+
+pub fn render_list(conn: &Connection, list_id: &str) -> Result<String, String> {
+    let name = tasks::list_name(conn, list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"SELECT {} FROM tasks WHERE list_id = ?1 AND list_name != 'Trash' AND deleted_at IS NULL ORDER BY "order""#,
+            TASK_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let list_tasks = stmt
+        .query_map([list_id], Task::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"
+            SELECT {} FROM subtasks
+            WHERE deleted_at IS NULL AND parent_id IN (SELECT id FROM tasks WHERE list_id = ?1)
+            ORDER BY parent_id, "order"
+            "#,
+            SUBTASK_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut subtasks: HashMap<String, Vec<Subtask>> = HashMap::new();
+    for subtask in stmt.query_map([list_id], Subtask::from_row).map_err(|e| e.to_string())? {
+        let subtask = subtask.map_err(|e| e.to_string())?;
+        subtasks.entry(subtask.parent_id.clone()).or_default().push(subtask);
+    }
+
+    let mut lines = vec![format!("# {}", escape(&name)), String::new()];
+    for task in &list_tasks {
+        let extras: Vec<String> = priority_emoji(task.priority)
+            .map(String::from)
+            .into_iter()
+            .chain(due_label(task.due_date))
+            .collect();
+        lines.push(checklist_line("", task.completed, &task.title, &extras));
+        for subtask in subtasks.get(&task.id).map(Vec::as_slice).unwrap_or_default() {
+            let extras: Vec<String> = due_label(subtask.due_date).into_iter().collect();
+            lines.push(checklist_line("  ", subtask.completed, &subtask.title, &extras));
+        }
+    }
+    lines.push(String::new());
+    Ok(lines.join("\n"))
+}
+
+#[tauri::command]
+pub async fn export_markdown(app: AppHandle, list_id: String) -> Result<String, String> {
+    let conn = db::connect(&app)?;
+    render_list(&conn, &list_id)
+}
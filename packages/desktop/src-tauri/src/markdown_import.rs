@@ -0,0 +1,230 @@
+use crate::tasks::{self, NewSubtask, NewTask};
+use crate::{dates, db, journal, list_defaults};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+// A tab counts as this many spaces when comparing indentation
+const TAB_WIDTH: usize = 4;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    // Set when the tasks went into a list named by the file's heading
+    pub list_id: Option<String>,
+    pub lists: usize,
+    pub tasks: usize,
+    pub subtasks: usize,
+    // Non-blank lines that are neither a heading nor a checklist item
+    pub skipped: usize,
+}
+
+struct Item {
+    indent: usize,
+    completed: bool,
+    title: String,
+    priority: Option<i64>,
+    due_date: Option<i64>,
+}
+
+fn indent_width(line: &str) -> usize {
+    let mut width = 0;
+    for ch in line.chars() {
+        match ch {
+            ' ' => width += 1,
+            '\t' => width += TAB_WIDTH - width % TAB_WIDTH,
+            _ => break,
+        }
+    }
+    width
+}
+
+// Undo `markdown::escape`
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' && let Some(next) = chars.next() {
+            out.push(next);
+            continue;
+        }
+        out.push(ch);
+    }
+    out
+}
+
+// The trailing extras `markdown::render_list` writes after the title: a priority emoji and
+// a `📅 YYYY-MM-DD` due date, in any order
+fn take_extras(mut text: &str) -> (&str, Option<i64>, Option<i64>) {
+    let mut priority = None;
+    let mut due_date = None;
+    loop {
+        text = text.trim_end();
+        if let Some((rest, date)) = text.rsplit_once("📅") && let Some(parsed) = dates::parse_due_date(date) {
+            due_date = due_date.or(Some(parsed));
+            text = rest;
+            continue;
+        }
+        let emoji = [("🔴", 1), ("🟡", 2), ("🔵", 3)]
+            .into_iter()
+            .find_map(|(emoji, p)| text.strip_suffix(emoji).map(|rest| (rest, p)));
+        match emoji {
+            Some((rest, p)) => {
+                priority = priority.or(Some(p));
+                text = rest;
+            }
+            None => return (text, priority, due_date),
+        }
+    }
+}
+
+// `- [ ] title`, `* [x] title` or `+ [X] title`, `None` for anything else
+fn checklist_item(line: &str) -> Option<Item> {
+    let indent = indent_width(line);
+    let rest = line.trim();
+    let rest = rest
+        .strip_prefix("- ")
+        .or_else(|| rest.strip_prefix("* "))
+        .or_else(|| rest.strip_prefix("+ "))?
+        .trim_start();
+    let (completed, rest) = if let Some(rest) = rest.strip_prefix("[ ]") {
+        (false, rest)
+    } else if let Some(rest) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    let (title, priority, due_date) = take_extras(rest.trim());
+    // Done items are exported struck through
+    let title = title
+        .strip_prefix("~~")
+        .and_then(|t| t.strip_suffix("~~"))
+        .unwrap_or(title);
+    let title = unescape(title.trim()).trim().to_string();
+    if title.is_empty() {
+        return None;
+    }
+    Some(Item {
+        indent,
+        completed,
+        title,
+        priority,
+        due_date,
+    })
+}
+
+fn heading(line: &str) -> Option<String> {
+    let text = line.trim_start().strip_prefix('#')?.trim_start_matches('#');
+    // `#tag` isn't a heading
+    if !text.is_empty() && !text.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = unescape(text.trim());
+    (!text.is_empty()).then_some(text)
+}
+
+// Live list with this name, or a new one
+fn list_named(conn: &Connection, name: &str, summary: &mut ImportSummary) -> rusqlite::Result<String> {
+    let existing: Option<String> = conn
+        .query_row(
+            r#"SELECT id FROM lists WHERE name = ?1 AND deleted_at IS NULL ORDER BY "order" LIMIT 1"#,
+            [name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match existing {
+        Some(id) => Ok(id),
+        None => {
+            summary.lists += 1;
+            tasks::insert_list(conn, name)
+        }
+    }
+}
+
+// Checklist items become tasks, items indented below one become its subtasks (subtasks
+// don't nest, deeper items join the same task). With an empty `list_id` the tasks go into
+// the list named by the first `#` heading (or the file name), otherwise headings are
+// skipped like any other line that isn't a checklist item.
+pub fn import(conn: &Connection, text: &str, list_id: &str, file_name: &str) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary::default();
+    let mut items = Vec::new();
+    let mut title = None;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(item) = checklist_item(line) {
+            items.push(item);
+        } else if let Some(text) = heading(line) {
+            title = title.or(Some(text));
+        } else {
+            summary.skipped += 1;
+        }
+    }
+
+    let list_id = if list_id.is_empty() {
+        let name = title.unwrap_or_else(|| file_name.to_string());
+        let id = list_named(conn, &name, &mut summary).map_err(|e| e.to_string())?;
+        summary.list_id = Some(id.clone());
+        id
+    } else {
+        list_id.to_string()
+    };
+    let list_name = tasks::list_name(conn, &list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+    let defaults = list_defaults::load(conn, &list_id)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+
+    let now = dates::now_ms();
+    // The task the next indented items belong to, with its indentation
+    let mut parent: Option<(String, usize)> = None;
+    for item in items {
+        let completed_at = item.completed.then_some(now);
+        if let Some((parent_id, indent)) = &parent && item.indent > *indent {
+            let subtask = NewSubtask {
+                title: item.title,
+                due_date: item.due_date,
+                completed_at,
+            };
+            tasks::insert_subtask(conn, parent_id, &subtask).map_err(|e| e.to_string())?;
+            summary.subtasks += 1;
+            continue;
+        }
+        let mut task = NewTask {
+            title: item.title,
+            due_date: item.due_date,
+            priority: item.priority,
+            completed_at,
+            ..Default::default()
+        };
+        defaults.apply(&mut task);
+        let id = tasks::insert(conn, &list_id, &list_name, &task).map_err(|e| e.to_string())?;
+        parent = Some((id, item.indent));
+        summary.tasks += 1;
+    }
+    Ok(summary)
+}
+
+// Import a Markdown (or plain text) checklist, e.g. one written by `export_markdown`
+#[tauri::command]
+pub async fn import_markdown(app: AppHandle, path: String, list_id: String) -> Result<ImportSummary, String> {
+    let path = Path::new(&path);
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let file_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported".to_string());
+
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_markdown").map_err(|e| e.to_string())?;
+    let summary = import(&tx, &text, list_id.trim(), &file_name)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
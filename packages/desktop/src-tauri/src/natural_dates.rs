@@ -0,0 +1,749 @@
+use crate::{dates, db, settings};
+use chrono::{Datelike, Months, NaiveDate, NaiveTime, TimeDelta, Weekday};
+use serde::Serialize;
+use std::ops::Range;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone)]
+pub struct DateMatch {
+    pub due_date: i64,
+    // Whether the phrase named a time of day, otherwise `due_date` is local midnight
+    pub has_time: bool,
+    // Byte range of the phrase inside the input
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    En,
+    Zh,
+}
+
+impl Language {
+    // Anything but English reads as zh-CN, the app's default language
+    pub fn from_setting(language: Option<&str>) -> Self {
+        match language {
+            Some(language) if language.starts_with("en") => Language::En,
+            _ => Language::Zh,
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Language::En => Language::Zh,
+            Language::Zh => Language::En,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Day {
+    date: NaiveDate,
+    // "tonight" and friends turn "at 8" into 20:00
+    evening: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Time {
+    time: NaiveTime,
+    // An hour from 1 to 11 without am/pm, `time` holds the morning reading
+    ambiguous: bool,
+}
+
+struct Candidate<T> {
+    range: Range<usize>,
+    value: T,
+}
+
+// Matchers look at the input from one position on and return the length they consumed
+type Matcher<T> = fn(&str, NaiveDate) -> Option<(usize, T)>;
+
+enum Outcome {
+    Nothing,
+    Ambiguous,
+    Found(DateMatch),
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+}
+
+const EN_RELATIVE: &[(&str, (i64, bool))] = &[
+    ("today", (0, false)),
+    ("tonight", (0, true)),
+    ("tomorrow", (1, false)),
+    ("tomorrow night", (1, true)),
+    ("day after tomorrow", (2, false)),
+    ("the day after tomorrow", (2, false)),
+];
+const ZH_RELATIVE: &[(&str, (i64, bool))] = &[
+    ("今天", (0, false)),
+    ("今日", (0, false)),
+    ("今早", (0, false)),
+    ("今晚", (0, true)),
+    ("明天", (1, false)),
+    ("明日", (1, false)),
+    ("明早", (1, false)),
+    ("明晚", (1, true)),
+    ("后天", (2, false)),
+    ("大后天", (3, false)),
+];
+
+const EN_WEEKDAYS: &[(&str, Weekday)] = &[
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+const ZH_WEEKDAYS: &[(&str, Weekday)] = &[
+    ("一", Weekday::Mon),
+    ("二", Weekday::Tue),
+    ("三", Weekday::Wed),
+    ("四", Weekday::Thu),
+    ("五", Weekday::Fri),
+    ("六", Weekday::Sat),
+    ("日", Weekday::Sun),
+    ("天", Weekday::Sun),
+];
+
+const EN_MONTHS: &[(&str, u32)] = &[
+    ("january", 1),
+    ("jan", 1),
+    ("february", 2),
+    ("feb", 2),
+    ("march", 3),
+    ("mar", 3),
+    ("april", 4),
+    ("apr", 4),
+    ("may", 5),
+    ("june", 6),
+    ("jun", 6),
+    ("july", 7),
+    ("jul", 7),
+    ("august", 8),
+    ("aug", 8),
+    ("september", 9),
+    ("sept", 9),
+    ("sep", 9),
+    ("october", 10),
+    ("oct", 10),
+    ("november", 11),
+    ("nov", 11),
+    ("december", 12),
+    ("dec", 12),
+];
+
+const EN_COUNTS: &[(&str, i64)] = &[
+    ("a", 1),
+    ("an", 1),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+    ("ten", 10),
+];
+const EN_UNITS: &[(&str, Unit)] = &[
+    ("day", Unit::Day),
+    ("days", Unit::Day),
+    ("week", Unit::Week),
+    ("weeks", Unit::Week),
+    ("month", Unit::Month),
+    ("months", Unit::Month),
+];
+const ZH_UNITS: &[(&str, Unit)] = &[
+    ("天", Unit::Day),
+    ("周", Unit::Week),
+    ("星期", Unit::Week),
+    ("个星期", Unit::Week),
+    ("礼拜", Unit::Week),
+    ("个礼拜", Unit::Week),
+    ("个月", Unit::Month),
+];
+
+#[derive(Clone, Copy)]
+enum Period {
+    Morning,
+    Noon,
+    Afternoon,
+}
+const ZH_PERIODS: &[(&str, Period)] = &[
+    ("凌晨", Period::Morning),
+    ("早上", Period::Morning),
+    ("早晨", Period::Morning),
+    ("上午", Period::Morning),
+    ("中午", Period::Noon),
+    ("下午", Period::Afternoon),
+    ("傍晚", Period::Afternoon),
+    ("晚上", Period::Afternoon),
+];
+
+fn is_word_char(ch: Option<char>) -> bool {
+    ch.is_some_and(|ch| ch.is_alphanumeric() && ch.is_ascii())
+}
+
+// The longest option `s` starts with
+fn longest<T: Copy>(s: &str, options: &[(&str, T)]) -> Option<(usize, T)> {
+    options
+        .iter()
+        .filter(|(phrase, _)| s.starts_with(phrase))
+        .max_by_key(|(phrase, _)| phrase.len())
+        .map(|(phrase, value)| (phrase.len(), *value))
+}
+
+// Like `longest`, but an ASCII word may not run on into more letters
+fn longest_word<T: Copy>(s: &str, options: &[(&str, T)]) -> Option<(usize, T)> {
+    options
+        .iter()
+        .filter(|(phrase, _)| s.starts_with(phrase) && !is_word_char(s[phrase.len()..].chars().next()))
+        .max_by_key(|(phrase, _)| phrase.len())
+        .map(|(phrase, value)| (phrase.len(), *value))
+}
+
+fn spaces(s: &str) -> usize {
+    s.len() - s.trim_start().len()
+}
+
+fn number(s: &str) -> Option<(i64, usize)> {
+    let len = s.bytes().take_while(u8::is_ascii_digit).count();
+    if len == 0 || len > 4 {
+        return None;
+    }
+    Some((s[..len].parse().ok()?, len))
+}
+
+fn zh_digit(ch: char) -> Option<i64> {
+    "零一二三四五六七八九".chars().position(|c| c == ch).map(|d| d as i64).or(match ch {
+        '〇' => Some(0),
+        '两' => Some(2),
+        _ => None,
+    })
+}
+
+// ASCII digits or a Chinese numeral below 100 (三, 十二, 二十三)
+fn zh_number(s: &str) -> Option<(i64, usize)> {
+    if let Some(found) = number(s) {
+        return Some(found);
+    }
+    let (mut tens, mut ones, mut len) = (None, None, 0);
+    for (i, ch) in s.char_indices() {
+        if ch == '十' && tens.is_none() {
+            tens = Some(ones.take().unwrap_or(1));
+        } else if let Some(digit) = zh_digit(ch).filter(|_| ones.is_none()) {
+            ones = Some(digit);
+        } else {
+            break;
+        }
+        len = i + ch.len_utf8();
+    }
+    if len == 0 {
+        return None;
+    }
+    Some((tens.unwrap_or(0) * 10 + ones.unwrap_or(0), len))
+}
+
+fn add(today: NaiveDate, count: i64, unit: Unit) -> Option<NaiveDate> {
+    if count <= 0 || count > 3650 {
+        return None;
+    }
+    match unit {
+        Unit::Day => today.checked_add_signed(TimeDelta::days(count)),
+        Unit::Week => today.checked_add_signed(TimeDelta::weeks(count)),
+        Unit::Month => today.checked_add_months(Months::new(count as u32)),
+    }
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - TimeDelta::days(date.weekday().num_days_from_monday() as i64)
+}
+
+// The first `weekday` from today on
+fn upcoming(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let diff = weekday.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64;
+    today + TimeDelta::days(diff.rem_euclid(7))
+}
+
+// `weekday` of the week after this one (weeks start on Monday)
+fn next_week(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    monday_of(today) + TimeDelta::days(7 + weekday.num_days_from_monday() as i64)
+}
+
+fn next_month(today: NaiveDate) -> Option<NaiveDate> {
+    today.with_day(1)?.checked_add_months(Months::new(1))
+}
+
+// During a weekend that weekend, otherwise the coming Saturday
+fn weekend(today: NaiveDate) -> NaiveDate {
+    match today.weekday() {
+        Weekday::Sat | Weekday::Sun => today,
+        _ => upcoming(today, Weekday::Sat),
+    }
+}
+
+// A date without a year is the next one to come, this year or next
+fn month_day(today: NaiveDate, year: Option<i64>, month: i64, day: i64) -> Option<NaiveDate> {
+    let (month, day) = (u32::try_from(month).ok()?, u32::try_from(day).ok()?);
+    if let Some(year) = year {
+        return NaiveDate::from_ymd_opt(year as i32, month, day);
+    }
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date >= today {
+        Some(date)
+    } else {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    }
+}
+
+fn day(date: NaiveDate) -> Day {
+    Day { date, evening: false }
+}
+
+// today, tomorrow, tonight, ...
+fn en_relative(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let (len, (days, evening)) = longest_word(s, EN_RELATIVE)?;
+    Some((len, Day { date: today + TimeDelta::days(days), evening }))
+}
+
+// in 3 days, in a week, in two months
+fn en_in(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let rest = s.strip_prefix("in ")?;
+    let mut len = 3 + spaces(rest);
+    let (count, count_len) = number(&s[len..]).or_else(|| longest_word(&s[len..], EN_COUNTS).map(|(l, c)| (c, l)))?;
+    len += count_len;
+    let gap = spaces(&s[len..]);
+    if gap == 0 {
+        return None;
+    }
+    len += gap;
+    let (unit_len, unit) = longest_word(&s[len..], EN_UNITS)?;
+    Some((len + unit_len, day(add(today, count, unit)?)))
+}
+
+// next week, next month, this weekend
+fn en_period(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let options: [(&str, Option<NaiveDate>); 5] = [
+        ("next week", Some(next_week(today, Weekday::Mon))),
+        ("next month", next_month(today)),
+        ("next weekend", Some(next_week(today, Weekday::Sat))),
+        ("this weekend", Some(weekend(today))),
+        ("weekend", Some(weekend(today))),
+    ];
+    let (len, date) = longest_word(s, &options)?;
+    Some((len, day(date?)))
+}
+
+// friday, on friday, this friday, next friday
+fn en_weekday(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let (prefix, next) = if s.starts_with("next ") {
+        (5, true)
+    } else if s.starts_with("this ") {
+        (5, false)
+    } else if s.starts_with("on ") {
+        (3, false)
+    } else {
+        (0, false)
+    };
+    let (len, weekday) = longest_word(&s[prefix..], EN_WEEKDAYS)?;
+    let date = if next { next_week(today, weekday) } else { upcoming(today, weekday) };
+    Some((prefix + len, day(date)))
+}
+
+fn ordinal_suffix(s: &str) -> usize {
+    ["st", "nd", "rd", "th"].iter().find(|suffix| s.starts_with(*suffix)).map_or(0, |suffix| suffix.len())
+}
+
+// ", 2026" or " 2026" after a date
+fn en_year(s: &str) -> Option<(i64, usize)> {
+    let mut len = usize::from(s.starts_with(','));
+    len += spaces(&s[len..]);
+    let (year, year_len) = number(&s[len..]).filter(|(_, l)| *l == 4)?;
+    Some((year, len + year_len))
+}
+
+// march 5, mar. 5th, 5 march, 5th of march 2026
+fn en_month_day(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let (mut len, month, day_of_month) = if let Some((month_len, month)) = longest_word(s, EN_MONTHS) {
+        let mut len = month_len + usize::from(s[month_len..].starts_with('.'));
+        let gap = spaces(&s[len..]);
+        if gap == 0 {
+            return None;
+        }
+        len += gap;
+        let (day_of_month, day_len) = number(&s[len..])?;
+        len += day_len;
+        len += ordinal_suffix(&s[len..]);
+        (len, month, day_of_month)
+    } else {
+        let (day_of_month, mut len) = number(s)?;
+        len += ordinal_suffix(&s[len..]);
+        let gap = spaces(&s[len..]);
+        if gap == 0 {
+            return None;
+        }
+        len += gap;
+        if s[len..].starts_with("of ") {
+            len += 3;
+        }
+        let (month_len, month) = longest_word(&s[len..], EN_MONTHS)?;
+        (len + month_len, month, day_of_month)
+    };
+    let year = en_year(&s[len..]).map(|(year, year_len)| {
+        len += year_len;
+        year
+    });
+    Some((len, day(month_day(today, year, month as i64, day_of_month)?)))
+}
+
+// 2026-03-05, 2026/3/5, 3/5, 3/5/2026
+fn numeric_date(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let (first, first_len) = number(s)?;
+    let separator = s[first_len..].chars().next().filter(|c| *c == '-' || *c == '/')?;
+    let mut len = first_len + 1;
+    let (second, second_len) = number(&s[len..])?;
+    len += second_len;
+    let third = s[len..].strip_prefix(separator).and_then(number);
+
+    let date = if first_len == 4 {
+        let (third, third_len) = third?;
+        len += 1 + third_len;
+        month_day(today, Some(first), second, third)?
+    } else if separator == '/' {
+        // Month first, with an optional year. Day-first dates can't be told apart, so
+        // they aren't supported at all.
+        let year = match third {
+            Some((year, 4)) => {
+                len += 5;
+                Some(year)
+            }
+            Some((year, 2)) => {
+                len += 3;
+                Some(2000 + year)
+            }
+            Some(_) => return None,
+            None => None,
+        };
+        month_day(today, year, first, second)?
+    } else {
+        return None;
+    };
+    Some((len, day(date)))
+}
+
+// 3pm, 3:30 pm, at 15:00, noon, at 8 (ambiguous)
+fn en_time(s: &str, _today: NaiveDate) -> Option<(usize, Time)> {
+    let prefix = if s.starts_with("at ") {
+        3
+    } else if s.starts_with('@') {
+        1
+    } else {
+        0
+    };
+    let mut len = prefix + spaces(&s[prefix..]);
+    if let Some((noon_len, ())) = longest_word(&s[len..], &[("noon", ())]) {
+        return Some((len + noon_len, Time { time: NaiveTime::from_hms_opt(12, 0, 0)?, ambiguous: false }));
+    }
+    let (hour, hour_len) = number(&s[len..]).filter(|(_, l)| *l <= 2)?;
+    len += hour_len;
+    let minute = s[len..].strip_prefix(':').and_then(number).filter(|(_, l)| *l == 2).map(|(m, _)| m);
+    if minute.is_some() {
+        len += 3;
+    }
+
+    let gap = spaces(&s[len..]);
+    let suffix = longest_word(&s[len + gap..], &[("am", false), ("a.m.", false), ("pm", true), ("p.m.", true)]);
+    let (hour, ambiguous) = match suffix {
+        Some((suffix_len, pm)) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            len += gap + suffix_len;
+            (hour % 12 + if pm { 12 } else { 0 }, false)
+        }
+        // A clock time is read as 24-hour time
+        None if minute.is_some() => (hour, false),
+        // A bare number only counts after "at"
+        None if prefix > 0 && (1..=12).contains(&hour) => (hour, hour < 12),
+        None => return None,
+    };
+    let time = NaiveTime::from_hms_opt(u32::try_from(hour).ok()?, u32::try_from(minute.unwrap_or(0)).ok()?, 0)?;
+    Some((len, Time { time, ambiguous }))
+}
+
+// 下午3点, 晚上八点半, 9点15分, 15点
+fn zh_time(s: &str, _today: NaiveDate) -> Option<(usize, Time)> {
+    let (mut len, period) = match longest(s, ZH_PERIODS) {
+        Some((len, period)) => (len, Some(period)),
+        None => (0, None),
+    };
+    let (hour, hour_len) = zh_number(&s[len..])?;
+    len += hour_len;
+    len += longest(&s[len..], &[("点钟", ()), ("点", ()), ("时", ())])?.0;
+
+    let minute = if let Some((half_len, minute)) = longest(&s[len..], &[("半", 30), ("一刻", 15), ("三刻", 45)]) {
+        len += half_len;
+        minute
+    } else if let Some((minute, minute_len)) = zh_number(&s[len..]) {
+        len += minute_len;
+        if s[len..].starts_with("分") {
+            len += "分".len();
+        }
+        minute
+    } else {
+        0
+    };
+
+    let (hour, ambiguous) = match period {
+        Some(Period::Morning) if hour <= 12 => (hour, false),
+        Some(Period::Noon) if hour == 11 || hour == 12 => (hour, false),
+        Some(Period::Noon) if (1..=2).contains(&hour) => (hour + 12, false),
+        Some(Period::Afternoon) if (1..=11).contains(&hour) => (hour + 12, false),
+        Some(Period::Afternoon) if hour == 12 => (hour, false),
+        Some(_) => return None,
+        None => (hour, (1..=11).contains(&hour)),
+    };
+    let time = NaiveTime::from_hms_opt(u32::try_from(hour).ok()?, u32::try_from(minute).ok()?, 0)?;
+    Some((len, Time { time, ambiguous }))
+}
+
+// 15:30 on its own
+fn clock_time(s: &str, _today: NaiveDate) -> Option<(usize, Time)> {
+    let (hour, hour_len) = number(s).filter(|(_, l)| *l <= 2)?;
+    let (minute, _) = s[hour_len..].strip_prefix(':').and_then(number).filter(|(_, l)| *l == 2)?;
+    let time = NaiveTime::from_hms_opt(u32::try_from(hour).ok()?, u32::try_from(minute).ok()?, 0)?;
+    Some((hour_len + 3, Time { time, ambiguous: false }))
+}
+
+fn zh_relative(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let (len, (days, evening)) = longest(s, ZH_RELATIVE)?;
+    Some((len, Day { date: today + TimeDelta::days(days), evening }))
+}
+
+// 3天后, 两周之后, 一个月以后
+fn zh_later(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let (count, mut len) = zh_number(s)?;
+    let (unit_len, unit) = longest(&s[len..], ZH_UNITS)?;
+    len += unit_len;
+    len += longest(&s[len..], &[("后", ()), ("之后", ()), ("以后", ())])?.0;
+    Some((len, day(add(today, count, unit)?)))
+}
+
+// 下周, 下个月, 周末
+fn zh_period(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let options: [(&str, Option<NaiveDate>); 11] = [
+        ("下周", Some(next_week(today, Weekday::Mon))),
+        ("下星期", Some(next_week(today, Weekday::Mon))),
+        ("下个星期", Some(next_week(today, Weekday::Mon))),
+        ("下礼拜", Some(next_week(today, Weekday::Mon))),
+        ("下个礼拜", Some(next_week(today, Weekday::Mon))),
+        ("下月", next_month(today)),
+        ("下个月", next_month(today)),
+        ("周末", Some(weekend(today))),
+        ("这周末", Some(weekend(today))),
+        ("本周末", Some(weekend(today))),
+        ("下周末", Some(next_week(today, Weekday::Sat))),
+    ];
+    let (len, date) = longest(s, &options)?;
+    Some((len, day(date?)))
+}
+
+// 周五, 星期五, 这周五, 下周五, 下个星期天
+fn zh_weekday(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let (mut len, next) = longest(s, &[("下个", true), ("下", true), ("这个", false), ("这", false), ("本", false)]).unwrap_or((0, false));
+    len += longest(&s[len..], &[("周", ()), ("星期", ()), ("礼拜", ())])?.0;
+    let (weekday_len, weekday) = longest(&s[len..], ZH_WEEKDAYS)?;
+    let date = if next { next_week(today, weekday) } else { upcoming(today, weekday) };
+    Some((len + weekday_len, day(date)))
+}
+
+// 3月5日, 十月十四号, 2026年3月5日
+fn zh_month_day(s: &str, today: NaiveDate) -> Option<(usize, Day)> {
+    let mut len = 0;
+    let year = match number(s) {
+        Some((year, 4)) if s[4..].starts_with("年") => {
+            len = 4 + "年".len();
+            Some(year)
+        }
+        _ => None,
+    };
+    let (month, month_len) = zh_number(&s[len..])?;
+    len += month_len;
+    len += longest(&s[len..], &[("月", ())])?.0;
+    let (day_of_month, day_len) = zh_number(&s[len..])?;
+    len += day_len;
+    len += longest(&s[len..], &[("日", ()), ("号", ())]).map_or(0, |(l, _)| l);
+    Some((len, day(month_day(today, year, month, day_of_month)?)))
+}
+
+const EN_DAY_MATCHERS: &[Matcher<Day>] = &[en_relative, en_in, en_period, en_weekday, en_month_day, numeric_date];
+const EN_TIME_MATCHERS: &[Matcher<Time>] = &[en_time, clock_time];
+const ZH_DAY_MATCHERS: &[Matcher<Day>] = &[zh_relative, zh_later, zh_period, zh_weekday, zh_month_day, numeric_date];
+const ZH_TIME_MATCHERS: &[Matcher<Time>] = &[zh_time, clock_time];
+
+fn matchers(language: Language) -> (&'static [Matcher<Day>], &'static [Matcher<Time>]) {
+    match language {
+        Language::En => (EN_DAY_MATCHERS, EN_TIME_MATCHERS),
+        Language::Zh => (ZH_DAY_MATCHERS, ZH_TIME_MATCHERS),
+    }
+}
+
+// Run every matcher at every position where a phrase may start
+fn scan<T>(haystack: &str, today: NaiveDate, matchers: &[Matcher<T>]) -> Vec<Candidate<T>> {
+    let mut found = Vec::new();
+    for (start, _) in haystack.char_indices() {
+        if is_word_char(haystack[..start].chars().next_back()) && is_word_char(haystack[start..].chars().next()) {
+            continue;
+        }
+        for matcher in matchers {
+            let Some((len, value)) = matcher(&haystack[start..], today) else {
+                continue;
+            };
+            let end = start + len;
+            if len == 0 || (is_word_char(haystack[..end].chars().next_back()) && is_word_char(haystack[end..].chars().next())) {
+                continue;
+            }
+            found.push(Candidate { range: start..end, value });
+        }
+    }
+    keep_longest(found)
+}
+
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+// Of overlapping candidates keep the longest, so "day after tomorrow" isn't read as
+// "tomorrow"
+fn keep_longest<T>(mut found: Vec<Candidate<T>>) -> Vec<Candidate<T>> {
+    found.sort_by_key(|c| (std::cmp::Reverse(c.range.len()), c.range.start));
+    let mut kept: Vec<Candidate<T>> = Vec::new();
+    for candidate in found {
+        if !kept.iter().any(|k| overlaps(&k.range, &candidate.range)) {
+            kept.push(candidate);
+        }
+    }
+    kept.sort_by_key(|c| c.range.start);
+    kept
+}
+
+// A date and a time only belong together when nothing but a filler word separates them
+fn adjacent(haystack: &str, a: &Range<usize>, b: &Range<usize>) -> bool {
+    let (first, second) = if a.start <= b.start { (a, b) } else { (b, a) };
+    matches!(haystack[first.end..second.start].trim(), "" | "at" | "on" | "," | "的")
+}
+
+fn parse_in(text: &str, reference_ms: i64, language: Language) -> Outcome {
+    let Some(today) = dates::local_date(reference_ms) else {
+        return Outcome::Nothing;
+    };
+    let haystack = text.to_ascii_lowercase();
+    let (day_matchers, time_matchers) = matchers(language);
+    let days = scan(&haystack, today, day_matchers);
+    let mut times = scan(&haystack, today, time_matchers);
+    times.retain(|t| !days.iter().any(|d| overlaps(&d.range, &t.range)));
+
+    // Two different dates (or times) in one title: no way to tell which one is meant
+    if days.iter().any(|d| d.value.date != days[0].value.date)
+        || times.iter().any(|t| t.value.time != times[0].value.time)
+    {
+        return Outcome::Ambiguous;
+    }
+
+    let found = |range: Range<usize>, date: NaiveDate, time: Option<NaiveTime>| {
+        Outcome::Found(DateMatch {
+            due_date: match time {
+                Some(time) => dates::from_local_naive(date.and_time(time)),
+                None => dates::local_midnight(date),
+            },
+            has_time: time.is_some(),
+            range,
+        })
+    };
+
+    match (days.first(), times.first()) {
+        (None, None) => Outcome::Nothing,
+        (Some(d), None) => found(d.range.clone(), d.value.date, None),
+        (None, Some(t)) if t.value.ambiguous => Outcome::Ambiguous,
+        // A time alone is its next occurrence
+        (None, Some(t)) => {
+            let date = if dates::from_local_naive(today.and_time(t.value.time)) < reference_ms {
+                today + TimeDelta::days(1)
+            } else {
+                today
+            };
+            found(t.range.clone(), date, Some(t.value.time))
+        }
+        // Without am/pm only an evening ("tonight at 8") settles the hour, otherwise the
+        // time is left in the title
+        (Some(d), Some(t)) if t.value.ambiguous && !d.value.evening => found(d.range.clone(), d.value.date, None),
+        (Some(d), Some(t)) if adjacent(&haystack, &d.range, &t.range) => {
+            let time = if t.value.ambiguous { t.value.time + TimeDelta::hours(12) } else { t.value.time };
+            let range = d.range.start.min(t.range.start)..d.range.end.max(t.range.end);
+            found(range, d.value.date, Some(time))
+        }
+        (Some(_), Some(_)) => Outcome::Ambiguous,
+    }
+}
+
+// Look for a due date in `text`, relative to `reference_ms`. Phrases of `language` are
+// tried first and the other language's only when there was nothing at all; ambiguous
+// input (two different dates, "at 3" on its own) gives `None` instead of a guess.
+pub fn find(text: &str, reference_ms: i64, language: Language) -> Option<DateMatch> {
+    let outcome = match parse_in(text, reference_ms, language) {
+        Outcome::Nothing => parse_in(text, reference_ms, language.other()),
+        outcome => outcome,
+    };
+    match outcome {
+        Outcome::Found(found) => Some(found),
+        Outcome::Nothing | Outcome::Ambiguous => None,
+    }
+}
+
+// Remove the matched phrase from a title and tidy up the whitespace around it. CJK text
+// has no spaces between words, so none is put back there.
+pub fn strip(text: &str, range: Range<usize>) -> String {
+    let before = text[..range.start].trim_end();
+    let after = text[range.end..].trim_start();
+    let cjk_join = before.chars().next_back().is_some_and(|c| !c.is_ascii())
+        || after.chars().next().is_some_and(|c| !c.is_ascii());
+    let separator = if before.is_empty() || after.is_empty() || cjk_join { "" } else { " " };
+    format!("{}{}{}", before, separator, after)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedDate {
+    pub due_date: i64,
+    pub has_time: bool,
+    // Span of the phrase in UTF-16 code units, like JavaScript string indices
+    pub start: usize,
+    pub end: usize,
+    pub matched: String,
+    // The input without the phrase
+    pub title: String,
+}
+
+#[tauri::command]
+pub async fn parse_due_date(app: AppHandle, text: String, reference_ms: i64) -> Result<Option<ParsedDate>, String> {
+    let language = {
+        let conn = db::connect(&app)?;
+        Language::from_setting(settings::language(&conn).as_deref())
+    };
+    Ok(find(&text, reference_ms, language).map(|found| ParsedDate {
+        due_date: found.due_date,
+        has_time: found.has_time,
+        start: text[..found.range.start].encode_utf16().count(),
+        end: text[..found.range.end].encode_utf16().count(),
+        matched: text[found.range.clone()].to_string(),
+        title: strip(&text, found.range),
+    }))
+}
@@ -0,0 +1,154 @@
+use crate::tasks::{self, NewSubtask, NewTask};
+use crate::{db, journal, list_defaults};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+// Outlines nested deeper than this (a top-level outline is 1) are skipped
+const MAX_DEPTH: usize = 4;
+// Prefix per level below a subtask, for outlines flattened into the subtask list
+const INDENT: &str = "  ";
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub tasks: usize,
+    // Includes the flattened ones
+    pub subtasks: usize,
+    // Outlines below subtask level that became indented subtasks
+    pub flattened: usize,
+    // Outlines nested deeper than `MAX_DEPTH`
+    pub too_deep: usize,
+    // Outlines without a `text`, and everything below such a top-level outline
+    pub untitled: usize,
+}
+
+struct Node {
+    depth: usize,
+    title: String,
+    note: Option<String>,
+}
+
+fn node(reader: &Reader<&[u8]>, element: &BytesStart, depth: usize) -> Result<Node, String> {
+    let mut title = String::new();
+    let mut note = None;
+    for attr in element.attributes() {
+        let attr = attr.map_err(|e| format!("Invalid OPML attribute: {}", e))?;
+        let value = || {
+            attr.decode_and_unescape_value(reader.decoder())
+                .map(|value| value.trim().to_string())
+                .map_err(|e| format!("Invalid OPML attribute: {}", e))
+        };
+        match attr.key.as_ref() {
+            b"text" => title = value()?,
+            b"_note" => note = Some(value()?).filter(|note| !note.is_empty()),
+            _ => {}
+        }
+    }
+    Ok(Node { depth, title, note })
+}
+
+// Every `<outline>` in document order with its nesting depth
+fn outlines(text: &str) -> Result<Vec<Node>, String> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+    let mut nodes = Vec::new();
+    let mut depth = 0;
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Invalid OPML at byte {}: {}", reader.error_position(), e))?;
+        match event {
+            Event::Start(element) if element.local_name().as_ref() == b"outline" => {
+                depth += 1;
+                nodes.push(node(&reader, &element, depth)?);
+            }
+            Event::Empty(element) if element.local_name().as_ref() == b"outline" => {
+                nodes.push(node(&reader, &element, depth + 1)?);
+            }
+            Event::End(element) if element.local_name().as_ref() == b"outline" => {
+                depth = depth.saturating_sub(1);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(nodes)
+}
+
+// Top-level outlines become tasks in `list_id` with `_note` as their notes, outlines
+// below one become its subtasks. Subtasks don't nest, so deeper outlines follow as
+// subtasks indented by their depth, down to `MAX_DEPTH`. Subtasks have no notes, their
+// `_note` is dropped.
+pub fn import(conn: &Connection, text: &str, list_id: &str) -> Result<ImportSummary, String> {
+    let nodes = outlines(text)?;
+    let list_name = tasks::list_name(conn, list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+    let defaults = list_defaults::load(conn, list_id)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+
+    let mut summary = ImportSummary::default();
+    let mut parent: Option<String> = None;
+    for node in nodes {
+        if node.depth <= 1 {
+            parent = None;
+            if node.title.is_empty() {
+                summary.untitled += 1;
+                continue;
+            }
+            let mut task = NewTask {
+                title: node.title,
+                content: node.note,
+                ..Default::default()
+            };
+            defaults.apply(&mut task);
+            parent = Some(tasks::insert(conn, list_id, &list_name, &task).map_err(|e| e.to_string())?);
+            summary.tasks += 1;
+            continue;
+        }
+        if node.depth > MAX_DEPTH {
+            summary.too_deep += 1;
+            continue;
+        }
+        let Some(parent_id) = &parent else {
+            summary.untitled += 1;
+            continue;
+        };
+        if node.title.is_empty() {
+            summary.untitled += 1;
+            continue;
+        }
+        let subtask = NewSubtask {
+            title: format!("{}{}", INDENT.repeat(node.depth - 2), node.title),
+            due_date: None,
+            completed_at: None,
+        };
+        tasks::insert_subtask(conn, parent_id, &subtask).map_err(|e| e.to_string())?;
+        summary.subtasks += 1;
+        if node.depth > 2 {
+            summary.flattened += 1;
+        }
+    }
+    Ok(summary)
+}
+
+// Import an OPML outline, e.g. from an outliner or mind-mapping app
+#[tauri::command]
+pub async fn import_opml(app: AppHandle, path: String, list_id: String) -> Result<ImportSummary, String> {
+    let path = Path::new(&path);
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_opml").map_err(|e| e.to_string())?;
+    let summary = import(&tx, &text, list_id.trim())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
@@ -0,0 +1,463 @@
+use crate::export::INBOX_LIST_ID;
+use crate::tasks::{self, Subtask, Task};
+use crate::{dates, db, journal, sort_keys};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use tauri::AppHandle;
+use uuid::Uuid;
+
+// Give every id its position in `ordered_ids` (1-based, like `tasks::next_order`) as its
+// "order". Nothing is written unless every id is a live task of `list_id`.
+pub fn reorder(conn: &mut Connection, list_id: &str, ordered_ids: &[String]) -> Result<HashMap<String, i64>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "reorder_tasks").map_err(|e| e.to_string())?;
+    let in_list: HashSet<String> = {
+        let mut stmt = tx
+            .prepare("SELECT id FROM tasks WHERE list_id = ?1 AND deleted_at IS NULL")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([list_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut seen = HashSet::new();
+    for id in ordered_ids {
+        if !in_list.contains(id) {
+            return Err(format!("Task {} is not in list {}", id, list_id));
+        }
+        if !seen.insert(id) {
+            return Err(format!("Task {} is listed more than once", id));
+        }
+    }
+
+    let now = dates::now_ms();
+    let mut orders = HashMap::new();
+    {
+        let mut stmt = tx
+            .prepare(r#"UPDATE tasks SET "order" = ?1, updated_at = ?2 WHERE id = ?3 AND "order" IS NOT ?1"#)
+            .map_err(|e| e.to_string())?;
+        for (index, id) in ordered_ids.iter().enumerate() {
+            let order = index as i64 + 1;
+            stmt.execute(params![order, now, id]).map_err(|e| e.to_string())?;
+            orders.insert(id.clone(), order);
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(orders)
+}
+
+// Gap between the orders `resequence` hands out, so the frontend can drop a task between
+// two others without renumbering the list
+const ORDER_STEP: i64 = 1000;
+
+// Renumber a list's tasks (trashed ones included) 1000, 2000, ... by their current order,
+// id breaking ties, if any two of them share an order. Returns how many tasks got a new
+// order, 0 for a list that has no duplicates, which is left alone.
+fn resequence(conn: &Connection, list_id: &str, now: i64) -> rusqlite::Result<usize> {
+    let duplicated: bool = conn.query_row(
+        r#"SELECT EXISTS (SELECT 1 FROM tasks WHERE list_id = ?1 GROUP BY "order" HAVING COUNT(*) > 1)"#,
+        [list_id],
+        |row| row.get(0),
+    )?;
+    if !duplicated {
+        return Ok(0);
+    }
+    let ids: Vec<String> = {
+        let mut stmt = conn.prepare(r#"SELECT id FROM tasks WHERE list_id = ?1 ORDER BY "order", id"#)?;
+        let rows = stmt.query_map([list_id], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut changed = 0;
+    {
+        let mut stmt =
+            conn.prepare(r#"UPDATE tasks SET "order" = ?1, updated_at = ?2 WHERE id = ?3 AND "order" IS NOT ?1"#)?;
+        for (index, id) in ids.iter().enumerate() {
+            changed += stmt.execute(params![(index as i64 + 1) * ORDER_STEP, now, id])?;
+        }
+    }
+    // The new orders left the sort keys stale, build them again in the same sequence
+    sort_keys::rebalance(conn, Some(list_id))?;
+    Ok(changed)
+}
+
+pub fn resequence_lists(conn: &mut Connection, list_id: Option<&str>) -> Result<usize, String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "resequence").map_err(|e| e.to_string())?;
+    let list_ids: Vec<String> = match list_id {
+        Some(id) => {
+            let found = tx
+                .query_row("SELECT 1 FROM lists WHERE id = ?1", [id], |_| Ok(()))
+                .optional()
+                .map_err(|e| e.to_string())?;
+            found.ok_or_else(|| format!("List {} not found", id))?;
+            vec![id.to_string()]
+        }
+        None => {
+            let mut stmt = tx
+                .prepare("SELECT DISTINCT list_id FROM tasks WHERE list_id IS NOT NULL")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        }
+    };
+    let mut changed = 0;
+    for id in &list_ids {
+        changed += resequence(&tx, id, now).map_err(|e| e.to_string())?;
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(changed)
+}
+
+// The columns `move_to_list` needs of the task being moved
+struct MovedTask {
+    list_id: Option<String>,
+    due_date: Option<i64>,
+    completed: bool,
+    deleted_at: Option<i64>,
+}
+
+pub fn move_to_list(conn: &mut Connection, task_id: &str, target_list_id: &str) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "move_task").map_err(|e| e.to_string())?;
+    let task = tx
+        .query_row(
+            "SELECT list_id, due_date, completed, deleted_at FROM tasks WHERE id = ?1",
+            [task_id],
+            |row| {
+                Ok(MovedTask {
+                    list_id: row.get(0)?,
+                    due_date: row.get(1)?,
+                    completed: row.get::<_, i64>(2)? != 0,
+                    deleted_at: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(MovedTask {
+        list_id,
+        due_date,
+        completed,
+        deleted_at,
+    }) = task
+    else {
+        return Err(format!("Task {} not found", task_id));
+    };
+    if deleted_at.is_some() {
+        return Err(format!("Task {} is in the trash", task_id));
+    }
+
+    let list_name = tasks::list_name(&tx, target_list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", target_list_id))?;
+    if list_id.as_deref() == Some(target_list_id) {
+        return Ok(());
+    }
+
+    let now = dates::now_ms();
+    let order = tasks::next_order(&tx, Some(target_list_id)).map_err(|e| e.to_string())?;
+    tx.execute(
+        r#"
+        UPDATE tasks
+        SET list_id = ?1, list_name = ?2, "order" = ?3, group_category = ?4, updated_at = ?5
+        WHERE id = ?6
+        "#,
+        params![
+            target_list_id,
+            list_name,
+            order,
+            dates::group_category(due_date, completed, now),
+            now,
+            task_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    // Subtasks follow their parent by id, they only need to show up as changed for sync
+    tx.execute(
+        "UPDATE subtasks SET updated_at = ?1 WHERE parent_id = ?2 AND deleted_at IS NULL",
+        params![now, task_id],
+    )
+    .map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Move every task of `source_id` to the end of `target_id`, keeping their relative order,
+// then delete the emptied source list. Returns how many tasks were moved.
+pub fn merge(conn: &mut Connection, source_id: &str, target_id: &str) -> Result<usize, String> {
+    if source_id == target_id {
+        return Err("Can't merge a list into itself".to_string());
+    }
+    if source_id == INBOX_LIST_ID {
+        return Err("The inbox can't be merged away".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "merge_lists").map_err(|e| e.to_string())?;
+    tasks::list_name(&tx, source_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", source_id))?;
+    let target_name = tasks::list_name(&tx, target_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", target_id))?;
+
+    let ids: Vec<String> = {
+        let mut stmt = tx
+            .prepare(r#"SELECT id FROM tasks WHERE list_id = ?1 ORDER BY "order", created_at"#)
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([source_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let now = dates::now_ms();
+    let first = tasks::next_order(&tx, Some(target_id)).map_err(|e| e.to_string())?;
+    {
+        // Tasks sitting in the trash keep their pseudo-list name
+        let mut stmt = tx
+            .prepare(
+                r#"
+                UPDATE tasks
+                SET list_id = ?1,
+                    list_name = CASE WHEN list_name = 'Trash' THEN list_name ELSE ?2 END,
+                    "order" = ?3,
+                    updated_at = ?4
+                WHERE id = ?5
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        for (index, id) in ids.iter().enumerate() {
+            stmt.execute(params![target_id, target_name, first + index as i64, now, id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.execute(
+        "UPDATE lists SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2",
+        params![now, source_id],
+    )
+    .map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(ids.len())
+}
+
+// Insert a task at the end of `list_id` with the subtask's title, due date and completion
+// and remove the subtask row, returns the task's id
+pub(crate) fn replace_with_task(
+    conn: &Connection,
+    subtask: &Subtask,
+    list_id: Option<&str>,
+    list_name: &str,
+) -> rusqlite::Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+    let order = tasks::next_order(conn, list_id)?;
+    conn.execute(
+        r#"
+        INSERT INTO tasks (id, title, completed, completed_at, due_date, list_id, list_name, "order",
+                           created_at, updated_at, group_category)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9, ?10)
+        "#,
+        params![
+            id,
+            subtask.title,
+            subtask.completed,
+            subtask.completed_at,
+            subtask.due_date,
+            list_id,
+            list_name,
+            order,
+            now,
+            dates::group_category(subtask.due_date, subtask.completed, now),
+        ],
+    )?;
+    conn.execute("DELETE FROM subtasks WHERE id = ?1", [&subtask.id])?;
+    Ok(id)
+}
+
+// Turn a subtask into a task at the end of its parent's list. It keeps its title, due date
+// and completion, the subtask row is removed.
+pub fn promote(conn: &mut Connection, subtask_id: &str) -> Result<Task, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "promote_subtask").map_err(|e| e.to_string())?;
+    let subtask = tasks::get_subtask(&tx, subtask_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Subtask {} not found", subtask_id))?;
+    let deleted_at: Option<i64> = tx
+        .query_row("SELECT deleted_at FROM subtasks WHERE id = ?1", [subtask_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if deleted_at.is_some() {
+        return Err(format!("Subtask {} is in the trash", subtask_id));
+    }
+    let parent = tasks::get(&tx, &subtask.parent_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", subtask.parent_id))?;
+
+    let id = replace_with_task(&tx, &subtask, parent.list_id.as_deref(), &parent.list_name)
+        .map_err(|e| e.to_string())?;
+    let task = tasks::get(&tx, &id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
+}
+
+// Turn a task into the last subtask of `new_parent_id`, which may sit in another list: the
+// subtask simply follows its new parent. The task's own subtasks move up to the new parent
+// right after it, since subtasks don't nest. Content, tags and priority have no place on a
+// subtask and are dropped; series parents are refused as their instances point at them, and
+// so are tasks with attachments, which would be deleted with the task. Comments move to the
+// new parent's timeline, marked with the subtask they were written on. The rest of the
+// timeline (see comments.rs) and earlier versions go with the task.
+pub fn demote(conn: &mut Connection, task_id: &str, new_parent_id: &str) -> Result<Subtask, String> {
+    if task_id == new_parent_id {
+        return Err("A task can't become its own subtask".to_string());
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "demote_task").map_err(|e| e.to_string())?;
+    let live = |id: &str| -> Result<Task, String> {
+        let deleted_at: Option<Option<i64>> = tx
+            .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match deleted_at {
+            None => Err(format!("Task {} not found", id)),
+            Some(Some(_)) => Err(format!("Task {} is in the trash", id)),
+            Some(None) => tasks::get(&tx, id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Task {} not found", id)),
+        }
+    };
+    let task = live(task_id)?;
+    live(new_parent_id)?;
+    let is_series: bool = tx
+        .query_row(
+            "SELECT recurrence IS NOT NULL AND recurrence_parent_id IS NULL FROM tasks WHERE id = ?1",
+            [task_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if is_series {
+        return Err(format!("Task {} repeats and can't become a subtask", task_id));
+    }
+    let attachments: i64 = tx
+        .query_row("SELECT COUNT(*) FROM attachments WHERE task_id = ?1", [task_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if attachments > 0 {
+        return Err(format!("Task {} has attachments, subtasks can't keep them", task_id));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+    let order: i64 = tx
+        .query_row(
+            r#"SELECT COALESCE(MAX("order"), 0) + 1 FROM subtasks WHERE parent_id = ?1"#,
+            [new_parent_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    tx.execute(
+        r#"
+        INSERT INTO subtasks (id, parent_id, title, completed, completed_at, due_date, "order", created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+        "#,
+        params![
+            id,
+            new_parent_id,
+            task.title,
+            task.completed,
+            task.completed_at,
+            task.due_date,
+            order,
+            now
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let children: Vec<String> = {
+        let mut stmt = tx
+            .prepare(r#"SELECT id FROM subtasks WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY "order""#)
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([task_id], |row| row.get(0)).map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    for (index, child) in children.iter().enumerate() {
+        tx.execute(
+            r#"UPDATE subtasks SET parent_id = ?1, "order" = ?2, updated_at = ?3 WHERE id = ?4"#,
+            params![new_parent_id, order + 1 + index as i64, now, child],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.execute(
+        "UPDATE task_comments SET task_id = ?1, body = 'On subtask \"' || ?2 || '\": ' || body
+         WHERE task_id = ?3 AND kind = 'comment'",
+        params![new_parent_id, task.title, task_id],
+    )
+    .map_err(|e| e.to_string())?;
+    // Trashed subtasks go with the task
+    tx.execute("DELETE FROM subtasks WHERE parent_id = ?1", [task_id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM tasks WHERE id = ?1", [task_id])
+        .map_err(|e| e.to_string())?;
+
+    let subtask = tasks::get_subtask(&tx, &id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Subtask {} not found", id))?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(subtask)
+}
+
+// Returns the new "order" of every reordered task
+#[tauri::command]
+pub async fn reorder_tasks(
+    app: AppHandle,
+    list_id: String,
+    ordered_ids: Vec<String>,
+) -> Result<HashMap<String, i64>, String> {
+    let mut conn = db::connect(&app)?;
+    reorder(&mut conn, &list_id, &ordered_ids)
+}
+
+#[tauri::command]
+pub async fn move_task(app: AppHandle, task_id: String, target_list_id: String) -> Result<(), String> {
+    let mut conn = db::connect(&app)?;
+    move_to_list(&mut conn, &task_id, &target_list_id)
+}
+
+#[tauri::command]
+pub async fn merge_lists(app: AppHandle, source_id: String, target_id: String) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    merge(&mut conn, &source_id, &target_id)
+}
+
+#[tauri::command]
+pub async fn promote_subtask(app: AppHandle, subtask_id: String) -> Result<Task, String> {
+    let mut conn = db::connect(&app)?;
+    promote(&mut conn, &subtask_id)
+}
+
+#[tauri::command]
+pub async fn demote_task(app: AppHandle, task_id: String, new_parent_id: String) -> Result<Subtask, String> {
+    let mut conn = db::connect(&app)?;
+    demote(&mut conn, &task_id, &new_parent_id)
+}
+
+#[tauri::command]
+pub async fn resequence_list(app: AppHandle, list_id: String) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    resequence_lists(&mut conn, Some(&list_id))
+}
+
+#[tauri::command]
+pub async fn resequence_all(app: AppHandle) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    resequence_lists(&mut conn, None)
+}
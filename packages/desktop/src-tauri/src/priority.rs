@@ -0,0 +1,43 @@
+use crate::{dates, db, journal, tray};
+use rusqlite::{params, Connection};
+use tauri::AppHandle;
+
+// Give `ids` the priority `priority` (1 high to 3 low), or none with `None`, returns how
+// many changed. Unknown ids and tasks in the trash are skipped. Nothing else needs to
+// follow: `group_category` only depends on the due date and focus scores are computed on
+// every read.
+pub fn assign(conn: &mut Connection, ids: &[String], priority: Option<i64>) -> Result<usize, String> {
+    if let Some(priority) = priority && !(1..=3).contains(&priority) {
+        return Err(format!("Priority must be between 1 and 3, got {}", priority));
+    }
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "set_priority").map_err(|e| e.to_string())?;
+    let mut changed = 0;
+    {
+        let mut stmt = tx
+            .prepare(
+                "UPDATE tasks SET priority = ?1, updated_at = ?2
+                 WHERE id = ?3 AND priority IS NOT ?1 AND deleted_at IS NULL AND list_name != 'Trash'",
+            )
+            .map_err(|e| e.to_string())?;
+        for id in ids {
+            changed += stmt.execute(params![priority, now, id]).map_err(|e| e.to_string())?;
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(changed)
+}
+
+#[tauri::command]
+pub async fn set_priority(app: AppHandle, task_ids: Vec<String>, priority: Option<i64>) -> Result<usize, String> {
+    let changed = {
+        let mut conn = db::connect(&app)?;
+        assign(&mut conn, &task_ids, priority)?
+    };
+    if changed > 0 && let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(changed)
+}
@@ -0,0 +1,260 @@
+use crate::db;
+use crate::tasks::{self, Subtask, Task, SUBTASK_COLUMNS, TASK_COLUMNS};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+const MAX_PAGE_SIZE: i64 = 500;
+const MAX_QUERY_RESULTS: i64 = 2000;
+
+// Position after the last task of a page, pages are ordered by ("order", id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCursor {
+    pub order: i64,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    // Subtasks of the tasks on this page
+    pub subtasks: Vec<Subtask>,
+    pub next_cursor: Option<TaskCursor>,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct PageFilter<'a> {
+    // `None` pages through every list except the trash
+    pub list_id: Option<&'a str>,
+    pub completed: Option<bool>,
+    pub group_category: Option<&'a str>,
+}
+
+fn subtasks_of(conn: &Connection, tasks: &[Task]) -> rusqlite::Result<Vec<Subtask>> {
+    if tasks.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; tasks.len()].join(", ");
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT {} FROM subtasks WHERE deleted_at IS NULL AND parent_id IN ({}) ORDER BY parent_id, "order""#,
+        SUBTASK_COLUMNS, placeholders
+    ))?;
+    let rows = stmt.query_map(params_from_iter(tasks.iter().map(|t| &t.id)), Subtask::from_row)?;
+    rows.collect()
+}
+
+// Keyset pagination: a page starts strictly after the cursor, so tasks inserted or
+// moved in between never shift later pages the way OFFSET would.
+pub fn page(conn: &Connection, filter: &PageFilter, cursor: Option<&TaskCursor>, limit: i64) -> rusqlite::Result<TaskPage> {
+    let limit = limit.clamp(1, MAX_PAGE_SIZE);
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM tasks
+        WHERE deleted_at IS NULL
+          AND (?1 IS NULL AND list_name != 'Trash' OR list_id = ?1)
+          AND (?2 IS NULL OR completed = ?2)
+          AND (?3 IS NULL OR group_category = ?3)
+          AND (?4 IS NULL OR ("order", id) > (?4, ?5))
+        ORDER BY "order", id
+        LIMIT ?6
+        "#,
+        TASK_COLUMNS
+    ))?;
+    let mut tasks = stmt
+        .query_map(
+            params![
+                filter.list_id,
+                filter.completed,
+                filter.group_category,
+                cursor.map(|c| c.order),
+                cursor.map(|c| c.id.as_str()),
+                // One extra row tells whether another page follows
+                limit + 1,
+            ],
+            Task::from_row,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let has_more = tasks.len() as i64 > limit;
+    tasks.truncate(limit as usize);
+    let next_cursor = tasks.last().filter(|_| has_more).map(|task| TaskCursor {
+        order: task.order,
+        id: task.id.clone(),
+    });
+    let subtasks = subtasks_of(conn, &tasks)?;
+
+    Ok(TaskPage {
+        tasks,
+        subtasks,
+        next_cursor,
+        has_more,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortBy {
+    #[default]
+    Order,
+    DueDate,
+    Priority,
+    CreatedAt,
+    UpdatedAt,
+    Title,
+}
+
+impl SortBy {
+    // Tasks without a due date or priority sort last either way
+    fn order_by(self, descending: bool) -> String {
+        let direction = if descending { "DESC" } else { "ASC" };
+        let key = match self {
+            SortBy::Order => format!(r#""order" {}"#, direction),
+            SortBy::DueDate => format!("due_date IS NULL, due_date {}", direction),
+            SortBy::Priority => format!("priority IS NULL, priority {}", direction),
+            SortBy::CreatedAt => format!("created_at {}", direction),
+            SortBy::UpdatedAt => format!("updated_at {}", direction),
+            SortBy::Title => format!("title COLLATE NOCASE {}", direction),
+        };
+        format!("{}, id", key)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TaskFilter {
+    // Empty or missing searches every list except the trash
+    pub list_ids: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+    pub tag_match: TagMatch,
+    // Inclusive, 1 is the highest priority. Tasks without one never match a range.
+    pub priority_min: Option<i64>,
+    pub priority_max: Option<i64>,
+    pub completed: Option<bool>,
+    // `due_after` <= due date < `due_before`
+    pub due_before: Option<i64>,
+    pub due_after: Option<i64>,
+    // Case-insensitive substring of the title or notes
+    pub text: Option<String>,
+    pub sort_by: SortBy,
+    pub descending: bool,
+    pub limit: Option<i64>,
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+fn escape_like(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '%' | '_') {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+// Every condition binds its values as parameters, user input never becomes SQL. Tags are
+// matched with `json_each` over the JSON column, case-insensitively; a task matches all of
+// the given tags when the number of distinct ones it has among them is the full count.
+pub fn query(conn: &Connection, filter: &TaskFilter) -> rusqlite::Result<Vec<Task>> {
+    let mut conditions: Vec<String> = vec!["deleted_at IS NULL".into()];
+    let mut values: Vec<Value> = Vec::new();
+
+    match filter.list_ids.as_deref() {
+        Some(ids) if !ids.is_empty() => {
+            conditions.push(format!("list_id IN ({})", placeholders(ids.len())));
+            values.extend(ids.iter().cloned().map(Value::Text));
+        }
+        _ => conditions.push("list_name != 'Trash'".into()),
+    }
+
+    let tags = tasks::normalize_tags(filter.tags.clone().unwrap_or_default());
+    if !tags.is_empty() {
+        let matching = format!(
+            "SELECT COUNT(DISTINCT lower(t.value)) FROM json_each(CASE WHEN json_valid(tasks.tags) THEN tasks.tags ELSE '[]' END) t
+             WHERE t.type = 'text' AND t.value COLLATE NOCASE IN ({})",
+            placeholders(tags.len())
+        );
+        conditions.push(match filter.tag_match {
+            TagMatch::Any => format!("({}) > 0", matching),
+            TagMatch::All => format!("({}) = {}", matching, tags.len()),
+        });
+        values.extend(tags.into_iter().map(Value::Text));
+    }
+
+    if let Some(min) = filter.priority_min {
+        conditions.push("priority >= ?".into());
+        values.push(Value::Integer(min));
+    }
+    if let Some(max) = filter.priority_max {
+        conditions.push("priority <= ?".into());
+        values.push(Value::Integer(max));
+    }
+    if let Some(completed) = filter.completed {
+        conditions.push("completed = ?".into());
+        values.push(Value::Integer(completed as i64));
+    }
+    if let Some(before) = filter.due_before {
+        conditions.push("due_date < ?".into());
+        values.push(Value::Integer(before));
+    }
+    if let Some(after) = filter.due_after {
+        conditions.push("due_date >= ?".into());
+        values.push(Value::Integer(after));
+    }
+    if let Some(text) = filter.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        conditions.push(r"(title LIKE ? ESCAPE '\' OR content LIKE ? ESCAPE '\')".into());
+        let pattern = format!("%{}%", escape_like(text));
+        values.push(Value::Text(pattern.clone()));
+        values.push(Value::Text(pattern));
+    }
+
+    let limit = filter.limit.unwrap_or(MAX_QUERY_RESULTS).clamp(1, MAX_QUERY_RESULTS);
+    values.push(Value::Integer(limit));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM tasks WHERE {} ORDER BY {} LIMIT ?",
+        TASK_COLUMNS,
+        conditions.join(" AND "),
+        filter.sort_by.order_by(filter.descending)
+    ))?;
+    let rows = stmt.query_map(params_from_iter(values), Task::from_row)?;
+    rows.collect()
+}
+
+#[tauri::command]
+pub async fn list_tasks_page(
+    app: AppHandle,
+    list_id: Option<String>,
+    cursor: Option<TaskCursor>,
+    limit: i64,
+    completed: Option<bool>,
+    group_category: Option<String>,
+) -> Result<TaskPage, String> {
+    let conn = db::connect(&app)?;
+    let filter = PageFilter {
+        list_id: list_id.as_deref(),
+        completed,
+        group_category: group_category.as_deref(),
+    };
+    page(&conn, &filter, cursor.as_ref(), limit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn query_tasks(app: AppHandle, filter: TaskFilter) -> Result<Vec<Task>, String> {
+    let conn = db::connect(&app)?;
+    query(&conn, &filter).map_err(|e| e.to_string())
+}
@@ -0,0 +1,173 @@
+use crate::settings;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// Set to anything but "0" to log queries for this run without touching the settings
+pub const ENV_VAR: &str = "TADA_QUERY_LOG";
+const LOG_DIR: &str = "logs";
+const LOG_FILE: &str = "queries.log";
+// Rotated to queries.log.1 .. queries.log.N once the current file reaches the size
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+const KEPT_LOGS: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStat {
+    pub sql: String,
+    pub count: u64,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+struct QueryLog {
+    stats: Mutex<HashMap<String, QueryStat>>,
+    file: Mutex<Option<LogFile>>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self { path, file, written })
+    }
+
+    fn rotated(path: &Path, n: usize) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let _ = fs::remove_file(Self::rotated(&self.path, KEPT_LOGS));
+        for n in (1..KEPT_LOGS).rev() {
+            let _ = fs::rename(Self::rotated(&self.path, n), Self::rotated(&self.path, n + 1));
+        }
+        fs::rename(&self.path, Self::rotated(&self.path, 1))?;
+        *self = Self::open(self.path.clone())?;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.written >= MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+// Only set when logging was enabled at startup, connections without it have no profile
+// callback installed at all
+static QUERY_LOG: OnceLock<QueryLog> = OnceLock::new();
+
+pub fn log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(LOG_DIR).join(LOG_FILE))
+}
+
+// The environment variable wins, otherwise `preferences.queryLog`. Read once when the pool
+// is created, so changing the setting takes effect on the next start.
+pub fn wanted(db_path: &Path) -> bool {
+    if let Ok(value) = std::env::var(ENV_VAR) {
+        return value != "0";
+    }
+    Connection::open(db_path)
+        .ok()
+        .and_then(|conn| settings::read(&conn, "preferences").ok().flatten())
+        .and_then(|p| p.get("queryLog").and_then(|v| v.as_bool()))
+        .unwrap_or(false)
+}
+
+pub fn enable(log_path: PathBuf) {
+    let file = match LogFile::open(log_path) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            // Stats are still collected
+            eprintln!("Failed to open the query log: {}", e);
+            None
+        }
+    };
+    let _ = QUERY_LOG.set(QueryLog {
+        stats: Mutex::new(HashMap::new()),
+        file: Mutex::new(file),
+    });
+}
+
+pub fn is_enabled() -> bool {
+    QUERY_LOG.get().is_some()
+}
+
+// Called for every new pool connection
+pub fn install(conn: &mut Connection) {
+    if is_enabled() {
+        conn.profile(Some(record));
+    }
+}
+
+// Statements are grouped by their text with whitespace collapsed, parameters aren't part
+// of it so every call of the same prepared query lands in one entry
+fn record(sql: &str, elapsed: Duration) {
+    let Some(log) = QUERY_LOG.get() else {
+        return;
+    };
+    let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    let ms = elapsed.as_secs_f64() * 1000.0;
+
+    if let Ok(mut stats) = log.stats.lock() {
+        let stat = stats.entry(sql.clone()).or_insert_with(|| QueryStat {
+            sql: sql.clone(),
+            count: 0,
+            total_ms: 0.0,
+            max_ms: 0.0,
+        });
+        stat.count += 1;
+        stat.total_ms += ms;
+        stat.max_ms = stat.max_ms.max(ms);
+    }
+    let Ok(mut file) = log.file.lock() else {
+        return;
+    };
+    if let Some(file) = file.as_mut() {
+        let line = format!("{}\t{:.3}ms\t{}\n", chrono::Local::now().to_rfc3339(), ms, sql);
+        if let Err(e) = file.write_line(&line) {
+            eprintln!("Failed to write the query log: {}", e);
+        }
+    }
+}
+
+// Slowest in total first
+pub fn stats() -> Vec<QueryStat> {
+    let Some(log) = QUERY_LOG.get() else {
+        return Vec::new();
+    };
+    let mut stats: Vec<QueryStat> = log
+        .stats
+        .lock()
+        .map(|stats| stats.values().cloned().collect())
+        .unwrap_or_default();
+    stats.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+    stats
+}
+
+// Empty unless query logging was enabled at startup
+#[tauri::command]
+pub async fn get_query_stats() -> Result<Vec<QueryStat>, String> {
+    Ok(stats())
+}
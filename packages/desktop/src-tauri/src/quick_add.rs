@@ -0,0 +1,179 @@
+use crate::export::{self, INBOX_LIST_ID};
+use crate::reminders::ReminderScheduler;
+use crate::tasks::{self, NewTask, Task};
+use crate::{dates, db, list_defaults, natural_dates, settings};
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const WINDOW_LABEL: &str = "quick-add";
+pub const TASK_CREATED_EVENT: &str = "task-created";
+const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+// Show the capture window, creating it on first use. Closing it only hides it (see the
+// window event handler in `run`), so later openings are instant.
+#[cfg(desktop)]
+pub fn open_window(app: &AppHandle) {
+    use tauri::{WebviewUrl, WebviewWindowBuilder};
+
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+    let built = WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App("quick-add.html".into()))
+        .title("Quick add")
+        .inner_size(560.0, 64.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build();
+    if let Err(e) = built {
+        eprintln!("Failed to open the quick add window: {}", e);
+    }
+}
+
+// Shortcut from the `shortcuts` settings row, in the accelerator syntax of the
+// global-shortcut plugin
+pub fn configured_shortcut(conn: &Connection) -> String {
+    settings::read(conn, "shortcuts")
+        .ok()
+        .flatten()
+        .and_then(|s| s.get("quickAdd").and_then(Value::as_str).map(String::from))
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string())
+}
+
+#[cfg(desktop)]
+pub fn register(app: &AppHandle, shortcut: &str) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                open_window(app);
+            }
+        })
+        .map_err(|e| format!("Failed to register the shortcut '{}': {}", shortcut, e))
+}
+
+#[cfg(desktop)]
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let conn = db::connect(app)?;
+    let shortcut = configured_shortcut(&conn);
+    register(app, &shortcut)
+}
+
+// The list named by `preferences.defaultNewTaskList`, falling back to the inbox
+fn default_list(conn: &Connection) -> rusqlite::Result<(String, String)> {
+    let preferred = settings::read(conn, "preferences")?
+        .and_then(|p| p.get("defaultNewTaskList").and_then(Value::as_str).map(String::from));
+    if let Some(name) = preferred {
+        let list: Option<(String, String)> = conn
+            .query_row(
+                r#"SELECT id, name FROM lists WHERE name = ?1 AND deleted_at IS NULL ORDER BY "order" LIMIT 1"#,
+                [&name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if let Some(list) = list {
+            return Ok(list);
+        }
+    }
+    let inbox_name = export::ensure_inbox(conn)?;
+    Ok((INBOX_LIST_ID.to_string(), inbox_name))
+}
+
+// Pull a date phrase out of a typed title. A title that is nothing but a date phrase stays
+// as typed.
+pub fn split_due_date(text: &str, now_ms: i64, language: natural_dates::Language) -> (String, Option<i64>) {
+    match natural_dates::find(text, now_ms, language) {
+        Some(found) => {
+            let title = natural_dates::strip(text, found.range);
+            if title.is_empty() {
+                (text.to_string(), None)
+            } else {
+                (title, Some(found.due_date))
+            }
+        }
+        None => (text.to_string(), None),
+    }
+}
+
+pub fn add(conn: &Connection, text: &str) -> Result<Task, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Title can't be empty".to_string());
+    }
+    let language = natural_dates::Language::from_setting(settings::language(conn).as_deref());
+    let (title, due_date) = split_due_date(text, dates::now_ms(), language);
+
+    let (list_id, list_name) = default_list(conn).map_err(|e| e.to_string())?;
+    let mut task = NewTask {
+        title,
+        due_date,
+        ..Default::default()
+    };
+    // The list's own defaults come before the app-wide default priority
+    if let Some(defaults) = list_defaults::load(conn, &list_id).map_err(|e| e.to_string())? {
+        defaults.apply(&mut task);
+    }
+    if task.priority.is_none() {
+        task.priority = settings::read(conn, "preferences")
+            .map_err(|e| e.to_string())?
+            .and_then(|p| p.get("defaultNewTaskPriority").and_then(Value::as_i64));
+    }
+    let id = tasks::insert(conn, &list_id, &list_name, &task).map_err(|e| e.to_string())?;
+    tasks::get(conn, &id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "The new task disappeared".to_string())
+}
+
+#[tauri::command]
+pub async fn quick_add_task(app: AppHandle, title: String) -> Result<Task, String> {
+    let conn = db::connect(&app)?;
+    let task = add(&conn, &title)?;
+    // The list's defaults may have given it a reminder
+    app.state::<ReminderScheduler>().wake();
+    // The main window keeps its own copy of the tasks
+    let _ = app.emit(TASK_CREATED_EVENT, &task);
+    Ok(task)
+}
+
+// Swap the global shortcut at runtime. The old one stays active if the new one can't be
+// registered (taken by another app, or not a valid accelerator).
+#[tauri::command]
+pub async fn set_quick_add_shortcut(app: AppHandle, shortcut: String) -> Result<(), String> {
+    #[cfg(desktop)]
+    {
+        use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+        let conn = db::connect(&app)?;
+        let previous = configured_shortcut(&conn);
+        let shortcut = shortcut.trim().to_string();
+        if shortcut == previous {
+            return Ok(());
+        }
+
+        let _ = app.global_shortcut().unregister(previous.as_str());
+        if let Err(e) = register(&app, &shortcut) {
+            let _ = register(&app, &previous);
+            return Err(e);
+        }
+
+        let mut shortcuts = settings::read(&conn, "shortcuts")
+            .map_err(|e| e.to_string())?
+            .filter(Value::is_object)
+            .unwrap_or_else(|| json!({}));
+        shortcuts["quickAdd"] = Value::String(shortcut);
+        settings::write(&conn, "shortcuts", &shortcuts).map_err(|e| e.to_string())
+    }
+    #[cfg(not(desktop))]
+    {
+        let _ = (app, shortcut);
+        Err("Global shortcuts are only available on desktop".to_string())
+    }
+}
@@ -0,0 +1,521 @@
+use crate::{dates, db, journal, settings, tasks};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::AppHandle;
+use uuid::Uuid;
+
+// How far ahead instances are generated when the app starts
+pub const STARTUP_HORIZON_DAYS: i64 = 14;
+const MAX_HORIZON_DAYS: i64 = 366;
+// Safety net for rules that rarely (or never) produce an occurrence, e.g. BYMONTHDAY=31 every 2 months
+const MAX_PERIODS: i64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+// The subset of RFC 5545 RRULE used by the app: FREQ, INTERVAL, COUNT, UNTIL, BYDAY
+// (with optional ordinals for monthly rules, e.g. `2MO` or `-1FR`) and BYMONTHDAY.
+#[derive(Debug, Clone)]
+pub struct RecurrenceRule {
+    freq: Frequency,
+    interval: i64,
+    count: Option<u32>,
+    until: Option<NaiveDateTime>,
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+}
+
+impl RecurrenceRule {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let body = input.trim();
+        let body = body.strip_prefix("RRULE:").unwrap_or(body);
+
+        let mut freq = None;
+        let mut interval = 1;
+        let mut count = None;
+        let mut until = None;
+        let mut by_day = Vec::new();
+        let mut by_month_day = Vec::new();
+
+        for part in body.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Malformed RRULE part '{}'", part))?;
+            let value = value.trim();
+
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("Unsupported FREQ '{}'", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|i| *i > 0)
+                        .ok_or_else(|| format!("Invalid INTERVAL '{}'", value))?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| format!("Invalid COUNT '{}'", value))?,
+                    )
+                }
+                "UNTIL" => until = Some(parse_until(value)?),
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(parse_by_day)
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                "BYMONTHDAY" => {
+                    by_month_day = value
+                        .split(',')
+                        .map(|d| {
+                            d.trim()
+                                .parse::<i32>()
+                                .ok()
+                                .filter(|d| *d != 0 && d.abs() <= 31)
+                                .ok_or_else(|| format!("Invalid BYMONTHDAY '{}'", d))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                // Weeks always start on Monday here
+                "WKST" => {}
+                other => return Err(format!("Unsupported RRULE part '{}'", other)),
+            }
+        }
+
+        let freq = freq.ok_or_else(|| "RRULE is missing FREQ".to_string())?;
+        if freq == Frequency::Yearly && (!by_day.is_empty() || !by_month_day.is_empty()) {
+            return Err("BYDAY/BYMONTHDAY are not supported for yearly rules".to_string());
+        }
+        if freq != Frequency::Monthly && by_day.iter().any(|(ordinal, _)| ordinal.is_some()) {
+            return Err("Ordinal BYDAY values are only supported for monthly rules".to_string());
+        }
+
+        Ok(Self {
+            freq,
+            interval,
+            count,
+            until,
+            by_day,
+            by_month_day,
+        })
+    }
+
+    // Occurrences strictly after `after` and up to `end` (inclusive)
+    pub fn occurrences_between(
+        &self,
+        dtstart: NaiveDateTime,
+        after: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<NaiveDateTime> {
+        let mut found = Vec::new();
+        self.walk(dtstart, |occurrence| {
+            if occurrence > end {
+                return false;
+            }
+            if occurrence > after {
+                found.push(occurrence);
+            }
+            true
+        });
+        found
+    }
+
+    // First occurrence strictly after `after`, `None` once COUNT/UNTIL is exhausted
+    pub fn next_after(&self, dtstart: NaiveDateTime, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut next = None;
+        self.walk(dtstart, |occurrence| {
+            if occurrence > after {
+                next = Some(occurrence);
+                return false;
+            }
+            true
+        });
+        next
+    }
+
+    // Visit occurrences in chronological order. `dtstart` is the first occurrence, as in
+    // RFC 5545, and counts towards COUNT. Stops as soon as `visit` returns false.
+    fn walk(&self, dtstart: NaiveDateTime, mut visit: impl FnMut(NaiveDateTime) -> bool) {
+        let mut emitted = 0u32;
+        for period in 0..MAX_PERIODS {
+            let Some(candidates) = self.period_candidates(dtstart, period * self.interval) else {
+                return;
+            };
+            for candidate in candidates {
+                if candidate < dtstart {
+                    continue;
+                }
+                if self.until.is_some_and(|until| candidate > until)
+                    || self.count.is_some_and(|count| emitted >= count)
+                {
+                    return;
+                }
+                emitted += 1;
+                if !visit(candidate) {
+                    return;
+                }
+            }
+        }
+    }
+
+    // Sorted candidate datetimes for the period `offset` units of FREQ after dtstart's
+    // period. `None` means the calendar ran out of range.
+    fn period_candidates(&self, dtstart: NaiveDateTime, offset: i64) -> Option<Vec<NaiveDateTime>> {
+        let start = dtstart.date();
+        let time = dtstart.time();
+
+        let mut days: Vec<NaiveDate> = match self.freq {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(TimeDelta::try_days(offset)?)?;
+                let weekday_ok = self.by_day.is_empty()
+                    || self.by_day.iter().any(|(_, wd)| *wd == day.weekday());
+                if weekday_ok && self.matches_month_day(day) {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let monday = start
+                    .checked_sub_signed(TimeDelta::try_days(start.weekday().num_days_from_monday() as i64)?)?
+                    .checked_add_signed(TimeDelta::try_weeks(offset)?)?;
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, wd)| *wd).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .filter_map(|wd| {
+                        monday.checked_add_signed(TimeDelta::days(wd.num_days_from_monday() as i64))
+                    })
+                    .filter(|day| self.matches_month_day(*day))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let total = start.year() as i64 * 12 + start.month0() as i64 + offset;
+                let year = i32::try_from(total.div_euclid(12)).ok()?;
+                let month = total.rem_euclid(12) as u32 + 1;
+                let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+                let last_day = days_in_month(first)?;
+
+                if !self.by_month_day.is_empty() {
+                    self.by_month_day
+                        .iter()
+                        .filter_map(|d| {
+                            let day = if *d > 0 { *d } else { last_day as i32 + d + 1 };
+                            u32::try_from(day).ok().and_then(|day| first.with_day(day))
+                        })
+                        .collect()
+                } else if !self.by_day.is_empty() {
+                    let mut days = Vec::new();
+                    for (ordinal, weekday) in &self.by_day {
+                        let matching: Vec<NaiveDate> = (1..=last_day)
+                            .filter_map(|d| first.with_day(d))
+                            .filter(|day| day.weekday() == *weekday)
+                            .collect();
+                        match ordinal {
+                            Some(n) if *n > 0 => days.extend(matching.get(*n as usize - 1)),
+                            Some(n) => days.extend(
+                                (matching.len() as i32 + n)
+                                    .try_into()
+                                    .ok()
+                                    .and_then(|i: usize| matching.get(i)),
+                            ),
+                            None => days.extend(matching),
+                        }
+                    }
+                    days
+                } else {
+                    // Months without dtstart's day (e.g. the 31st) are skipped, as in RFC 5545
+                    first.with_day(start.day()).into_iter().collect()
+                }
+            }
+            Frequency::Yearly => {
+                let year = i32::try_from(start.year() as i64 + offset).ok()?;
+                // Guard against running off the end of the calendar
+                NaiveDate::from_ymd_opt(year, 1, 1)?;
+                NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        days.sort();
+        days.dedup();
+        Some(days.into_iter().map(|day| day.and_time(time)).collect())
+    }
+
+    fn matches_month_day(&self, day: NaiveDate) -> bool {
+        if self.by_month_day.is_empty() {
+            return true;
+        }
+        let last_day = days_in_month(day).unwrap_or(31) as i32;
+        self.by_month_day.iter().any(|d| {
+            let wanted = if *d > 0 { *d } else { last_day + d + 1 };
+            wanted == day.day() as i32
+        })
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> Option<u32> {
+    let first = date.with_day(1)?;
+    let next = first.checked_add_months(chrono::Months::new(1))?;
+    Some(next.pred_opt()?.day())
+}
+
+fn parse_by_day(value: &str) -> Result<(Option<i32>, Weekday), String> {
+    let value = value.trim().to_ascii_uppercase();
+    if value.len() < 2 || !value.is_ascii() {
+        return Err(format!("Invalid BYDAY '{}'", value));
+    }
+    let (ordinal, day) = value.split_at(value.len() - 2);
+    let weekday = match day {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(format!("Invalid BYDAY '{}'", value)),
+    };
+    let ordinal = if ordinal.is_empty() {
+        None
+    } else {
+        Some(
+            ordinal
+                .parse::<i32>()
+                .ok()
+                .filter(|n| *n != 0 && n.abs() <= 5)
+                .ok_or_else(|| format!("Invalid BYDAY '{}'", value))?,
+        )
+    };
+    Ok((ordinal, weekday))
+}
+
+fn parse_until(value: &str) -> Result<NaiveDateTime, String> {
+    let invalid = || format!("Invalid UNTIL '{}'", value);
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return dates::to_local_naive(naive.and_utc().timestamp_millis()).ok_or_else(invalid);
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Ok(naive);
+    }
+    // A bare date includes the whole day
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+    Ok(date.and_time(NaiveTime::MIN) + TimeDelta::days(1) - TimeDelta::seconds(1))
+}
+
+struct RecurringTask {
+    id: String,
+    title: String,
+    content: Option<String>,
+    list_id: Option<String>,
+    list_name: String,
+    tags: Option<String>,
+    priority: Option<i64>,
+    due_date: i64,
+    recurrence: String,
+}
+
+const RECURRING_COLUMNS: &str = "id, title, content, list_id, list_name, tags, priority, due_date, recurrence";
+
+impl RecurringTask {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            list_id: row.get(3)?,
+            list_name: row.get(4)?,
+            tags: row.get(5)?,
+            priority: row.get(6)?,
+            due_date: row.get(7)?,
+            recurrence: row.get(8)?,
+        })
+    }
+}
+
+// Where generation resumes: the last occurrence generated for the series (recorded on the
+// head by `insert_instance`), or DTSTART itself. Instances that were deleted or moved
+// since don't change it, so nothing is recreated or skipped. A head that never had it
+// recorded falls back to its latest instance.
+fn resume_after(conn: &Connection, head_id: &str, dtstart: NaiveDateTime) -> rusqlite::Result<NaiveDateTime> {
+    let latest: Option<i64> = conn.query_row(
+        "SELECT COALESCE(recurrence_materialized_until,
+                         (SELECT MAX(due_date) FROM tasks WHERE recurrence_parent_id = ?1))
+         FROM tasks WHERE id = ?1",
+        [head_id],
+        |row| row.get(0),
+    )?;
+    Ok(latest
+        .and_then(dates::to_local_naive)
+        .map_or(dtstart, |latest| latest.max(dtstart)))
+}
+
+fn insert_instance(conn: &Connection, head: &RecurringTask, due_date: i64, order: i64, now: i64) -> rusqlite::Result<String> {
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        r#"
+        INSERT INTO tasks (id, title, completed, due_date, list_id, list_name, content, "order",
+                           created_at, updated_at, tags, priority, group_category, recurrence_parent_id)
+        VALUES (?1, ?2, 0, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9, ?10, ?11, ?12)
+        "#,
+        params![
+            id,
+            head.title,
+            due_date,
+            head.list_id,
+            head.list_name,
+            head.content,
+            order,
+            now,
+            head.tags,
+            head.priority,
+            dates::group_category(Some(due_date), false, now),
+            head.id,
+        ],
+    )?;
+    conn.execute(
+        "UPDATE tasks SET recurrence_materialized_until = MAX(COALESCE(recurrence_materialized_until, ?1), ?1)
+         WHERE id = ?2",
+        params![due_date, head.id],
+    )?;
+    Ok(id)
+}
+
+// Generate concrete instances of every recurring task up to `now + horizon_days`.
+// The task holding the RRULE is the series' first occurrence (its due date is DTSTART);
+// instances point back at it through `recurrence_parent_id`. Generation resumes after the
+// last occurrence generated before (see `resume_after`), so instances the user deleted or
+// rescheduled are neither recreated nor skipped past.
+pub fn materialize(conn: &mut Connection, horizon_days: i64) -> Result<usize, String> {
+    let now = dates::now_ms();
+    let horizon_end = dates::to_local_naive(now + horizon_days * 86_400_000)
+        .ok_or_else(|| "Invalid horizon".to_string())?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::pause(&tx).map_err(|e| e.to_string())?;
+
+    let recurring = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT {} FROM tasks
+                 WHERE recurrence IS NOT NULL AND recurrence_parent_id IS NULL AND due_date IS NOT NULL
+                   AND deleted_at IS NULL",
+                RECURRING_COLUMNS
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], RecurringTask::from_row).map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut created = 0;
+    for task in recurring {
+        let rule = match RecurrenceRule::parse(&task.recurrence) {
+            Ok(rule) => rule,
+            Err(e) => {
+                eprintln!("Skipping task {} with invalid recurrence: {}", task.id, e);
+                continue;
+            }
+        };
+        let Some(dtstart) = dates::to_local_naive(task.due_date) else {
+            continue;
+        };
+
+        let after = resume_after(&tx, &task.id, dtstart).map_err(|e| e.to_string())?;
+
+        let occurrences = rule.occurrences_between(dtstart, after, horizon_end);
+        if occurrences.is_empty() {
+            continue;
+        }
+
+        let first_order = tasks::next_order(&tx, task.list_id.as_deref()).map_err(|e| e.to_string())?;
+        for (order, occurrence) in (first_order..).zip(occurrences) {
+            insert_instance(&tx, &task, dates::from_local_naive(occurrence), order, now)
+                .map_err(|e| e.to_string())?;
+            created += 1;
+        }
+    }
+
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(created)
+}
+
+// Only when chosen, unreadable preferences keep the startup materialization
+pub fn spawns_on_complete(conn: &Connection) -> bool {
+    settings::get_typed::<settings::Preferences>(conn)
+        .map(|p| p.recurrence_mode == settings::RECURRENCE_SPAWN_ON_COMPLETE)
+        .unwrap_or(false)
+}
+
+// Called once `task_id` is completed: add the series' next occurrence after its latest
+// one, the completed tasks stay as they are. Nothing is added while another occurrence
+// of the series is still open or once COUNT/UNTIL is used up. Returns the new task's id.
+pub fn spawn_next(conn: &Connection, task_id: &str, now: i64) -> Result<Option<String>, String> {
+    let head = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM tasks
+                 WHERE id = COALESCE((SELECT recurrence_parent_id FROM tasks WHERE id = ?1), ?1)
+                   AND recurrence IS NOT NULL AND due_date IS NOT NULL AND deleted_at IS NULL",
+                RECURRING_COLUMNS
+            ),
+            [task_id],
+            RecurringTask::from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(head) = head else {
+        return Ok(None);
+    };
+    let open: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM tasks
+                            WHERE (id = ?1 OR recurrence_parent_id = ?1) AND completed = 0 AND deleted_at IS NULL)",
+            [&head.id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if open {
+        return Ok(None);
+    }
+
+    let rule = RecurrenceRule::parse(&head.recurrence)?;
+    let Some(dtstart) = dates::to_local_naive(head.due_date) else {
+        return Ok(None);
+    };
+    let after = resume_after(conn, &head.id, dtstart).map_err(|e| e.to_string())?;
+    let Some(next) = rule.next_after(dtstart, after) else {
+        return Ok(None);
+    };
+    let order = tasks::next_order(conn, head.list_id.as_deref()).map_err(|e| e.to_string())?;
+    insert_instance(conn, &head, dates::from_local_naive(next), order, now)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn materialize_recurrences(app: AppHandle, horizon_days: i64) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    materialize(&mut conn, horizon_days.clamp(0, MAX_HORIZON_DAYS))
+}
@@ -0,0 +1,250 @@
+use crate::{backup, db, export, AppState};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+// Where the database lives when it isn't in the app config directory. The file itself
+// always stays in the config directory, it's read before anything opens the database.
+const LOCATION_FILE: &str = "database-location.json";
+// Files that make up the database, the WAL and SHM ones may be missing
+const DB_SUFFIXES: &[&str] = &["", "-wal", "-shm"];
+// Probe writes are done in chunks of this size
+const PROBE_CHUNK: usize = 1 << 20;
+// Time for the command's answer to reach the frontend before the relaunch
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Location {
+    // `None` for the config directory
+    dir: Option<PathBuf>,
+    // Staged by `relocate_database`, moved to by `apply_pending` on the next launch
+    pending: Option<PathBuf>,
+    // Why the last move was rolled back
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseLocation {
+    pub path: String,
+    pub is_default: bool,
+    pub pending: Option<String>,
+    pub last_error: Option<String>,
+}
+
+fn read_location(config_dir: &Path) -> Location {
+    fs::read_to_string(config_dir.join(LOCATION_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_location(config_dir: &Path, location: &Location) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(location).map_err(|e| e.to_string())?;
+    export::write_file_atomic(&config_dir.join(LOCATION_FILE), &text)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", path.display(), suffix))
+}
+
+// The directory holding tada.db. A recorded directory that has gone missing (an unplugged
+// disk) is an error: opening the config directory instead would start an empty database.
+pub fn database_dir(config_dir: &Path) -> Result<PathBuf, String> {
+    match read_location(config_dir).dir {
+        Some(dir) if dir.is_dir() => Ok(dir),
+        Some(dir) => Err(format!(
+            "The database folder '{}' is not available. Reconnect the drive it is on, or delete '{}' to start over with a database in '{}'.",
+            dir.display(),
+            config_dir.join(LOCATION_FILE).display(),
+            config_dir.display()
+        )),
+        None => Ok(config_dir.to_path_buf()),
+    }
+}
+
+// Copy every database file into `target`, as `.partial` files first so a half-written copy
+// never looks like a database
+fn copy_files(from: &Path, target: &Path) -> std::io::Result<()> {
+    let live = from.join(db::DB_FILE);
+    let moved = target.join(db::DB_FILE);
+    for suffix in DB_SUFFIXES {
+        let source = with_suffix(&live, suffix);
+        if source.exists() {
+            let partial = with_suffix(&moved, &format!("{}.partial", suffix));
+            fs::copy(&source, &partial)?;
+            File::open(&partial)?.sync_all()?;
+        }
+    }
+    for suffix in DB_SUFFIXES {
+        let partial = with_suffix(&moved, &format!("{}.partial", suffix));
+        if partial.exists() {
+            fs::rename(&partial, with_suffix(&moved, suffix))?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_files(dir: &Path) {
+    let path = dir.join(db::DB_FILE);
+    for suffix in DB_SUFFIXES {
+        let _ = fs::remove_file(with_suffix(&path, suffix));
+        let _ = fs::remove_file(with_suffix(&path, &format!("{}.partial", suffix)));
+    }
+}
+
+// Carry out a move staged by `relocate_database`, before the database is opened. The files
+// are copied rather than renamed since the target is usually another disk, the originals
+// are only removed once the copies are in place and recorded. Any failure leaves the
+// database where it was, with the reason kept for `get_database_location`.
+fn apply_pending(config_dir: &Path, current: &Path) {
+    let mut location = read_location(config_dir);
+    let Some(target) = location.pending.take() else {
+        return;
+    };
+    let result = copy_files(current, &target)
+        .map_err(|e| format!("Failed to move the database to '{}': {}", target.display(), e))
+        .and_then(|_| {
+            let moved = Location {
+                dir: (!config_dir.canonicalize().is_ok_and(|dir| dir == target)).then(|| target.clone()),
+                pending: None,
+                last_error: None,
+            };
+            write_location(config_dir, &moved)
+        });
+    match result {
+        Ok(()) => remove_files(current),
+        Err(e) => {
+            eprintln!("{}", e);
+            remove_files(&target);
+            location.last_error = Some(e);
+            if let Err(e) = write_location(config_dir, &location) {
+                eprintln!("Failed to record the database location: {}", e);
+            }
+        }
+    }
+}
+
+// The URL the sql plugin preloads, the frontend loads and the migrations are registered
+// under. The plugin resolves it against the config directory, an absolute path is taken
+// as is.
+fn url_for(config_dir: &Path) -> Result<String, String> {
+    let dir = database_dir(config_dir)?;
+    if dir == config_dir {
+        return Ok(format!("sqlite:{}", db::DB_FILE));
+    }
+    Ok(format!("sqlite:{}", dir.join(db::DB_FILE).display()))
+}
+
+pub fn plugin_url(app: &AppHandle) -> Result<String, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    url_for(&config_dir)
+}
+
+// Runs before the app is built, so nothing has the database open yet: swap in a staged
+// restore, carry out a staged move and return the URL of the database where it now lives.
+// Without an app handle there's no `app_config_dir`, the directory is worked out the way
+// the desktop path resolver does it.
+pub fn prepare(config: &tauri::Config) -> Result<String, String> {
+    let Some(config_dir) = dirs::config_dir().map(|dir| dir.join(&config.identifier)) else {
+        return Ok(format!("sqlite:{}", db::DB_FILE));
+    };
+    let current = database_dir(&config_dir)?;
+    if let Err(e) = backup::apply_staged_restore(&current) {
+        eprintln!("Failed to apply staged database restore: {}", e);
+    }
+    apply_pending(&config_dir, &current);
+    url_for(&config_dir)
+}
+
+fn write_zeros(path: &Path, bytes: u64) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    let chunk = vec![0u8; PROBE_CHUNK];
+    let mut left = bytes;
+    while left > 0 {
+        let len = left.min(PROBE_CHUNK as u64) as usize;
+        file.write_all(&chunk[..len])?;
+        left -= len as u64;
+    }
+    file.sync_all()
+}
+
+// Write `bytes` to a scratch file in `dir` and remove it again: fails when the directory
+// isn't writable or hasn't the room
+fn probe(dir: &Path, bytes: u64) -> Result<(), String> {
+    let path = dir.join(format!("{}.probe", db::DB_FILE));
+    let result = write_zeros(&path, bytes);
+    let _ = fs::remove_file(&path);
+    result.map_err(|e| format!("Can't write {} bytes to '{}': {}", bytes, dir.display(), e))
+}
+
+// Stage a move of the database (with its WAL and SHM files) to `new_dir` and relaunch.
+// The sql plugin keeps the file open, so the move itself happens on the next start (see
+// `apply_pending`), where a failure keeps the database in its current place. Returns the
+// path the database will have.
+#[tauri::command]
+pub async fn relocate_database(app: AppHandle, new_dir: String) -> Result<String, String> {
+    let new_dir = new_dir.trim();
+    if new_dir.is_empty() {
+        return Err("No directory given".to_string());
+    }
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let current = database_dir(&config_dir)?;
+    fs::create_dir_all(new_dir).map_err(|e| format!("Failed to create '{}': {}", new_dir, e))?;
+    let target = Path::new(new_dir)
+        .canonicalize()
+        .map_err(|e| format!("'{}' is not accessible: {}", new_dir, e))?;
+    if current.canonicalize().is_ok_and(|current| current == target) {
+        return Err(format!("The database is already in '{}'", target.display()));
+    }
+    if target.join(db::DB_FILE).exists() {
+        return Err(format!("'{}' already holds a {}", target.display(), db::DB_FILE));
+    }
+
+    let live = current.join(db::DB_FILE);
+    let size: u64 = DB_SUFFIXES
+        .iter()
+        .filter_map(|suffix| fs::metadata(with_suffix(&live, suffix)).ok())
+        .map(|meta| meta.len())
+        .sum();
+    probe(&target, size)?;
+
+    let mut location = read_location(&config_dir);
+    location.pending = Some(target.clone());
+    location.last_error = None;
+    write_location(&config_dir, &location)?;
+
+    let path = target.join(db::DB_FILE).to_string_lossy().into_owned();
+    let handle = app.clone();
+    thread::spawn(move || {
+        thread::sleep(RESTART_DELAY);
+        // Let the relaunch close the main window instead of hiding it to the tray
+        handle.state::<AppState>().is_quitting.store(true, Ordering::Relaxed);
+        handle.restart();
+    });
+    Ok(path)
+}
+
+#[tauri::command]
+pub async fn get_database_location(app: AppHandle) -> Result<DatabaseLocation, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let location = read_location(&config_dir);
+    let dir = database_dir(&config_dir)?;
+    Ok(DatabaseLocation {
+        path: dir.join(db::DB_FILE).to_string_lossy().into_owned(),
+        is_default: dir == config_dir,
+        pending: location.pending.map(|dir| dir.to_string_lossy().into_owned()),
+        last_error: location.last_error,
+    })
+}
+
+#[tauri::command]
+pub async fn get_database_url(app: AppHandle) -> Result<String, String> {
+    plugin_url(&app)
+}
@@ -0,0 +1,203 @@
+use crate::{dates, db, settings, working_hours};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+// Tasks with a reminder that still has to fire
+const PENDING_REMINDER: &str = "completed = 0 AND due_date IS NOT NULL AND reminder_offset_minutes IS NOT NULL \
+     AND reminded_at IS NULL AND list_name != 'Trash' AND deleted_at IS NULL";
+const FIRE_AT: &str = "(due_date - reminder_offset_minutes * 60000)";
+
+// Upper bound between two looks at the database, so edits made without calling
+// `reschedule_reminders` are still picked up
+const MAX_SLEEP: Duration = Duration::from_secs(300);
+// Reminders that are this late (e.g. after the machine slept) are dropped instead of shown
+const STALE_AFTER_MS: i64 = 15 * 60 * 1000;
+
+// The scheduler keeps no timers of its own: every wake-up recomputes what is due from the
+// `tasks` table, so nothing is lost across restarts and `reminded_at` prevents repeats.
+#[derive(Default)]
+pub struct ReminderScheduler {
+    pending: Mutex<bool>,
+    signal: Condvar,
+}
+
+impl ReminderScheduler {
+    pub fn wake(&self) {
+        *self.pending.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.signal.notify_one();
+    }
+
+    fn sleep(&self, timeout: Duration) {
+        let guard = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut guard, _) = self
+            .signal
+            .wait_timeout_while(guard, timeout, |pending| !*pending)
+            .unwrap_or_else(|e| e.into_inner());
+        *guard = false;
+    }
+}
+
+struct Pending {
+    id: String,
+    title: String,
+    list_name: String,
+    due_date: i64,
+    // Moved into the working hours, if any are set
+    fire_at: i64,
+}
+
+// Every pending reminder, soonest to fire first
+fn pending(conn: &Connection) -> rusqlite::Result<Vec<Pending>> {
+    let hours = working_hours::load(conn);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, title, list_name, due_date, {} FROM tasks WHERE {}",
+        FIRE_AT, PENDING_REMINDER
+    ))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Pending {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            list_name: row.get(2)?,
+            due_date: row.get(3)?,
+            fire_at: working_hours::clamp(hours.as_ref(), row.get(4)?),
+        })
+    })?;
+    let mut pending = rows.collect::<Result<Vec<_>, _>>()?;
+    pending.sort_by(|a, b| (a.fire_at, a.due_date, &a.id).cmp(&(b.fire_at, b.due_date, &b.id)));
+    Ok(pending)
+}
+
+// Mark reminders whose fire time is before `cutoff` as handled without showing them
+fn skip_missed(conn: &Connection, cutoff: i64) -> rusqlite::Result<usize> {
+    let now = dates::now_ms();
+    let mut skipped = 0;
+    for reminder in pending(conn)?.iter().take_while(|r| r.fire_at < cutoff) {
+        skipped += conn.execute(
+            "UPDATE tasks SET reminded_at = ?1 WHERE id = ?2",
+            params![now, reminder.id],
+        )?;
+    }
+    Ok(skipped)
+}
+
+fn due_body(conn: &Connection, due_date: i64) -> String {
+    let time = dates::to_local_naive(due_date)
+        .map(|dt| dt.format("%H:%M").to_string())
+        .unwrap_or_default();
+    let language = settings::read(conn, "preferences")
+        .ok()
+        .flatten()
+        .and_then(|p| p.get("language").and_then(|l| l.as_str()).map(String::from));
+    match language.as_deref() {
+        Some("en") => format!("Due at {}", time),
+        _ => format!("截止时间 {}", time),
+    }
+}
+
+fn fire_due(app: &AppHandle, conn: &Connection) -> rusqlite::Result<()> {
+    let now = dates::now_ms();
+    for reminder in pending(conn)?.into_iter().take_while(|r| r.fire_at <= now) {
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title(&reminder.title)
+            .body(due_body(conn, reminder.due_date))
+            .show()
+        {
+            eprintln!("Failed to show reminder for task {}: {}", reminder.id, e);
+        }
+        conn.execute(
+            "UPDATE tasks SET reminded_at = ?1 WHERE id = ?2",
+            params![now, reminder.id],
+        )?;
+    }
+    Ok(())
+}
+
+fn next_fire_at(conn: &Connection) -> rusqlite::Result<Option<i64>> {
+    Ok(pending(conn)?.first().map(|r| r.fire_at))
+}
+
+pub fn pending_count(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM tasks WHERE {}", PENDING_REMINDER),
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count as usize)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderEntry {
+    pub task_id: String,
+    pub title: String,
+    pub list_name: String,
+    pub due_date: i64,
+    pub fire_at: i64,
+}
+
+// Pending reminders of tasks due after `now_ms`, soonest to fire first
+pub fn upcoming(conn: &Connection, limit: i64, now_ms: i64) -> rusqlite::Result<Vec<ReminderEntry>> {
+    Ok(pending(conn)?
+        .into_iter()
+        .filter(|r| r.due_date > now_ms)
+        .take(limit.max(0) as usize)
+        .map(|r| ReminderEntry {
+            task_id: r.id,
+            title: r.title,
+            list_name: r.list_name,
+            due_date: r.due_date,
+            fire_at: r.fire_at,
+        })
+        .collect())
+}
+
+fn run_loop(app: &AppHandle, conn: &Connection) {
+    let scheduler = app.state::<ReminderScheduler>();
+    // Anything that came due while the app was closed is not delivered late
+    let mut cutoff = dates::now_ms();
+
+    loop {
+        if let Err(e) = skip_missed(conn, cutoff).and_then(|_| fire_due(app, conn)) {
+            eprintln!("Reminder scheduler error: {}", e);
+        }
+
+        let timeout = match next_fire_at(conn) {
+            Ok(Some(next)) => {
+                let delay = (next - dates::now_ms()).max(1000) as u64;
+                Duration::from_millis(delay).min(MAX_SLEEP)
+            }
+            _ => MAX_SLEEP,
+        };
+        scheduler.sleep(timeout);
+        cutoff = dates::now_ms() - STALE_AFTER_MS;
+    }
+}
+
+pub fn start(app: AppHandle) {
+    thread::spawn(move || match db::connect(&app) {
+        Ok(conn) => run_loop(&app, &conn),
+        Err(e) => eprintln!("Reminder scheduler could not open the database: {}", e),
+    });
+}
+
+// Recompute reminders after tasks were edited, returns how many are pending
+#[tauri::command]
+pub async fn reschedule_reminders(app: AppHandle) -> Result<usize, String> {
+    let conn = db::connect(&app)?;
+    let count = pending_count(&conn).map_err(|e| e.to_string())?;
+    app.state::<ReminderScheduler>().wake();
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn get_upcoming_reminders(app: AppHandle, limit: i64, now_ms: i64) -> Result<Vec<ReminderEntry>, String> {
+    let conn = db::connect(&app)?;
+    upcoming(&conn, limit, now_ms).map_err(|e| e.to_string())
+}
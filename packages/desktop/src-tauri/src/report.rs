@@ -0,0 +1,182 @@
+use crate::{db, markdown};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Deserialize;
+use tauri::AppHandle;
+
+const DELETED_PLACEHOLDER: &str = "[deleted]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+struct ReportTask {
+    // None once the task was deleted or purged from the trash
+    title: Option<String>,
+    completed: bool,
+}
+
+struct ReportSummary {
+    text: String,
+    tasks: Vec<ReportTask>,
+}
+
+struct ReportSection {
+    list_key: String,
+    summaries: Vec<ReportSummary>,
+}
+
+// Same labels as the summary history: `all` or `list-<name>`
+fn list_label(list_key: &str) -> String {
+    match list_key {
+        "all" => "All lists".to_string(),
+        key => key.strip_prefix("list-").unwrap_or(key).to_string(),
+    }
+}
+
+// Archived tasks still count, only trashed or purged ones are shown as deleted
+fn resolve_task(conn: &Connection, id: &str) -> rusqlite::Result<ReportTask> {
+    let row: Option<(String, bool)> = conn
+        .query_row(
+            "SELECT title, completed FROM tasks WHERE id = ?1 AND deleted_at IS NULL AND list_name != 'Trash'
+             UNION ALL
+             SELECT title, completed FROM archived_tasks WHERE id = ?1
+             LIMIT 1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(match row {
+        Some((title, completed)) => ReportTask { title: Some(title), completed },
+        None => ReportTask { title: None, completed: false },
+    })
+}
+
+fn load_sections(conn: &Connection, period_key: &str) -> rusqlite::Result<Vec<ReportSection>> {
+    let rows: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT list_key, task_ids, summary_text FROM summaries WHERE period_key = ?1
+             ORDER BY list_key != 'all', list_key, created_at",
+        )?;
+        let rows = stmt.query_map([period_key], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut sections: Vec<ReportSection> = Vec::new();
+    for (list_key, task_ids, text) in rows {
+        let ids: Vec<String> = serde_json::from_str(&task_ids).unwrap_or_default();
+        let tasks = ids
+            .iter()
+            .map(|id| resolve_task(conn, id))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let summary = ReportSummary { text, tasks };
+        match sections.last_mut() {
+            Some(section) if section.list_key == list_key => section.summaries.push(summary),
+            _ => sections.push(ReportSection { list_key, summaries: vec![summary] }),
+        }
+    }
+    Ok(sections)
+}
+
+fn render_markdown(period_key: &str, sections: &[ReportSection]) -> String {
+    let mut lines = vec![format!("# Summary: {}", markdown::escape(period_key)), String::new()];
+    for section in sections {
+        lines.push(format!("## {}", markdown::escape(&list_label(&section.list_key))));
+        lines.push(String::new());
+        for summary in &section.summaries {
+            // Summaries are Markdown already
+            lines.push(summary.text.trim().to_string());
+            lines.push(String::new());
+            if summary.tasks.is_empty() {
+                continue;
+            }
+            lines.push("### Tasks".to_string());
+            lines.push(String::new());
+            for task in &summary.tasks {
+                let title = task.title.as_deref().unwrap_or(DELETED_PLACEHOLDER);
+                lines.push(markdown::checklist_line("", task.completed, title, &[]));
+            }
+            lines.push(String::new());
+        }
+    }
+    lines.join("\n")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+// A standalone page. Summary texts are kept as written (there's no Markdown renderer
+// here), `pre-wrap` preserves their line breaks.
+fn render_html(period_key: &str, sections: &[ReportSection]) -> String {
+    let period = escape_html(period_key);
+    let mut html = vec![
+        "<!DOCTYPE html>".to_string(),
+        "<html>".to_string(),
+        "<head>".to_string(),
+        "<meta charset=\"utf-8\">".to_string(),
+        format!("<title>Summary: {}</title>", period),
+        "</head>".to_string(),
+        "<body>".to_string(),
+        format!("<h1>Summary: {}</h1>", period),
+    ];
+    for section in sections {
+        html.push("<section>".to_string());
+        html.push(format!("<h2>{}</h2>", escape_html(&list_label(&section.list_key))));
+        for summary in &section.summaries {
+            html.push(format!(
+                "<div style=\"white-space: pre-wrap\">{}</div>",
+                escape_html(summary.text.trim())
+            ));
+            if summary.tasks.is_empty() {
+                continue;
+            }
+            html.push("<h3>Tasks</h3>".to_string());
+            html.push("<ul>".to_string());
+            for task in &summary.tasks {
+                let item = match &task.title {
+                    Some(title) if task.completed => format!("<li>&#9745; <s>{}</s></li>", escape_html(title)),
+                    Some(title) => format!("<li>&#9744; {}</li>", escape_html(title)),
+                    None => format!("<li><em>{}</em></li>", DELETED_PLACEHOLDER),
+                };
+                html.push(item);
+            }
+            html.push("</ul>".to_string());
+        }
+        html.push("</section>".to_string());
+    }
+    html.push("</body>".to_string());
+    html.push("</html>".to_string());
+    html.push(String::new());
+    html.join("\n")
+}
+
+pub fn render(conn: &Connection, period_key: &str, format: ReportFormat) -> Result<String, String> {
+    let sections = load_sections(conn, period_key).map_err(|e| e.to_string())?;
+    if sections.is_empty() {
+        return Err(format!("No summaries stored for '{}'", period_key));
+    }
+    Ok(match format {
+        ReportFormat::Markdown => render_markdown(period_key, &sections),
+        ReportFormat::Html => render_html(period_key, &sections),
+    })
+}
+
+#[tauri::command]
+pub async fn export_summary_report(app: AppHandle, period_key: String, format: ReportFormat) -> Result<String, String> {
+    let conn = db::connect(&app)?;
+    render(&conn, &period_key, format)
+}
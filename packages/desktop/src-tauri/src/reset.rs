@@ -0,0 +1,80 @@
+use crate::reminders::ReminderScheduler;
+use crate::{attachments, db, journal, tray};
+use rusqlite::Connection;
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+// Same rows the first migration seeds. Migrations can't be edited (the plugin checksums
+// them), so keep this in step by hand.
+const SEED_INBOX: &str = r#"
+    INSERT OR IGNORE INTO lists (id, name, icon, "order")
+    VALUES ('inbox-default', 'Inbox', 'inbox', 1);
+"#;
+
+const SEED_SETTINGS: &str = r#"
+    INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('appearance', '{"themeId":"default-coral","darkMode":"system","interfaceDensity":"default"}'),
+    ('preferences', '{"language":"zh-CN","defaultNewTaskDueDate":null,"defaultNewTaskPriority":null,"defaultNewTaskList":"Inbox","confirmDeletions":true}'),
+    ('ai', '{"provider":"openai","apiKey":"","model":"","baseUrl":"","availableModels":[]}');
+"#;
+
+// Children before parents, so nothing is left pointing at a deleted row even where there
+// is no foreign key to cascade (focus sessions, the list counts)
+const WIPED_TABLES: &[&str] = &[
+    "focus_sessions",
+    "task_dependencies",
+    "task_comments",
+    "task_versions",
+    "attachments",
+    "subtasks",
+    "tasks",
+    "archived_tasks",
+    "lists",
+    "list_counts",
+    "templates",
+    "summaries",
+    "day_plans",
+    "echo_reports",
+    "operations_log",
+    // After the lists, tasks and subtasks, their delete triggers record every row removed
+    "outbox",
+];
+
+// Delete every list, task and report and seed the inbox again, all in one transaction.
+// With `keep_settings` the settings and stored secrets stay, otherwise they go back to the
+// defaults too. The reset isn't journaled and the undo history is cleared with it.
+pub fn reset(conn: &mut Connection, keep_settings: bool) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    journal::pause(&tx)?;
+    for table in WIPED_TABLES {
+        tx.execute(&format!("DELETE FROM {}", table), [])?;
+    }
+    if !keep_settings {
+        tx.execute("DELETE FROM settings", [])?;
+        // After the settings, their delete trigger records every value it removes
+        tx.execute("DELETE FROM settings_history", [])?;
+        tx.execute("DELETE FROM secrets", [])?;
+    }
+    tx.execute_batch(SEED_INBOX)?;
+    tx.execute_batch(SEED_SETTINGS)?;
+    journal::end(&tx)?;
+    tx.commit()
+}
+
+#[tauri::command]
+pub async fn factory_reset(app: AppHandle, keep_settings: bool) -> Result<(), String> {
+    {
+        let mut conn = db::connect(&app)?;
+        reset(&mut conn, keep_settings).map_err(|e| e.to_string())?;
+    }
+    // The rows are gone, their files only take up space now
+    let dir = attachments::attachments_dir(&app)?;
+    if let Err(e) = fs::remove_dir_all(&dir) && e.kind() != std::io::ErrorKind::NotFound {
+        eprintln!("Failed to delete '{}': {}", dir.display(), e);
+    }
+    app.state::<ReminderScheduler>().wake();
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(())
+}
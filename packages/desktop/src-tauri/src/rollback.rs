@@ -0,0 +1,138 @@
+use crate::{backup, db, journal, AppState};
+use rusqlite::Connection;
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_sql::MigrationKind;
+
+// What rolling back a version throws away, as (description, query counting the rows)
+fn data_at_risk(version: i64) -> &'static [(&'static str, &'static str)] {
+    match version {
+        1 => &[
+            ("tasks", "SELECT COUNT(*) FROM tasks"),
+            ("lists", "SELECT COUNT(*) FROM lists WHERE id != 'inbox-default'"),
+            ("summaries", "SELECT COUNT(*) FROM summaries"),
+        ],
+        2 => &[("echo reports", "SELECT COUNT(*) FROM echo_reports")],
+        4 => &[("recurrence rules", "SELECT COUNT(*) FROM tasks WHERE recurrence IS NOT NULL")],
+        5 => &[("stored secrets", "SELECT COUNT(*) FROM secrets")],
+        6 => &[(
+            "task reminders",
+            "SELECT COUNT(*) FROM tasks WHERE reminder_offset_minutes IS NOT NULL",
+        )],
+        7 => &[(
+            "trashed items",
+            "SELECT (SELECT COUNT(*) FROM tasks WHERE deleted_at IS NOT NULL)
+                  + (SELECT COUNT(*) FROM lists WHERE deleted_at IS NOT NULL)
+                  + (SELECT COUNT(*) FROM subtasks WHERE deleted_at IS NOT NULL)",
+        )],
+        9 => &[("archived tasks", "SELECT COUNT(*) FROM archived_tasks")],
+        10 => &[("list defaults", "SELECT COUNT(*) FROM lists WHERE defaults IS NOT NULL")],
+        12 => &[("attachments", "SELECT COUNT(*) FROM attachments")],
+        14 => &[("focus sessions", "SELECT COUNT(*) FROM focus_sessions")],
+        17 => &[("pinned lists", "SELECT COUNT(*) FROM lists WHERE pinned = 1")],
+        18 => &[("task dependencies", "SELECT COUNT(*) FROM task_dependencies")],
+        19 => &[(
+            "time estimates and logged time",
+            "SELECT (SELECT COUNT(*) FROM tasks WHERE estimate_minutes IS NOT NULL OR actual_minutes IS NOT NULL)
+                  + (SELECT COUNT(*) FROM archived_tasks WHERE estimate_minutes IS NOT NULL OR actual_minutes IS NOT NULL)",
+        )],
+        20 => &[("earlier settings values", "SELECT COUNT(*) FROM settings_history")],
+        22 => &[("templates", "SELECT COUNT(*) FROM templates")],
+        23 => &[("task comments", "SELECT COUNT(*) FROM task_comments WHERE kind = 'comment'")],
+        25 => &[("earlier task versions", "SELECT COUNT(*) FROM task_versions")],
+        26 => &[("hidden lists", "SELECT COUNT(*) FROM lists WHERE hidden = 1")],
+        27 => &[("comments of archived tasks", "SELECT COUNT(*) FROM archived_tasks WHERE comments != '[]'")],
+        28 => &[("earlier versions of archived tasks", "SELECT COUNT(*) FROM archived_tasks WHERE versions != '[]'")],
+        _ => &[],
+    }
+}
+
+// Version recorded by the sql plugin's migrator
+pub fn applied_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success = 1",
+        [],
+        |row| row.get(0),
+    )
+}
+
+// Apply Down migrations from the current version down to `target`, all in one
+// transaction. Unless `force` is set this refuses when any of the reverted versions
+// still holds data that only exists in its schema, and lists what would be lost.
+pub fn rollback(conn: &mut Connection, target: i64, force: bool) -> Result<(), String> {
+    let current = applied_version(conn).map_err(|e| e.to_string())?;
+    if target < 0 {
+        return Err("Target version can't be negative".to_string());
+    }
+    if target > current {
+        return Err(format!(
+            "Database is at version {}, relaunch the app to migrate forward",
+            current
+        ));
+    }
+
+    let migrations = crate::migrations();
+    let mut steps = Vec::new();
+    for version in (target + 1..=current).rev() {
+        let down = migrations
+            .iter()
+            .find(|m| m.version == version && matches!(m.kind, MigrationKind::Down))
+            .ok_or_else(|| format!("No down migration for version {}", version))?;
+        steps.push((version, down.sql));
+    }
+
+    if !force {
+        let mut losses = Vec::new();
+        for (version, _) in &steps {
+            for (what, query) in data_at_risk(*version) {
+                let count: i64 = conn.query_row(query, [], |row| row.get(0)).map_err(|e| e.to_string())?;
+                if count > 0 {
+                    losses.push(format!("{} {} (version {})", count, what, version));
+                }
+            }
+        }
+        if !losses.is_empty() {
+            return Err(format!(
+                "Rolling back to version {} would delete {}. Pass force to go ahead anyway",
+                target,
+                losses.join(", ")
+            ));
+        }
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    // Down steps that drop a column of lists, tasks or subtasks would fail while the undo
+    // journal's triggers reference it, `journal::install` recreates them on the next start
+    journal::drop_triggers(&tx).map_err(|e| e.to_string())?;
+    for (version, sql) in steps {
+        tx.execute_batch(sql)
+            .map_err(|e| format!("Down migration {} failed: {}", version, e))?;
+        tx.execute("DELETE FROM _sqlx_migrations WHERE version = ?1", [version])
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Roll the schema back so an older build can open the database. A copy of the database
+// is kept next to it first, and the app quits afterwards: the frontend can't keep working
+// against the reverted schema, and relaunching this same build re-applies the migrations,
+// so the older build has to be installed before starting the app again.
+#[tauri::command]
+pub async fn migrate_to_version(app: AppHandle, target: i64, force: Option<bool>) -> Result<(), String> {
+    let mut conn = db::connect(&app)?;
+    let current = applied_version(&conn).map_err(|e| e.to_string())?;
+    if target == current {
+        return Ok(());
+    }
+
+    if target < current {
+        let copy = db::db_path(&app)?.with_file_name(format!("{}.pre-rollback-v{}", db::DB_FILE, current));
+        backup::snapshot_to(&app, &copy)?;
+    }
+    rollback(&mut conn, target, force.unwrap_or(false))?;
+    drop(conn);
+
+    app.state::<AppState>().is_quitting.store(true, Ordering::Relaxed);
+    app.exit(0);
+    Ok(())
+}
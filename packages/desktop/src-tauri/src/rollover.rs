@@ -0,0 +1,78 @@
+use crate::{dates, db, journal, settings, tray};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use tauri::AppHandle;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// `preferences.rolloverMode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RolloverMode {
+    // The due date stays, the task only shows under today (until the next day starts)
+    KeepDate,
+    // The due date moves to today, keeping its time of day
+    Bump,
+}
+
+impl RolloverMode {
+    pub fn load(conn: &Connection) -> Self {
+        let mode = settings::read(conn, "preferences")
+            .ok()
+            .flatten()
+            .and_then(|p| p.get("rolloverMode").and_then(Value::as_str).map(String::from));
+        match mode.as_deref() {
+            Some("bump") => RolloverMode::Bump,
+            _ => RolloverMode::KeepDate,
+        }
+    }
+}
+
+// Carry open tasks due before today over into today, returns how many moved. Running it
+// again the same day finds nothing left to do: bumped tasks are no longer overdue and kept
+// ones are marked with today's start in `rolled_over_at`.
+pub fn rollover(conn: &mut Connection, now_ms: i64, tz_offset_minutes: i32) -> Result<usize, String> {
+    let today = dates::day_start_at_offset(now_ms, tz_offset_minutes)?;
+    let mode = RolloverMode::load(conn);
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "rollover_overdue").map_err(|e| e.to_string())?;
+    let overdue = "completed = 0 AND deleted_at IS NULL AND list_name != 'Trash' AND due_date < ?1";
+    let moved = match mode {
+        RolloverMode::KeepDate => tx.execute(
+            &format!(
+                "UPDATE tasks SET group_category = 'today', rolled_over_at = ?1
+                 WHERE {} AND rolled_over_at IS NOT ?1",
+                overdue
+            ),
+            [today],
+        ),
+        RolloverMode::Bump => {
+            let offset_ms = tz_offset_minutes as i64 * 60_000;
+            tx.execute(
+                &format!(
+                    "UPDATE tasks
+                     SET due_date = ?1 + ((due_date + ?2) % ?3 + ?3) % ?3, group_category = 'today',
+                         rolled_over_at = NULL, updated_at = ?4
+                     WHERE {}",
+                    overdue
+                ),
+                params![today, offset_ms, DAY_MS, now_ms],
+            )
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(moved)
+}
+
+#[tauri::command]
+pub async fn rollover_overdue(app: AppHandle, now_ms: i64, tz_offset_minutes: i32) -> Result<usize, String> {
+    let moved = {
+        let mut conn = db::connect(&app)?;
+        rollover(&mut conn, now_ms, tz_offset_minutes)?
+    };
+    if moved > 0 && let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(moved)
+}
@@ -0,0 +1,261 @@
+use crate::{dates, db, journal};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSearchResult {
+    pub id: String,
+    pub title: String,
+    pub list_id: Option<String>,
+    pub list_name: String,
+    pub completed: bool,
+    // Title and content fragments with matches wrapped in <mark></mark>
+    pub title_highlight: String,
+    pub content_snippet: String,
+    // bm25 score, lower is a better match
+    pub rank: f64,
+}
+
+// Turn raw user input into a safe FTS5 expression. Every whitespace separated term is
+// quoted (so operators like `*`, `-`, `:` or `NEAR` are taken literally) and matched as a
+// prefix, and the terms are implicitly AND-ed together.
+fn build_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| term.replace('"', "\"\""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[tauri::command]
+pub async fn search_tasks(
+    app: AppHandle,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<TaskSearchResult>, String> {
+    let Some(match_query) = build_match_query(&query) else {
+        return Ok(Vec::new());
+    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let conn = db::connect(&app)?;
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT t.id, t.title, t.list_id, t.list_name, t.completed,
+                   highlight(tasks_fts, 1, '<mark>', '</mark>'),
+                   snippet(tasks_fts, 2, '<mark>', '</mark>', '…', 16),
+                   bm25(tasks_fts) AS score
+            FROM tasks_fts
+            JOIN tasks t ON t.id = tasks_fts.task_id
+            WHERE tasks_fts MATCH ?1 AND t.deleted_at IS NULL
+            ORDER BY score
+            LIMIT ?2
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![match_query, limit], |row| {
+            Ok(TaskSearchResult {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                list_id: row.get(2)?,
+                list_name: row.get(3)?,
+                completed: row.get::<_, i64>(4)? != 0,
+                title_highlight: row.get(5)?,
+                content_snippet: row.get(6)?,
+                rank: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Field {
+    Title,
+    Content,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceChange {
+    pub task_id: String,
+    pub title: String,
+    // `None` where the field has no match
+    pub new_title: Option<String>,
+    pub new_content: Option<String>,
+    pub occurrences: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceSummary {
+    pub tasks: usize,
+    pub occurrences: usize,
+    // Nothing was written, `changes` is what a real run would do
+    pub dry_run: bool,
+    pub changes: Vec<ReplaceChange>,
+    // Tasks whose title would have become blank, they keep it
+    pub blank_titles: usize,
+}
+
+// Every match of `find` in `text` replaced, with the number of matches. Without
+// `case_sensitive` characters are compared lowercased.
+fn replace_text(text: &str, find: &str, replace: &str, case_sensitive: bool) -> (String, usize) {
+    if case_sensitive {
+        return (text.replace(find, replace), text.matches(find).count());
+    }
+    let needle: Vec<char> = find.chars().flat_map(char::to_lowercase).collect();
+    let mut out = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let mut lowered = Vec::with_capacity(needle.len());
+        let mut matched = None;
+        for (i, c) in rest.char_indices() {
+            lowered.extend(c.to_lowercase());
+            if !needle.starts_with(&lowered) {
+                break;
+            }
+            if lowered.len() == needle.len() {
+                matched = Some(i + c.len_utf8());
+                break;
+            }
+        }
+        match matched {
+            Some(end) => {
+                out.push_str(replace);
+                count += 1;
+                rest = &rest[end..];
+            }
+            None => {
+                out.push(first);
+                rest = &rest[first.len_utf8()..];
+            }
+        }
+    }
+    (out, count)
+}
+
+// Replace `find` in the titles and/or content of live tasks outside the trash. With
+// `dry_run` nothing is written. Otherwise the edits are one undo step, and the replaced
+// values go to each task's version history through the `task_versions_update` trigger.
+pub fn replace_in(
+    conn: &mut Connection,
+    find: &str,
+    replace: &str,
+    fields: &[Field],
+    case_sensitive: bool,
+    dry_run: bool,
+) -> Result<ReplaceSummary, String> {
+    if find.is_empty() {
+        return Err("Nothing to find".to_string());
+    }
+    if fields.is_empty() {
+        return Err("No fields to search".to_string());
+    }
+    let (in_title, in_content) = (fields.contains(&Field::Title), fields.contains(&Field::Content));
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let tasks: Vec<(String, String, Option<String>)> = {
+        let mut stmt = tx
+            .prepare(
+                r#"SELECT id, title, content FROM tasks WHERE deleted_at IS NULL AND list_name != 'Trash'
+                   ORDER BY list_name, "order""#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut summary = ReplaceSummary {
+        tasks: 0,
+        occurrences: 0,
+        dry_run,
+        changes: Vec::new(),
+        blank_titles: 0,
+    };
+    for (task_id, title, content) in tasks {
+        let mut change = ReplaceChange {
+            task_id,
+            title,
+            new_title: None,
+            new_content: None,
+            occurrences: 0,
+        };
+        if in_title {
+            let (new_title, count) = replace_text(&change.title, find, replace, case_sensitive);
+            if count > 0 && new_title.trim().is_empty() {
+                summary.blank_titles += 1;
+            } else if count > 0 {
+                change.new_title = Some(new_title);
+                change.occurrences += count;
+            }
+        }
+        if let Some(content) = content.as_deref().filter(|_| in_content) {
+            let (new_content, count) = replace_text(content, find, replace, case_sensitive);
+            if count > 0 {
+                change.new_content = Some(new_content);
+                change.occurrences += count;
+            }
+        }
+        if change.occurrences > 0 {
+            summary.tasks += 1;
+            summary.occurrences += change.occurrences;
+            summary.changes.push(change);
+        }
+    }
+    if dry_run || summary.changes.is_empty() {
+        return Ok(summary);
+    }
+
+    journal::begin(&tx, "replace_in_tasks").map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "UPDATE tasks SET title = COALESCE(?1, title), content = COALESCE(?2, content), updated_at = ?3
+                 WHERE id = ?4",
+            )
+            .map_err(|e| e.to_string())?;
+        let now = dates::now_ms();
+        for change in &summary.changes {
+            stmt.execute(params![change.new_title, change.new_content, now, change.task_id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn replace_in_tasks(
+    app: AppHandle,
+    find: String,
+    replace: String,
+    fields: Vec<Field>,
+    case_sensitive: bool,
+    dry_run: bool,
+) -> Result<ReplaceSummary, String> {
+    let mut conn = db::connect(&app)?;
+    replace_in(&mut conn, &find, &replace, &fields, case_sensitive, dry_run)
+}
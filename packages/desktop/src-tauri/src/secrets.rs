@@ -0,0 +1,119 @@
+use crate::{dates, db, settings};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::AppHandle;
+
+// Secrets are encrypted with AES-256-GCM before they reach the `secrets` table. The key
+// itself never touches the database: it is generated once and kept in the OS keyring
+// (Keychain, Credential Manager or the Secret Service).
+const KEYRING_SERVICE: &str = "com.loadshine.tada";
+const KEYRING_USER: &str = "secrets-master-key";
+const NONCE_LEN: usize = 12;
+
+pub const AI_API_KEY: &str = "ai.apiKey";
+
+fn master_key(create: bool) -> Result<Option<Key<Aes256Gcm>>, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())?;
+    match entry.get_password() {
+        Ok(encoded) => {
+            let bytes = STANDARD.decode(encoded).map_err(|e| e.to_string())?;
+            let bytes = <[u8; 32]>::try_from(bytes.as_slice())
+                .map_err(|_| "Keyring holds a malformed encryption key".to_string())?;
+            Ok(Some(bytes.into()))
+        }
+        Err(keyring::Error::NoEntry) if create => {
+            let key = Aes256Gcm::generate_key(OsRng);
+            entry
+                .set_password(&STANDARD.encode(key))
+                .map_err(|e| e.to_string())?;
+            Ok(Some(key))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// The secret's name is bound as associated data so ciphertexts can't be swapped between rows
+fn encrypt(key: &Key<Aes256Gcm>, name: &str, value: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: value.as_bytes(), aad: name.as_bytes() })
+        .map_err(|e| e.to_string())?;
+
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(blob))
+}
+
+fn decrypt(key: &Key<Aes256Gcm>, name: &str, encoded: &str) -> Option<String> {
+    let blob = STANDARD.decode(encoded).ok()?;
+    if blob.len() <= NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    let nonce: Nonce<<Aes256Gcm as AeadCore>::NonceSize> = <[u8; NONCE_LEN]>::try_from(nonce).ok()?.into();
+    let plaintext = Aes256Gcm::new(key)
+        .decrypt(&nonce, Payload { msg: ciphertext, aad: name.as_bytes() })
+        .ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
+// An empty value removes the secret
+pub fn store(conn: &Connection, name: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        conn.execute("DELETE FROM secrets WHERE key = ?1", [name])
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let key = master_key(true)?.ok_or_else(|| "Keyring is unavailable".to_string())?;
+    let encrypted = encrypt(&key, name, value)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO secrets (key, value, updated_at) VALUES (?1, ?2, ?3)",
+        params![name, encrypted, dates::now_ms()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Fails softly: a missing row, a missing keyring entry or a value that no longer decrypts
+// all read as `None`.
+pub fn load(conn: &Connection, name: &str) -> Option<String> {
+    let encrypted: String = conn
+        .query_row("SELECT value FROM secrets WHERE key = ?1", [name], |row| row.get(0))
+        .optional()
+        .ok()??;
+    let key = master_key(false).ok()??;
+    decrypt(&key, name, &encrypted)
+}
+
+// Move a plaintext `ai.apiKey` left over from older versions into the secret store. The
+// settings copy is only blanked once the encrypted copy has been written.
+pub fn migrate_plaintext_api_key(conn: &Connection) -> Result<(), String> {
+    let Some(mut ai) = settings::read(conn, "ai").map_err(|e| e.to_string())? else {
+        return Ok(());
+    };
+    let api_key = match ai.get("apiKey").and_then(|v| v.as_str()) {
+        Some(api_key) if !api_key.is_empty() => api_key.to_string(),
+        _ => return Ok(()),
+    };
+
+    store(conn, AI_API_KEY, &api_key)?;
+    ai["apiKey"] = serde_json::Value::String(String::new());
+    settings::write(conn, "ai", &ai).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_secret(app: AppHandle, key: String, value: String) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    store(&conn, &key, &value)
+}
+
+#[tauri::command]
+pub async fn get_secret(app: AppHandle, key: String) -> Result<Option<String>, String> {
+    let conn = db::connect(&app)?;
+    Ok(load(&conn, &key))
+}
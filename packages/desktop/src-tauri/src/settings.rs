@@ -0,0 +1,399 @@
+use crate::local_api::LocalApiSettings;
+use crate::stats::WeeklyScoreWeights;
+use crate::working_hours::WorkingHours;
+use crate::{dates, db, secrets};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+
+// Settings rows hold one JSON document per key ('appearance', 'preferences', 'ai', ...).
+// A missing row or unparsable JSON reads as `None`, callers fall back to defaults.
+pub fn read(conn: &Connection, key: &str) -> rusqlite::Result<Option<Value>> {
+    let raw: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()?;
+    Ok(raw.and_then(|raw| serde_json::from_str(&raw).ok()))
+}
+
+pub fn write(conn: &Connection, key: &str, value: &Value) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
+        params![key, value.to_string(), dates::now_ms()],
+    )?;
+    Ok(())
+}
+
+// `preferences.language` ("en", "zh-CN", ...)
+pub fn language(conn: &Connection) -> Option<String> {
+    read(conn, "preferences")
+        .ok()
+        .flatten()
+        .and_then(|p| p.get("language").and_then(Value::as_str).map(String::from))
+}
+
+// Typed views of the settings rows the frontend edits. Fields the structs don't know
+// (keys written by native commands, newer frontend fields) are kept in `extra` and
+// written back untouched.
+pub trait TypedSetting: Serialize + DeserializeOwned + Default {
+    const KEY: &'static str;
+
+    // One message per offending field, empty when the value can be stored
+    fn problems(&self) -> Vec<String>;
+}
+
+fn one_of(problems: &mut Vec<String>, field: &str, value: &str, allowed: &[&str]) {
+    if !allowed.contains(&value) {
+        problems.push(format!("{} must be one of {}, got '{}'", field, allowed.join(", "), value));
+    }
+}
+
+fn not_blank(problems: &mut Vec<String>, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        problems.push(format!("{} can't be empty", field));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppearanceSettings {
+    pub theme_id: String,
+    pub dark_mode: String,
+    pub interface_density: String,
+    pub text_size: String,
+    pub font_weight: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// Same defaults as `defaultAppearanceSettingsForApi` in the frontend
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        Self {
+            theme_id: "default-coral".into(),
+            dark_mode: "system".into(),
+            interface_density: "default".into(),
+            text_size: "default".into(),
+            font_weight: "light".into(),
+            extra: Map::new(),
+        }
+    }
+}
+
+impl TypedSetting for AppearanceSettings {
+    const KEY: &'static str = "appearance";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        not_blank(&mut problems, "themeId", &self.theme_id);
+        one_of(&mut problems, "darkMode", &self.dark_mode, &["system", "light", "dark"]);
+        one_of(&mut problems, "interfaceDensity", &self.interface_density, &["compact", "default", "comfortable"]);
+        one_of(&mut problems, "textSize", &self.text_size, &["default", "large"]);
+        one_of(&mut problems, "fontWeight", &self.font_weight, &["light", "regular", "bold"]);
+        problems
+    }
+}
+
+// Completing a recurring task adds its next occurrence, or the upcoming occurrences are
+// generated ahead of time on startup (`recurrence::materialize`). The latter is the default,
+// it's how recurring tasks worked before the setting existed.
+pub const RECURRENCE_SPAWN_ON_COMPLETE: &str = "spawnOnComplete";
+pub const RECURRENCE_PRE_MATERIALIZE: &str = "preMaterialize";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Preferences {
+    pub language: String,
+    pub default_new_task_due_date: Option<String>,
+    pub default_new_task_priority: Option<i64>,
+    pub default_new_task_list: String,
+    pub confirm_deletions: bool,
+    pub zen_mode_shy_native: bool,
+    pub enable_echo: bool,
+    pub echo_job_types: Vec<String>,
+    pub echo_past_examples: String,
+    #[serde(rename = "alwaysUseAITask")]
+    pub always_use_ai_task: bool,
+    // RECURRENCE_SPAWN_ON_COMPLETE or RECURRENCE_PRE_MATERIALIZE
+    pub recurrence_mode: String,
+    // Reminders outside these hours wait for the next window, `None` lets them fire any time
+    pub working_hours: Option<WorkingHours>,
+    // Earlier versions kept per task, read by the `task_versions_update` trigger
+    pub task_version_limit: i64,
+    // Estimated minutes a day above which `get_workload` flags a list
+    pub daily_capacity_minutes: i64,
+    // What a task without an estimate counts as in `get_workload`
+    pub default_estimate_minutes: i64,
+    // The read-only HTTP server of `local_api`, off unless enabled
+    pub local_api: LocalApiSettings,
+    // Complete a task once `set_subtasks_completed` has completed all its subtasks
+    pub auto_complete_parent: bool,
+    // Weights of the parts of `get_weekly_score`
+    pub weekly_score: WeeklyScoreWeights,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// Same defaults as `defaultPreferencesSettingsForApi` in the frontend
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            language: "zh-CN".into(),
+            default_new_task_due_date: None,
+            default_new_task_priority: None,
+            default_new_task_list: "Inbox".into(),
+            confirm_deletions: true,
+            zen_mode_shy_native: false,
+            enable_echo: true,
+            echo_job_types: Vec::new(),
+            echo_past_examples: String::new(),
+            always_use_ai_task: false,
+            recurrence_mode: RECURRENCE_PRE_MATERIALIZE.into(),
+            working_hours: None,
+            task_version_limit: 20,
+            daily_capacity_minutes: 480,
+            default_estimate_minutes: 30,
+            local_api: LocalApiSettings::default(),
+            auto_complete_parent: false,
+            weekly_score: WeeklyScoreWeights::default(),
+            extra: Map::new(),
+        }
+    }
+}
+
+impl TypedSetting for Preferences {
+    const KEY: &'static str = "preferences";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        one_of(&mut problems, "language", &self.language, &["en", "zh-CN"]);
+        if let Some(due) = &self.default_new_task_due_date {
+            one_of(&mut problems, "defaultNewTaskDueDate", due, &["today", "tomorrow"]);
+        }
+        if let Some(priority) = self.default_new_task_priority && !(1..=3).contains(&priority) {
+            problems.push(format!("defaultNewTaskPriority must be between 1 and 3, got {}", priority));
+        }
+        not_blank(&mut problems, "defaultNewTaskList", &self.default_new_task_list);
+        one_of(
+            &mut problems,
+            "recurrenceMode",
+            &self.recurrence_mode,
+            &[RECURRENCE_SPAWN_ON_COMPLETE, RECURRENCE_PRE_MATERIALIZE],
+        );
+        if let Some(hours) = &self.working_hours {
+            problems.extend(hours.problems());
+        }
+        if !(1..=1000).contains(&self.task_version_limit) {
+            problems.push(format!("taskVersionLimit must be between 1 and 1000, got {}", self.task_version_limit));
+        }
+        if self.daily_capacity_minutes <= 0 {
+            problems.push(format!("dailyCapacityMinutes must be positive, got {}", self.daily_capacity_minutes));
+        }
+        if self.default_estimate_minutes < 0 {
+            problems.push(format!(
+                "defaultEstimateMinutes can't be negative, got {}",
+                self.default_estimate_minutes
+            ));
+        }
+        problems.extend(self.local_api.problems());
+        problems.extend(self.weekly_score.problems());
+        problems
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AiSettings {
+    pub provider: String,
+    // Only ever stored blank, the key itself lives in the secret store
+    pub api_key: String,
+    pub model: String,
+    pub base_url: String,
+    pub available_models: Vec<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// Same defaults as `defaultAISettingsForApi` in the frontend
+impl Default for AiSettings {
+    fn default() -> Self {
+        Self {
+            provider: "openai".into(),
+            api_key: String::new(),
+            model: String::new(),
+            base_url: String::new(),
+            available_models: Vec::new(),
+            extra: Map::new(),
+        }
+    }
+}
+
+impl TypedSetting for AiSettings {
+    const KEY: &'static str = "ai";
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        not_blank(&mut problems, "provider", &self.provider);
+        if !self.base_url.is_empty() && !self.base_url.starts_with("http://") && !self.base_url.starts_with("https://") {
+            problems.push(format!("baseUrl must start with http:// or https://, got '{}'", self.base_url));
+        }
+        problems
+    }
+}
+
+// Missing rows and missing fields read as the defaults. A stored field that is invalid
+// (or of the wrong type) is replaced by its default instead of failing the whole read.
+pub fn get_typed<T: TypedSetting>(conn: &Connection) -> rusqlite::Result<T> {
+    let Some(Value::Object(stored)) = read(conn, T::KEY)? else {
+        return Ok(T::default());
+    };
+    let mut current = match serde_json::to_value(T::default()) {
+        Ok(Value::Object(map)) => map,
+        _ => return Ok(T::default()),
+    };
+    for (field, value) in stored {
+        let mut candidate = current.clone();
+        candidate.insert(field, value);
+        let accepted = serde_json::from_value::<T>(Value::Object(candidate.clone()))
+            .is_ok_and(|typed| typed.problems().is_empty());
+        if accepted {
+            current = candidate;
+        }
+    }
+    Ok(serde_json::from_value(Value::Object(current)).unwrap_or_default())
+}
+
+pub fn set_typed<T: TypedSetting>(conn: &Connection, value: &T) -> Result<(), String> {
+    let problems = value.problems();
+    if !problems.is_empty() {
+        return Err(format!("Invalid {} settings: {}", T::KEY, problems.join("; ")));
+    }
+    let json = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    write(conn, T::KEY, &json).map_err(|e| e.to_string())
+}
+
+fn parse_typed<T: TypedSetting>(value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| format!("Invalid {} settings: {}", T::KEY, e))
+}
+
+const TYPED_KEYS: &[&str] = &[AppearanceSettings::KEY, Preferences::KEY, AiSettings::KEY];
+
+fn unknown_key(key: &str) -> String {
+    format!("Unknown settings key '{}', expected one of {}", key, TYPED_KEYS.join(", "))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingVersion {
+    pub value: Value,
+    // When this value was written and when something else replaced it (or the row was deleted)
+    pub updated_at: i64,
+    pub replaced_at: i64,
+}
+
+// Stored text that isn't valid JSON is returned as a string, that is what a debugging
+// view needs to see
+fn raw_value(raw: String) -> Value {
+    serde_json::from_str(&raw).unwrap_or(Value::String(raw))
+}
+
+// Earlier values of `key`, newest first. The triggers from migration 20 fill the history
+// on every write, whether it came through `set_setting` or straight from the frontend.
+pub fn history(conn: &Connection, key: &str) -> rusqlite::Result<Vec<SettingVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT value, updated_at, replaced_at FROM settings_history WHERE key = ?1 ORDER BY id DESC",
+    )?;
+    let rows = stmt.query_map([key], |row| {
+        Ok(SettingVersion {
+            value: raw_value(row.get(0)?),
+            updated_at: row.get(1)?,
+            replaced_at: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+// Every settings row as one pretty-printed JSON object, for attaching to bug reports. A
+// plaintext AI API key from older versions is blanked, like `backup::export_copy` does.
+pub fn export_all(conn: &Connection) -> rusqlite::Result<String> {
+    let mut stmt = conn.prepare(
+        "SELECT key,
+                CASE WHEN key = 'ai' AND json_valid(value) THEN json_set(value, '$.apiKey', '') ELSE value END,
+                updated_at
+         FROM settings ORDER BY key",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+    })?;
+    let mut out = Map::new();
+    for row in rows {
+        let (key, value, updated_at) = row?;
+        out.insert(key, serde_json::json!({ "value": raw_value(value), "updatedAt": updated_at }));
+    }
+    Ok(serde_json::to_string_pretty(&Value::Object(out)).unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn get_setting(app: AppHandle, key: String) -> Result<Value, String> {
+    let conn = db::connect(&app)?;
+    let value = match key.as_str() {
+        AppearanceSettings::KEY => serde_json::to_value(get_typed::<AppearanceSettings>(&conn).map_err(|e| e.to_string())?),
+        Preferences::KEY => serde_json::to_value(get_typed::<Preferences>(&conn).map_err(|e| e.to_string())?),
+        AiSettings::KEY => {
+            let mut ai = get_typed::<AiSettings>(&conn).map_err(|e| e.to_string())?;
+            if let Some(api_key) = secrets::load(&conn, secrets::AI_API_KEY) {
+                ai.api_key = api_key;
+            }
+            serde_json::to_value(ai)
+        }
+        _ => return Err(unknown_key(&key)),
+    };
+    value.map_err(|e| e.to_string())
+}
+
+// Validates before writing anything. Returns the settings as stored, an AI key handed in
+// goes to the secret store and comes back blank.
+#[tauri::command]
+pub async fn set_setting(app: AppHandle, key: String, value: Value) -> Result<Value, String> {
+    let conn = db::connect(&app)?;
+    let stored = match key.as_str() {
+        AppearanceSettings::KEY => {
+            let appearance: AppearanceSettings = parse_typed(value)?;
+            set_typed(&conn, &appearance)?;
+            serde_json::to_value(appearance)
+        }
+        Preferences::KEY => {
+            let preferences: Preferences = parse_typed(value)?;
+            set_typed(&conn, &preferences)?;
+            serde_json::to_value(preferences)
+        }
+        AiSettings::KEY => {
+            let mut ai: AiSettings = parse_typed(value)?;
+            let problems = ai.problems();
+            if !problems.is_empty() {
+                return Err(format!("Invalid {} settings: {}", AiSettings::KEY, problems.join("; ")));
+            }
+            if !ai.api_key.is_empty() {
+                secrets::store(&conn, secrets::AI_API_KEY, &ai.api_key)?;
+                ai.api_key.clear();
+            }
+            set_typed(&conn, &ai)?;
+            serde_json::to_value(ai)
+        }
+        _ => return Err(unknown_key(&key)),
+    };
+    stored.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_settings(app: AppHandle) -> Result<String, String> {
+    let conn = db::connect(&app)?;
+    export_all(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_settings_history(app: AppHandle, key: String) -> Result<Vec<SettingVersion>, String> {
+    let conn = db::connect(&app)?;
+    history(&conn, &key).map_err(|e| e.to_string())
+}
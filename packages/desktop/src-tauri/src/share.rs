@@ -0,0 +1,173 @@
+use crate::export::{self, Row};
+use crate::{dates, db, journal};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+// Bump when the layout changes in a way older importers can't read
+pub const LIST_SCHEMA_VERSION: i64 = 1;
+
+// One list with its live tasks and subtasks, rows column-for-column like `ExportDocument`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDocument {
+    pub schema: i64,
+    #[serde(default)]
+    pub app_version: String,
+    #[serde(default)]
+    pub exported_at: i64,
+    pub list: Row,
+    #[serde(default)]
+    pub tasks: Vec<Row>,
+    #[serde(default)]
+    pub subtasks: Vec<Row>,
+}
+
+pub fn collect_list(conn: &Connection, list_id: &str, app_version: String) -> Result<ListDocument, String> {
+    let list = export::dump_rows(conn, "SELECT * FROM lists WHERE id = ?1 AND deleted_at IS NULL", [list_id])
+        .map_err(|e| e.to_string())?
+        .pop()
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+    let tasks = export::dump_rows(
+        conn,
+        r#"SELECT * FROM tasks WHERE list_id = ?1 AND deleted_at IS NULL ORDER BY "order""#,
+        [list_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let subtasks = export::dump_rows(
+        conn,
+        r#"
+        SELECT s.* FROM subtasks s JOIN tasks t ON t.id = s.parent_id
+        WHERE t.list_id = ?1 AND t.deleted_at IS NULL AND s.deleted_at IS NULL
+        ORDER BY s.parent_id, s."order"
+        "#,
+        [list_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(ListDocument {
+        schema: LIST_SCHEMA_VERSION,
+        app_version,
+        exported_at: dates::now_ms(),
+        list,
+        tasks,
+        subtasks,
+    })
+}
+
+// `name`, or `name 2`, `name 3`... whichever no list uses yet. Trashed lists count too,
+// restoring one must not produce two lists of the same name.
+fn unique_name(conn: &Connection, name: &str) -> rusqlite::Result<String> {
+    let taken: Vec<String> = {
+        let mut stmt = conn.prepare("SELECT name FROM lists")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut candidate = name.to_string();
+    let mut suffix = 2;
+    while taken.iter().any(|t| t == &candidate) {
+        candidate = format!("{} {}", name, suffix);
+        suffix += 1;
+    }
+    Ok(candidate)
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+// Insert the document as a new list inside the caller's transaction, returns its id.
+// Every row gets a fresh id so importing the same file twice gives two separate lists.
+pub fn apply_list(conn: &Connection, document: &ListDocument) -> Result<String, String> {
+    let name = document
+        .list
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .ok_or("The shared list has no name")?;
+    let name = unique_name(conn, name).map_err(|e| e.to_string())?;
+    let list_id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+
+    let mut list = document.list.clone();
+    list.insert("id".into(), string(&list_id));
+    list.insert("name".into(), string(&name));
+    // At the end of the sidebar, like a list created in the app
+    list.insert("order".into(), now.into());
+    list.insert("created_at".into(), now.into());
+    list.insert("updated_at".into(), now.into());
+    list.remove("deleted_at");
+    let columns = export::table_columns(conn, "lists").map_err(|e| e.to_string())?;
+    export::insert_row(conn, "lists", &columns, &list).map_err(|e| e.to_string())?;
+
+    let task_ids: HashMap<&str, String> = document
+        .tasks
+        .iter()
+        .filter_map(|task| task.get("id").and_then(Value::as_str))
+        .map(|id| (id, Uuid::new_v4().to_string()))
+        .collect();
+
+    let columns = export::table_columns(conn, "tasks").map_err(|e| e.to_string())?;
+    for original in &document.tasks {
+        let Some(new_id) = original.get("id").and_then(Value::as_str).and_then(|id| task_ids.get(id)) else {
+            continue;
+        };
+        let mut task = original.clone();
+        task.insert("id".into(), string(new_id));
+        task.insert("list_id".into(), string(&list_id));
+        task.insert("list_name".into(), string(&name));
+        task.remove("deleted_at");
+        // Keep recurrence series together when their parent came along, cut them loose otherwise
+        if let Some(parent) = task.get("recurrence_parent_id").and_then(Value::as_str) {
+            let parent = task_ids.get(parent).map_or(Value::Null, |id| string(id));
+            task.insert("recurrence_parent_id".into(), parent);
+        }
+        export::insert_row(conn, "tasks", &columns, &task).map_err(|e| e.to_string())?;
+    }
+
+    let columns = export::table_columns(conn, "subtasks").map_err(|e| e.to_string())?;
+    for original in &document.subtasks {
+        let parent = original.get("parent_id").and_then(Value::as_str);
+        let Some(parent_id) = parent.and_then(|id| task_ids.get(id)) else {
+            continue;
+        };
+        let mut subtask = original.clone();
+        subtask.insert("id".into(), string(&Uuid::new_v4().to_string()));
+        subtask.insert("parent_id".into(), string(parent_id));
+        subtask.remove("deleted_at");
+        export::insert_row(conn, "subtasks", &columns, &subtask).map_err(|e| e.to_string())?;
+    }
+    Ok(list_id)
+}
+
+#[tauri::command]
+pub async fn export_list(app: AppHandle, list_id: String, path: String) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    let document = collect_list(&conn, &list_id, app.package_info().version.to_string())?;
+    let json = serde_json::to_string_pretty(&document).map_err(|e| e.to_string())?;
+    export::write_file_atomic(Path::new(&path), &json)
+}
+
+#[tauri::command]
+pub async fn import_list(app: AppHandle, path: String) -> Result<String, String> {
+    let raw = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    let document: ListDocument = serde_json::from_str(&raw).map_err(|e| format!("Invalid list file: {}", e))?;
+    if document.schema > LIST_SCHEMA_VERSION {
+        return Err(format!(
+            "List file uses format {} but this version of Tada only reads up to {}",
+            document.schema, LIST_SCHEMA_VERSION
+        ));
+    }
+
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_list").map_err(|e| e.to_string())?;
+    let list_id = apply_list(&tx, &document)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(list_id)
+}
@@ -0,0 +1,103 @@
+use crate::tasks::{Task, TASK_COLUMNS};
+use crate::{dates, db};
+use rusqlite::{params, params_from_iter, Connection};
+use serde::Deserialize;
+use tauri::AppHandle;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+// Today plus this many days ahead
+const UPCOMING_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SmartView {
+    Today,
+    Upcoming7Days,
+    Overdue,
+    NoDate,
+}
+
+// Open tasks of live lists (and list-less ones), outside the trash. Tasks of hidden lists
+// only with `include_hidden`.
+fn open_tasks(include_hidden: bool) -> String {
+    format!(
+        "completed = 0 AND deleted_at IS NULL AND list_name != 'Trash'
+         AND (list_id IS NULL OR list_id IN (SELECT id FROM lists WHERE deleted_at IS NULL{}))",
+        if include_hidden { "" } else { " AND hidden = 0" }
+    )
+}
+
+// Sorted by due date, then priority (none last), then manual order
+pub fn smart_view(
+    conn: &Connection,
+    view: SmartView,
+    now_ms: i64,
+    utc_offset_minutes: i32,
+    include_hidden: bool,
+) -> Result<Vec<Task>, String> {
+    let today = dates::day_start_at_offset(now_ms, utc_offset_minutes)?;
+    let (range, bounds) = match view {
+        SmartView::Today => ("due_date >= ?1 AND due_date < ?2", vec![today, today + DAY_MS]),
+        SmartView::Upcoming7Days => (
+            "due_date >= ?1 AND due_date < ?2",
+            vec![today, today + (UPCOMING_DAYS + 1) * DAY_MS],
+        ),
+        SmartView::Overdue => ("due_date < ?1", vec![today]),
+        SmartView::NoDate => ("due_date IS NULL", vec![]),
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"
+            SELECT {} FROM tasks
+            WHERE {} AND {}
+            ORDER BY due_date, priority IS NULL, priority, "order"
+            "#,
+            TASK_COLUMNS,
+            open_tasks(include_hidden),
+            range
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_from_iter(bounds), Task::from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// Open tasks nobody touched for `idle_days` that aren't waiting on a future due date,
+// least recently updated first. `idx_tasks_updated_at` (migration 21) serves the range.
+pub fn stale(conn: &Connection, idle_days: i64, now_ms: i64, include_hidden: bool) -> rusqlite::Result<Vec<Task>> {
+    let cutoff = now_ms - idle_days.max(0) * DAY_MS;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM tasks
+         WHERE {} AND updated_at < ?1 AND (due_date IS NULL OR due_date <= ?2)
+         ORDER BY updated_at, id",
+        TASK_COLUMNS,
+        open_tasks(include_hidden)
+    ))?;
+    let rows = stmt.query_map(params![cutoff, now_ms], Task::from_row)?;
+    rows.collect()
+}
+
+// `utc_offset_minutes` is the local clock's offset east of UTC, i.e.
+// `-new Date().getTimezoneOffset()` in the webview
+#[tauri::command]
+pub async fn get_smart_view(
+    app: AppHandle,
+    view: SmartView,
+    utc_offset_minutes: i32,
+    include_hidden: Option<bool>,
+) -> Result<Vec<Task>, String> {
+    let conn = db::connect(&app)?;
+    smart_view(&conn, view, dates::now_ms(), utc_offset_minutes, include_hidden.unwrap_or(false))
+}
+
+#[tauri::command]
+pub async fn get_stale_tasks(
+    app: AppHandle,
+    idle_days: i64,
+    now_ms: i64,
+    include_hidden: Option<bool>,
+) -> Result<Vec<Task>, String> {
+    let conn = db::connect(&app)?;
+    stale(&conn, idle_days, now_ms, include_hidden.unwrap_or(false)).map_err(|e| e.to_string())
+}
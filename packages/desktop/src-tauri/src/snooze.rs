@@ -0,0 +1,212 @@
+use crate::reminders::ReminderScheduler;
+use crate::tasks::{self, Task};
+use crate::{dates, db, journal, tray};
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::{AppHandle, Manager};
+
+// Open, live tasks outside the trash
+const SNOOZABLE: &str = "completed = 0 AND deleted_at IS NULL AND list_name != 'Trash'";
+
+fn check_until(until_ms: i64, now: i64) -> Result<(), String> {
+    if until_ms < now {
+        return Err("Tasks can only be snoozed to a time in the future".into());
+    }
+    Ok(())
+}
+
+// Move the due dates of `ids` to `until_ms`, skipping tasks that are already due later.
+// Changing the due date re-arms the reminder (see the `tasks_rearm_reminder` trigger).
+fn snooze_ids(conn: &Connection, ids: &[String], until_ms: i64, now: i64) -> rusqlite::Result<usize> {
+    let mut stmt = conn.prepare(&format!(
+        "UPDATE tasks SET due_date = ?1, group_category = ?2, updated_at = ?3
+         WHERE id = ?4 AND {} AND (due_date IS NULL OR due_date < ?1)",
+        SNOOZABLE
+    ))?;
+    let category = dates::group_category(Some(until_ms), false, now);
+    let mut snoozed = 0;
+    for id in ids {
+        snoozed += stmt.execute(params![until_ms, category, now, id])?;
+    }
+    Ok(snoozed)
+}
+
+pub fn snooze(conn: &mut Connection, id: &str, until_ms: i64) -> Result<Task, String> {
+    let now = dates::now_ms();
+    check_until(until_ms, now)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "snooze_task").map_err(|e| e.to_string())?;
+    let task = tasks::get(&tx, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))?;
+    if task.completed {
+        return Err(format!("Task {} is already completed", id));
+    }
+    snooze_ids(&tx, &[task.id], until_ms, now).map_err(|e| e.to_string())?;
+    let task = tasks::get(&tx, id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
+}
+
+// Snooze every open task that is overdue right now, returns how many moved
+pub fn snooze_all_overdue(conn: &mut Connection, until_ms: i64) -> Result<usize, String> {
+    let now = dates::now_ms();
+    check_until(until_ms, now)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "snooze_overdue").map_err(|e| e.to_string())?;
+    let ids: Vec<String> = {
+        let mut stmt = tx
+            .prepare(&format!("SELECT id, due_date FROM tasks WHERE {} AND due_date IS NOT NULL", SNOOZABLE))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+            .map_err(|e| e.to_string())?;
+        let mut ids = Vec::new();
+        for row in rows {
+            let (id, due_date) = row.map_err(|e| e.to_string())?;
+            if dates::group_category(Some(due_date), false, now) == "overdue" {
+                ids.push(id);
+            }
+        }
+        ids
+    };
+    let snoozed = snooze_ids(&tx, &ids, until_ms, now).map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(snoozed)
+}
+
+// Move the due dates of `ids` by `delta_ms`, earlier for a negative delta but never before
+// the epoch. Tasks without a due date (or in the trash) are skipped, returns how many moved.
+pub fn shift(conn: &mut Connection, ids: &[String], delta_ms: i64) -> Result<usize, String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "shift_due_dates").map_err(|e| e.to_string())?;
+    let mut shifted = 0;
+    {
+        let mut select = tx
+            .prepare(
+                "SELECT due_date, completed FROM tasks
+                 WHERE id = ?1 AND due_date IS NOT NULL AND deleted_at IS NULL AND list_name != 'Trash'",
+            )
+            .map_err(|e| e.to_string())?;
+        let mut update = tx
+            .prepare("UPDATE tasks SET due_date = ?1, group_category = ?2, updated_at = ?3 WHERE id = ?4")
+            .map_err(|e| e.to_string())?;
+        for id in ids {
+            let found = select
+                .query_row([id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)))
+                .optional()
+                .map_err(|e| e.to_string())?;
+            let Some((due_date, completed)) = found else {
+                continue;
+            };
+            let moved = due_date.saturating_add(delta_ms).max(0);
+            if moved == due_date {
+                continue;
+            }
+            let category = dates::group_category(Some(moved), completed, now);
+            shifted += update
+                .execute(params![moved, category, now, id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(shifted)
+}
+
+// Give `ids` the due date `due_ms`, or none with `None`, returns how many changed. Unknown
+// ids and tasks in the trash are skipped. Clearing the date also silences the reminder, a
+// reminder only fires for a task with a due date, and setting one re-arms it.
+pub fn assign(conn: &mut Connection, ids: &[String], due_ms: Option<i64>) -> Result<usize, String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "assign_due_date").map_err(|e| e.to_string())?;
+    let mut assigned = 0;
+    {
+        let mut select = tx
+            .prepare(
+                "SELECT due_date, completed FROM tasks
+                 WHERE id = ?1 AND deleted_at IS NULL AND list_name != 'Trash'",
+            )
+            .map_err(|e| e.to_string())?;
+        let mut update = tx
+            .prepare("UPDATE tasks SET due_date = ?1, group_category = ?2, updated_at = ?3 WHERE id = ?4")
+            .map_err(|e| e.to_string())?;
+        for id in ids {
+            let found = select
+                .query_row([id], |row| Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, bool>(1)?)))
+                .optional()
+                .map_err(|e| e.to_string())?;
+            let Some((due_date, completed)) = found else {
+                continue;
+            };
+            if due_date == due_ms {
+                continue;
+            }
+            let category = dates::group_category(due_ms, completed, now);
+            assigned += update
+                .execute(params![due_ms, category, now, id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(assigned)
+}
+
+fn after_snooze(app: &AppHandle) {
+    app.state::<ReminderScheduler>().wake();
+    if let Err(e) = tray::refresh(app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn snooze_task(app: AppHandle, id: String, until_ms: i64) -> Result<Task, String> {
+    let task = {
+        let mut conn = db::connect(&app)?;
+        snooze(&mut conn, &id, until_ms)?
+    };
+    after_snooze(&app);
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn snooze_overdue(app: AppHandle, until_ms: i64) -> Result<usize, String> {
+    let snoozed = {
+        let mut conn = db::connect(&app)?;
+        snooze_all_overdue(&mut conn, until_ms)?
+    };
+    if snoozed > 0 {
+        after_snooze(&app);
+    }
+    Ok(snoozed)
+}
+
+#[tauri::command]
+pub async fn shift_due_dates(app: AppHandle, task_ids: Vec<String>, delta_ms: i64) -> Result<usize, String> {
+    let shifted = {
+        let mut conn = db::connect(&app)?;
+        shift(&mut conn, &task_ids, delta_ms)?
+    };
+    if shifted > 0 {
+        after_snooze(&app);
+    }
+    Ok(shifted)
+}
+
+#[tauri::command]
+pub async fn assign_due_date(app: AppHandle, task_ids: Vec<String>, due_ms: Option<i64>) -> Result<usize, String> {
+    let assigned = {
+        let mut conn = db::connect(&app)?;
+        assign(&mut conn, &task_ids, due_ms)?
+    };
+    if assigned > 0 {
+        after_snooze(&app);
+    }
+    Ok(assigned)
+}
@@ -0,0 +1,276 @@
+use crate::reminders::ReminderScheduler;
+use crate::tasks::{self, NewTask, Task};
+use crate::{db, journal, list_defaults, tray};
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::{AppHandle, Manager};
+
+// `tasks.sort_key` (migration 15) orders tasks inside a list by plain string comparison.
+// A key is read as the base-36 fraction 0.<key>, so there is always room for another key
+// between two neighbors and inserting a task writes only its own row. Keys never end in
+// '0': "a" and "a0" would be the same fraction with nothing between them.
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const BASE: usize = 36;
+// Rebalanced keys are the rank padded to this width plus RANK_SUFFIX, the backfill in
+// migration 15 writes the same format
+const RANK_WIDTH: usize = 8;
+const RANK_SUFFIX: char = 'i';
+// A list is rebalanced once an insert produces a key longer than this
+pub const MAX_KEY_LEN: usize = 24;
+
+fn digit(c: u8) -> Option<usize> {
+    DIGITS.iter().position(|d| *d == c)
+}
+
+pub fn is_valid(key: &str) -> bool {
+    !key.is_empty() && !key.ends_with('0') && key.bytes().all(|c| digit(c).is_some())
+}
+
+// `a` < `b`, an empty `a` stands for 0 and a missing `b` for 1
+fn midpoint(a: &[u8], b: Option<&[u8]>) -> Vec<u8> {
+    if let Some(b) = b {
+        // Shared leading digits (`a` padded with zeros) stay as they are
+        let mut n = 0;
+        while n < b.len() && a.get(n).copied().unwrap_or(b'0') == b[n] {
+            n += 1;
+        }
+        if n > 0 {
+            let mut key = b[..n].to_vec();
+            key.extend(midpoint(a.get(n..).unwrap_or_default(), Some(&b[n..])));
+            return key;
+        }
+    }
+    let low = a.first().and_then(|c| digit(*c)).unwrap_or(0);
+    let high = b.and_then(|b| b.first()).and_then(|c| digit(*c)).unwrap_or(BASE);
+    if high - low > 1 {
+        return vec![DIGITS[(low + high) / 2]];
+    }
+    match b {
+        // `b`'s first digit alone already sorts before `b`
+        Some(b) if b.len() > 1 => vec![b[0]],
+        _ => {
+            let mut key = vec![DIGITS[low]];
+            key.extend(midpoint(a.get(1..).unwrap_or_default(), None));
+            key
+        }
+    }
+}
+
+// A key strictly between two neighbors, either of which may be missing (start or end of
+// the list)
+pub fn key_between(before: Option<&str>, after: Option<&str>) -> Result<String, String> {
+    for key in [before, after].into_iter().flatten() {
+        if !is_valid(key) {
+            return Err(format!("'{}' is not a valid sort key", key));
+        }
+    }
+    if let (Some(before), Some(after)) = (before, after) && before >= after {
+        return Err(format!("Sort key '{}' doesn't sort before '{}'", before, after));
+    }
+    let key = midpoint(before.unwrap_or("").as_bytes(), after.map(str::as_bytes));
+    Ok(String::from_utf8(key).unwrap_or_default())
+}
+
+fn rank_key(rank: usize) -> String {
+    format!("{:0width$}{}", rank, RANK_SUFFIX, width = RANK_WIDTH)
+}
+
+// Give every task of the list (trashed ones included) a fresh short key. "order" comes
+// first, so a reorder made by the frontend (which only writes "order" and leaves the key
+// cleared, see the trigger in migration 15) wins; the old keys break ties.
+pub fn rebalance(conn: &Connection, list_id: Option<&str>) -> rusqlite::Result<usize> {
+    let ids: Vec<String> = {
+        let mut stmt = conn.prepare(
+            r#"SELECT id FROM tasks WHERE list_id IS ?1
+               ORDER BY "order", sort_key IS NULL, sort_key, created_at, id"#,
+        )?;
+        let rows = stmt.query_map([list_id], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    for (index, id) in ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE tasks SET sort_key = ?1 WHERE id = ?2",
+            params![rank_key(index + 1), id],
+        )?;
+    }
+    Ok(ids.len())
+}
+
+// Rebalance only if some task of the list has no usable key
+fn ensure_keys(conn: &Connection, list_id: &str) -> rusqlite::Result<()> {
+    let keys: Vec<Option<String>> = {
+        let mut stmt = conn.prepare("SELECT sort_key FROM tasks WHERE list_id = ?1")?;
+        let rows = stmt.query_map([list_id], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    if keys.iter().any(|key| !key.as_deref().is_some_and(is_valid)) {
+        rebalance(conn, Some(list_id))?;
+    }
+    Ok(())
+}
+
+// Key for a task appended to the end of a list, `None` while the list still has tasks
+// without one (the next `ensure_keys` sorts it in by "order")
+pub fn next_key(conn: &Connection, list_id: &str) -> rusqlite::Result<Option<String>> {
+    let missing: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM tasks WHERE list_id = ?1 AND sort_key IS NULL)",
+        [list_id],
+        |row| row.get(0),
+    )?;
+    if missing {
+        return Ok(None);
+    }
+    let last: Option<String> = conn
+        .query_row(
+            "SELECT sort_key FROM tasks WHERE list_id = ?1 ORDER BY sort_key DESC LIMIT 1",
+            [list_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+    Ok(key_between(last.as_deref(), None).ok())
+}
+
+struct Neighbor {
+    order: i64,
+    sort_key: String,
+}
+
+fn neighbor(conn: &Connection, list_id: &str, id: Option<&str>) -> Result<Option<Neighbor>, String> {
+    let Some(id) = id else {
+        return Ok(None);
+    };
+    let row: Option<(Option<String>, i64, Option<String>)> = conn
+        .query_row(
+            r#"SELECT list_id, "order", sort_key FROM tasks WHERE id = ?1 AND deleted_at IS NULL"#,
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match row {
+        None => Err(format!("Task {} not found", id)),
+        Some((task_list, _, _)) if task_list.as_deref() != Some(list_id) => {
+            Err(format!("Task {} is not in list {}", id, list_id))
+        }
+        Some((_, order, sort_key)) => Ok(Some(Neighbor {
+            order,
+            sort_key: sort_key.unwrap_or_default(),
+        })),
+    }
+}
+
+// Insert a task between two neighbors of the same list (either may be left out for the
+// start or end). The new task also takes the "order" right after `prev` (that of `next` at
+// the start of the list) so the frontend, which sorts by "order", shows it in the same
+// place. When a task already has that "order" the rest of the list moves down one, which
+// clears their keys (the trigger in migration 15), so the list is rebalanced.
+pub fn insert_between(
+    conn: &mut Connection,
+    list_id: &str,
+    prev_id: Option<&str>,
+    next_id: Option<&str>,
+    mut task: NewTask,
+) -> Result<Task, String> {
+    if prev_id.is_none() && next_id.is_none() {
+        return Err("Give at least one neighbor".to_string());
+    }
+    if prev_id.is_some() && prev_id == next_id {
+        return Err("The neighbors must be two different tasks".to_string());
+    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "insert_task_between").map_err(|e| e.to_string())?;
+    let list_name = tasks::list_name(&tx, list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+    ensure_keys(&tx, list_id).map_err(|e| e.to_string())?;
+    let prev = neighbor(&tx, list_id, prev_id)?;
+    let next = neighbor(&tx, list_id, next_id)?;
+    let key = key_between(
+        prev.as_ref().map(|n| n.sort_key.as_str()),
+        next.as_ref().map(|n| n.sort_key.as_str()),
+    )?;
+    let order = match (&prev, &next) {
+        (Some(prev), _) => prev.order + 1,
+        (None, Some(next)) => next.order,
+        (None, None) => unreachable!(),
+    };
+    let taken: bool = tx
+        .query_row(
+            r#"SELECT EXISTS (SELECT 1 FROM tasks WHERE list_id = ?1 AND "order" = ?2)"#,
+            params![list_id, order],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if taken {
+        tx.execute(
+            r#"UPDATE tasks SET "order" = "order" + 1 WHERE list_id = ?1 AND "order" >= ?2"#,
+            params![list_id, order],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(defaults) = list_defaults::load(&tx, list_id).map_err(|e| e.to_string())? {
+        defaults.apply(&mut task);
+    }
+    let id = tasks::insert(&tx, list_id, &list_name, &task).map_err(|e| e.to_string())?;
+    tx.execute(
+        r#"UPDATE tasks SET "order" = ?1, sort_key = ?2 WHERE id = ?3"#,
+        params![order, key, id],
+    )
+    .map_err(|e| e.to_string())?;
+    if taken || key.len() > MAX_KEY_LEN {
+        rebalance(&tx, Some(list_id)).map_err(|e| e.to_string())?;
+    }
+
+    let task = tasks::get(&tx, &id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", id))?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn insert_task_between(
+    app: AppHandle,
+    list_id: String,
+    prev_id: Option<String>,
+    next_id: Option<String>,
+    title: String,
+    due_date: Option<i64>,
+    priority: Option<i64>,
+) -> Result<Task, String> {
+    let title = title.trim().to_string();
+    if title.is_empty() {
+        return Err("A task needs a title".to_string());
+    }
+    let task = {
+        let mut conn = db::connect(&app)?;
+        let new_task = NewTask {
+            title,
+            due_date,
+            priority,
+            ..Default::default()
+        };
+        insert_between(&mut conn, &list_id, prev_id.as_deref(), next_id.as_deref(), new_task)?
+    };
+    if task.due_date.is_some() {
+        app.state::<ReminderScheduler>().wake();
+    }
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(task)
+}
+
+// Returns how many tasks got a new key
+#[tauri::command]
+pub async fn rebalance_sort_keys(app: AppHandle, list_id: String) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::pause(&tx).map_err(|e| e.to_string())?;
+    let count = rebalance(&tx, Some(&list_id)).map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(count)
+}
@@ -0,0 +1,621 @@
+use crate::settings::{self, Preferences};
+use crate::tasks::{Task, TASK_COLUMNS};
+use crate::{dates, db, lists};
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayStat {
+    // Local calendar day, YYYY-MM-DD
+    pub date: String,
+    pub created: i64,
+    pub completed: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListStat {
+    pub list_id: Option<String>,
+    pub list_name: String,
+    // Tasks that were open at some point during the range
+    pub total: i64,
+    // Of those, tasks completed within the range
+    pub completed: i64,
+    pub completion_rate: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsReport {
+    pub range_start: i64,
+    pub range_end: i64,
+    pub days: Vec<DayStat>,
+    pub lists: Vec<ListStat>,
+    // Mean of completed_at - created_at for tasks completed within the range
+    pub average_completion_ms: Option<f64>,
+    // Tasks due within the range (up to now) that were not completed by their due date
+    pub overdue_count: i64,
+}
+
+// All ranges are half-open, [range_start, range_end). Completion is only attributed when
+// `completed_at` is known: a task flagged completed without a timestamp can't be placed in
+// time, so it is left out of completion counts, rates and averages. Tasks of hidden lists
+// only count with `include_hidden`.
+pub fn compute(
+    conn: &Connection,
+    range_start: i64,
+    range_end: i64,
+    include_hidden: bool,
+) -> rusqlite::Result<StatisticsReport> {
+    let hidden = lists::hidden_filter(include_hidden);
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT day, SUM(created), SUM(completed) FROM (
+            SELECT date(created_at / 1000, 'unixepoch', 'localtime') AS day, 1 AS created, 0 AS completed
+            FROM tasks
+            WHERE created_at >= ?1 AND created_at < ?2 AND list_name != 'Trash' AND deleted_at IS NULL{0}
+            UNION ALL
+            SELECT date(completed_at / 1000, 'unixepoch', 'localtime'), 0, 1
+            FROM tasks
+            WHERE completed = 1 AND completed_at >= ?1 AND completed_at < ?2 AND list_name != 'Trash' AND deleted_at IS NULL{0}
+        )
+        GROUP BY day
+        ORDER BY day
+        "#,
+        hidden
+    ))?;
+    let days = stmt
+        .query_map(params![range_start, range_end], |row| {
+            Ok(DayStat {
+                date: row.get(0)?,
+                created: row.get(1)?,
+                completed: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // A task is in scope if it existed before the range ended and was not already done
+    // before it started
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT list_id, list_name,
+               COUNT(*) AS total,
+               SUM(CASE WHEN completed = 1 AND completed_at >= ?1 AND completed_at < ?2 THEN 1 ELSE 0 END)
+        FROM tasks
+        WHERE created_at < ?2
+          AND list_name != 'Trash' AND deleted_at IS NULL{}
+          AND NOT (completed = 1 AND (completed_at IS NULL OR completed_at < ?1))
+        GROUP BY list_id, list_name
+        ORDER BY list_name
+        "#,
+        hidden
+    ))?;
+    let lists = stmt
+        .query_map(params![range_start, range_end], |row| {
+            let total: i64 = row.get(2)?;
+            let completed: i64 = row.get(3)?;
+            Ok(ListStat {
+                list_id: row.get(0)?,
+                list_name: row.get(1)?,
+                total,
+                completed,
+                completion_rate: if total > 0 { completed as f64 / total as f64 } else { 0.0 },
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let average_completion_ms: Option<f64> = conn.query_row(
+        &format!(
+            r#"
+            SELECT AVG(completed_at - created_at)
+            FROM tasks
+            WHERE completed = 1 AND completed_at >= ?1 AND completed_at < ?2
+              AND completed_at >= created_at AND list_name != 'Trash' AND deleted_at IS NULL{}
+            "#,
+            hidden
+        ),
+        params![range_start, range_end],
+        |row| row.get(0),
+    )?;
+
+    let overdue_count: i64 = conn.query_row(
+        &format!(
+            r#"
+            SELECT COUNT(*)
+            FROM tasks
+            WHERE due_date >= ?1 AND due_date < MIN(?2, ?3)
+              AND list_name != 'Trash' AND deleted_at IS NULL{}
+              AND (completed = 0 OR completed_at > due_date)
+            "#,
+            hidden
+        ),
+        params![range_start, range_end, dates::now_ms()],
+        |row| row.get(0),
+    )?;
+
+    Ok(StatisticsReport {
+        range_start,
+        range_end,
+        days,
+        lists,
+        average_completion_ms,
+        overdue_count,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayCount {
+    // Calendar day at the requested offset, YYYY-MM-DD
+    pub date: String,
+    pub count: i64,
+}
+
+// Completions per day in [start_ms, end_ms), every day of the range listed, zeros
+// included. Days are cut at `tz_offset_minutes` east of UTC. Archived tasks count: they
+// were completed like any other. So do the tasks of hidden lists, hiding a list sets its
+// open work aside but doesn't undo what was done.
+pub fn heatmap(conn: &Connection, start_ms: i64, end_ms: i64, tz_offset_minutes: i32) -> rusqlite::Result<Vec<DayCount>> {
+    let offset_ms = tz_offset_minutes as i64 * 60_000;
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT date((completed_at + ?3) / 1000, 'unixepoch') AS day, COUNT(*) FROM (
+            SELECT completed_at FROM tasks
+            WHERE completed = 1 AND completed_at >= ?1 AND completed_at < ?2 AND list_name != 'Trash' AND deleted_at IS NULL
+            UNION ALL
+            SELECT completed_at FROM archived_tasks WHERE completed_at >= ?1 AND completed_at < ?2
+        )
+        GROUP BY day
+        "#,
+    )?;
+    let counts: HashMap<String, i64> = stmt
+        .query_map(params![start_ms, end_ms, offset_ms], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let day_of = |ms: i64| DateTime::from_timestamp_millis(ms + offset_ms).map(|dt| dt.date_naive());
+    let (Some(first), Some(last)) = (day_of(start_ms), day_of(end_ms - 1)) else {
+        return Ok(Vec::new());
+    };
+    Ok(first
+        .iter_days()
+        .take_while(|day| *day <= last)
+        .map(|day| {
+            let date = day.format("%Y-%m-%d").to_string();
+            DayCount {
+                count: counts.get(&date).copied().unwrap_or(0),
+                date,
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurndownPoint {
+    // Calendar day at the requested offset, YYYY-MM-DD
+    pub date: String,
+    // Tasks of the list that existed and were still open when the day ended
+    pub open: i64,
+}
+
+// Open tasks of a list at the end of each day in [start_ms, end_ms), every day listed.
+// Archived tasks count until their completion. Tasks flagged completed without a
+// `completed_at` can't be placed in time and are left out, the same as in `compute`.
+pub fn burndown(
+    conn: &Connection,
+    list_id: &str,
+    start_ms: i64,
+    end_ms: i64,
+    tz_offset_minutes: i32,
+) -> rusqlite::Result<Vec<BurndownPoint>> {
+    let offset_ms = tz_offset_minutes as i64 * 60_000;
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT created_at, CASE WHEN completed = 1 THEN completed_at END FROM tasks
+        WHERE list_id = ?1 AND created_at < ?2 AND list_name != 'Trash' AND deleted_at IS NULL
+          AND NOT (completed = 1 AND completed_at IS NULL)
+        UNION ALL
+        SELECT created_at, completed_at FROM archived_tasks
+        WHERE list_id = ?1 AND created_at < ?2 AND completed_at IS NOT NULL
+        "#,
+    )?;
+    let tasks: Vec<(i64, Option<i64>)> = stmt
+        .query_map(params![list_id, end_ms], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let day_of = |ms: i64| DateTime::from_timestamp_millis(ms + offset_ms).map(|dt| dt.date_naive());
+    let (Some(first), Some(last)) = (day_of(start_ms), day_of(end_ms - 1)) else {
+        return Ok(Vec::new());
+    };
+    Ok(first
+        .iter_days()
+        .take_while(|day| *day <= last)
+        .map(|day| {
+            let day_end = day
+                .succ_opt()
+                .map(|next| next.and_time(NaiveTime::MIN).and_utc().timestamp_millis() - offset_ms)
+                .unwrap_or(i64::MAX);
+            let open = tasks
+                .iter()
+                .filter(|(created_at, completed_at)| {
+                    *created_at < day_end && !completed_at.is_some_and(|done| done < day_end)
+                })
+                .count() as i64;
+            BurndownPoint {
+                date: day.format("%Y-%m-%d").to_string(),
+                open,
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreakInfo {
+    // Consecutive days with a completion, ending today or, while today has none yet,
+    // yesterday
+    pub current: i64,
+    pub longest: i64,
+    pub completed_today: bool,
+    // Local day of the most recent completion, YYYY-MM-DD
+    pub last_day: Option<String>,
+}
+
+// Days are local calendar days, each timestamp converted with the offset in force at the
+// time so DST changes don't move completions across midnight. Archived tasks count, like in
+// `heatmap`.
+pub fn streak(conn: &Connection, now_ms: i64) -> rusqlite::Result<StreakInfo> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT DISTINCT date(completed_at / 1000, 'unixepoch', 'localtime') AS day FROM (
+            SELECT completed_at FROM tasks
+            WHERE completed = 1 AND completed_at IS NOT NULL AND list_name != 'Trash' AND deleted_at IS NULL
+            UNION ALL
+            SELECT completed_at FROM archived_tasks WHERE completed_at IS NOT NULL
+        )
+        ORDER BY day
+        "#,
+    )?;
+    let days: Vec<NaiveDate> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .filter_map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .collect();
+
+    let mut info = StreakInfo::default();
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in &days {
+        run = match previous {
+            Some(prev) if prev.succ_opt() == Some(*day) => run + 1,
+            _ => 1,
+        };
+        info.longest = info.longest.max(run);
+        previous = Some(*day);
+    }
+
+    let Some(today) = dates::local_date(now_ms) else {
+        return Ok(info);
+    };
+    info.last_day = previous.map(|day| day.format("%Y-%m-%d").to_string());
+    info.completed_today = previous == Some(today);
+    // The run ending at the last completion is the current one if that was today or
+    // yesterday, a day without completions in between breaks it
+    if previous.is_some_and(|last| last == today || last.succ_opt() == Some(today)) {
+        info.current = run;
+    }
+    Ok(info)
+}
+
+// Tasks listed in `OverdueAging::oldest`
+const OLDEST_OVERDUE: usize = 10;
+
+// Overdue tasks by how many calendar days they are past due
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdueAging {
+    pub one_day: i64,
+    pub two_to_seven_days: i64,
+    pub eight_to_thirty_days: i64,
+    pub over_thirty_days: i64,
+    pub total: i64,
+    // Longest overdue first
+    pub oldest: Vec<Task>,
+}
+
+// Incomplete live tasks due before the day of `now_ms` at `tz_offset_minutes` east of UTC,
+// bucketed by how many calendar days they are past due. Tasks carried over to today by
+// `rollover::rollover` aren't overdue, like in `groups::grouped`. Tasks of hidden lists only
+// with `include_hidden`.
+pub fn overdue_aging(
+    conn: &Connection,
+    now_ms: i64,
+    tz_offset_minutes: i32,
+    include_hidden: bool,
+) -> Result<OverdueAging, String> {
+    let today_start = dates::day_start_at_offset(now_ms, tz_offset_minutes)?;
+    let offset_ms = tz_offset_minutes as i64 * 60_000;
+    let overdue = format!(
+        "completed = 0 AND due_date < ?1 AND deleted_at IS NULL AND list_name != 'Trash'
+         AND (rolled_over_at IS NULL OR rolled_over_at < ?1){}",
+        lists::hidden_filter(include_hidden)
+    );
+
+    let mut aging = OverdueAging::default();
+    let mut stmt = conn
+        .prepare(&format!(
+            r#"
+            SELECT CASE WHEN days <= 1 THEN 0 WHEN days <= 7 THEN 1 WHEN days <= 30 THEN 2 ELSE 3 END AS bucket, COUNT(*)
+            FROM (
+                SELECT CAST(julianday(date((?1 + ?2) / 1000, 'unixepoch'))
+                            - julianday(date((due_date + ?2) / 1000, 'unixepoch')) AS INTEGER) AS days
+                FROM tasks WHERE {}
+            )
+            GROUP BY bucket
+            "#,
+            overdue
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![today_start, offset_ms], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (bucket, count) = row.map_err(|e| e.to_string())?;
+        match bucket {
+            0 => aging.one_day = count,
+            1 => aging.two_to_seven_days = count,
+            2 => aging.eight_to_thirty_days = count,
+            _ => aging.over_thirty_days = count,
+        }
+        aging.total += count;
+    }
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM tasks WHERE {} ORDER BY due_date, id LIMIT {}",
+            TASK_COLUMNS, overdue, OLDEST_OVERDUE
+        ))
+        .map_err(|e| e.to_string())?;
+    aging.oldest = stmt
+        .query_map([today_start], Task::from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(|e| e.to_string())?;
+    Ok(aging)
+}
+
+const WEEK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+// `preferences.weeklyScore`: how much each part counts towards `get_weekly_score`, relative
+// to each other
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WeeklyScoreWeights {
+    pub completed: i64,
+    pub on_time: i64,
+    pub carryover: i64,
+    // Completions in a week that earn the full completed part
+    pub completed_target: i64,
+}
+
+impl Default for WeeklyScoreWeights {
+    fn default() -> Self {
+        Self {
+            completed: 40,
+            on_time: 40,
+            carryover: 20,
+            completed_target: 20,
+        }
+    }
+}
+
+impl WeeklyScoreWeights {
+    // Used by `Preferences::problems`
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let weights = [("completed", self.completed), ("onTime", self.on_time), ("carryover", self.carryover)];
+        for (name, weight) in weights {
+            if weight < 0 {
+                problems.push(format!("weeklyScore.{} can't be negative, got {}", name, weight));
+            }
+        }
+        if self.completed + self.on_time + self.carryover <= 0 {
+            problems.push("weeklyScore needs a positive weight".to_string());
+        }
+        if self.completed_target < 1 {
+            problems.push(format!("weeklyScore.completedTarget must be at least 1, got {}", self.completed_target));
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyScore {
+    pub week_start: i64,
+    pub week_end: i64,
+    // 0 to 100, `None` for a week without completions or carryover
+    pub score: Option<i64>,
+    pub completed: i64,
+    // `completed` against `completedTarget`, 0 to 1
+    pub completed_part: f64,
+    // Completed tasks that had a due date, and those done on or before that day
+    pub completed_with_due_date: i64,
+    pub completed_on_time: i64,
+    // `None` without completed tasks that had a due date
+    pub on_time_part: Option<f64>,
+    // Tasks past their due date and still open when the week ended (or now, for this week)
+    pub carryover: i64,
+    // 1 without carryover, lower the more dated work was left open. `None` without dated
+    // tasks either way.
+    pub carryover_part: Option<f64>,
+    pub weights: WeeklyScoreWeights,
+}
+
+// The week from the start of the day of `week_start_ms` at `tz_offset_minutes` east of UTC.
+// The parts that apply are averaged by their weights, one without data (no dated tasks)
+// leaves the mix instead of counting as zero. Archived tasks count, like in `heatmap`, live
+// tasks of hidden lists only with `include_hidden`.
+pub fn weekly_score(
+    conn: &Connection,
+    week_start_ms: i64,
+    tz_offset_minutes: i32,
+    weights: WeeklyScoreWeights,
+    now_ms: i64,
+    include_hidden: bool,
+) -> Result<WeeklyScore, String> {
+    let start = dates::day_start_at_offset(week_start_ms, tz_offset_minutes)?;
+    let end = start + WEEK_MS;
+    let offset_ms = tz_offset_minutes as i64 * 60_000;
+    let hidden = lists::hidden_filter(include_hidden);
+
+    let (completed, completed_with_due_date, completed_on_time): (i64, i64, i64) = conn
+        .query_row(
+            &format!(
+                r#"
+                SELECT COUNT(*), COUNT(due_date), COALESCE(SUM(
+                    due_date IS NOT NULL
+                    AND date((completed_at + ?3) / 1000, 'unixepoch') <= date((due_date + ?3) / 1000, 'unixepoch')
+                ), 0) FROM (
+                    SELECT completed_at, due_date FROM tasks
+                    WHERE completed = 1 AND completed_at >= ?1 AND completed_at < ?2 AND list_name != 'Trash' AND deleted_at IS NULL{}
+                    UNION ALL
+                    SELECT completed_at, due_date FROM archived_tasks WHERE completed_at >= ?1 AND completed_at < ?2
+                )
+                "#,
+                hidden
+            ),
+            params![start, end, offset_ms],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let carryover: i64 = conn
+        .query_row(
+            &format!(
+                r#"
+                SELECT COUNT(*) FROM tasks
+                WHERE due_date < MIN(?1, ?2) AND created_at < ?1
+                  AND list_name != 'Trash' AND deleted_at IS NULL{}
+                  AND (completed = 0 OR completed_at >= ?1)
+                "#,
+                hidden
+            ),
+            params![end, now_ms],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let completed_part = (completed as f64 / weights.completed_target.max(1) as f64).min(1.0);
+    let on_time_part = (completed_with_due_date > 0).then(|| completed_on_time as f64 / completed_with_due_date as f64);
+    let dated = carryover + completed_with_due_date;
+    let carryover_part = (dated > 0).then(|| 1.0 - carryover as f64 / dated as f64);
+
+    let parts = [
+        (Some(completed_part), weights.completed),
+        (on_time_part, weights.on_time),
+        (carryover_part, weights.carryover),
+    ];
+    let (total, weight) = parts
+        .iter()
+        .filter_map(|(part, weight)| part.map(|part| (part, (*weight).max(0) as f64)))
+        .fold((0.0, 0.0), |(total, sum), (part, weight)| (total + part * weight, sum + weight));
+    let score = (completed + carryover > 0 && weight > 0.0).then(|| (total / weight * 100.0).round() as i64);
+
+    Ok(WeeklyScore {
+        week_start: start,
+        week_end: end,
+        score,
+        completed,
+        completed_part,
+        completed_with_due_date,
+        completed_on_time,
+        on_time_part,
+        carryover,
+        carryover_part,
+        weights,
+    })
+}
+
+#[tauri::command]
+pub async fn get_completion_heatmap(
+    app: AppHandle,
+    start_ms: i64,
+    end_ms: i64,
+    tz_offset_minutes: i32,
+) -> Result<Vec<DayCount>, String> {
+    if end_ms <= start_ms {
+        return Err("end_ms must be after start_ms".to_string());
+    }
+    let conn = db::connect(&app)?;
+    heatmap(&conn, start_ms, end_ms, tz_offset_minutes).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_statistics(
+    app: AppHandle,
+    range_start: i64,
+    range_end: i64,
+    include_hidden: Option<bool>,
+) -> Result<StatisticsReport, String> {
+    if range_end <= range_start {
+        return Err("range_end must be after range_start".to_string());
+    }
+    let conn = db::connect(&app)?;
+    compute(&conn, range_start, range_end, include_hidden.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_burndown(
+    app: AppHandle,
+    list_id: String,
+    start_ms: i64,
+    end_ms: i64,
+    tz_offset_minutes: i32,
+) -> Result<Vec<BurndownPoint>, String> {
+    if end_ms <= start_ms {
+        return Err("end_ms must be after start_ms".to_string());
+    }
+    let conn = db::connect(&app)?;
+    burndown(&conn, &list_id, start_ms, end_ms, tz_offset_minutes).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn current_streak(app: AppHandle) -> Result<StreakInfo, String> {
+    let conn = db::connect(&app)?;
+    streak(&conn, dates::now_ms()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_overdue_aging(
+    app: AppHandle,
+    now_ms: i64,
+    tz_offset_minutes: i32,
+    include_hidden: Option<bool>,
+) -> Result<OverdueAging, String> {
+    let conn = db::connect(&app)?;
+    overdue_aging(&conn, now_ms, tz_offset_minutes, include_hidden.unwrap_or(false))
+}
+
+#[tauri::command]
+pub async fn get_weekly_score(
+    app: AppHandle,
+    week_start_ms: i64,
+    tz_offset_minutes: i32,
+    include_hidden: Option<bool>,
+) -> Result<WeeklyScore, String> {
+    let conn = db::connect(&app)?;
+    let prefs = settings::get_typed::<Preferences>(&conn).map_err(|e| e.to_string())?;
+    weekly_score(
+        &conn,
+        week_start_ms,
+        tz_offset_minutes,
+        prefs.weekly_score,
+        dates::now_ms(),
+        include_hidden.unwrap_or(false),
+    )
+}
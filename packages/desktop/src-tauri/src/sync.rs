@@ -0,0 +1,155 @@
+use crate::export::{self, ExportDocument, ImportMode};
+use crate::{db, journal, secrets, settings};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tauri::AppHandle;
+use tauri_plugin_http::reqwest::{self, StatusCode};
+
+const WEBDAV_USERNAME: &str = "webdav.username";
+const WEBDAV_PASSWORD: &str = "webdav.password";
+// File created inside the WebDAV folder when the url doesn't name a file itself
+const SYNC_FILE: &str = "tada-sync.json";
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    // Records where the remote copy won (new or newer than the local one)
+    pub from_remote: usize,
+    // Records where the local copy won and was uploaded
+    pub from_local: usize,
+    // Records that were the same on both sides
+    pub unchanged: usize,
+    // False on the very first sync, when there was no remote copy yet
+    pub remote_existed: bool,
+}
+
+fn sync_file_url(url: &str) -> String {
+    let url = url.trim();
+    if url.ends_with(".json") {
+        url.to_string()
+    } else {
+        format!("{}/{}", url.trim_end_matches('/'), SYNC_FILE)
+    }
+}
+
+fn check_status(status: StatusCode) -> Result<(), String> {
+    match status {
+        StatusCode::UNAUTHORIZED => Err("The WebDAV server rejected the username or password (401)".to_string()),
+        StatusCode::FORBIDDEN => Err("The WebDAV account isn't allowed to access this folder (403)".to_string()),
+        status if status.is_success() => Ok(()),
+        status => Err(format!("The WebDAV server returned {}", status)),
+    }
+}
+
+// Count local records that are missing remotely or newer than the remote copy, and
+// records that are the same age on both sides
+fn compare(local: &ExportDocument, remote: &ExportDocument) -> (usize, usize) {
+    let (mut newer, mut same) = (0, 0);
+    for ((table, local_rows), (_, remote_rows)) in local.tables().into_iter().zip(remote.tables()) {
+        let key = export::key_column(table);
+        let remote_updated: HashMap<&str, Option<i64>> = remote_rows
+            .iter()
+            .filter_map(|row| {
+                let id = row.get(key)?.as_str()?;
+                Some((id, row.get("updated_at").and_then(Value::as_i64)))
+            })
+            .collect();
+        for row in local_rows {
+            let Some(id) = row.get(key).and_then(Value::as_str) else {
+                continue;
+            };
+            let local_updated = row.get("updated_at").and_then(Value::as_i64);
+            match (remote_updated.get(id), local_updated) {
+                (None, _) => newer += 1,
+                (Some(Some(remote)), Some(local)) if local > *remote => newer += 1,
+                (Some(remote), local) if *remote == local => same += 1,
+                _ => {}
+            }
+        }
+    }
+    (newer, same)
+}
+
+// Merge the remote copy into the database, returns the document to upload
+fn merge(app: &AppHandle, remote: Option<&ExportDocument>, result: &mut SyncResult) -> Result<String, String> {
+    let mut conn = db::connect(app)?;
+    let app_version = app.package_info().version.to_string();
+
+    if let Some(remote) = remote {
+        let local = export::collect_document(&conn, app_version.clone())?;
+        (result.from_local, result.unchanged) = compare(&local, remote);
+
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        // Remote changes are another device's edits, not something to undo here
+        journal::pause(&tx).map_err(|e| e.to_string())?;
+        let stats = export::apply_document(&tx, remote, ImportMode::Merge)?;
+        journal::end(&tx).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        result.from_remote = stats.inserted + stats.updated;
+    }
+
+    let document = export::collect_document(&conn, app_version)?;
+    if remote.is_none() {
+        result.from_local = document.tables().iter().map(|(_, rows)| rows.len()).sum();
+    }
+    serde_json::to_string(&document).map_err(|e| e.to_string())
+}
+
+// Last-write-wins sync against a JSON export kept on a WebDAV server: the remote copy is
+// downloaded and merged by `updated_at`, then the merged state is uploaded again. An empty
+// username or password falls back to the credentials saved by the previous sync.
+#[tauri::command]
+pub async fn sync_webdav(app: AppHandle, url: String, username: String, password: String) -> Result<SyncResult, String> {
+    let (username, password) = {
+        let conn = db::connect(&app)?;
+        let username = Some(username)
+            .filter(|u| !u.is_empty())
+            .or_else(|| secrets::load(&conn, WEBDAV_USERNAME))
+            .unwrap_or_default();
+        let password = Some(password)
+            .filter(|p| !p.is_empty())
+            .or_else(|| secrets::load(&conn, WEBDAV_PASSWORD))
+            .unwrap_or_default();
+        (username, password)
+    };
+    let file_url = sync_file_url(&url);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(&file_url)
+        .basic_auth(&username, Some(&password))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the WebDAV server: {}", e))?;
+    let remote = if response.status() == StatusCode::NOT_FOUND {
+        None
+    } else {
+        check_status(response.status())?;
+        let raw = response.text().await.map_err(|e| e.to_string())?;
+        Some(export::parse_document(&raw)?)
+    };
+
+    let mut result = SyncResult {
+        remote_existed: remote.is_some(),
+        ..Default::default()
+    };
+    let body = merge(&app, remote.as_ref(), &mut result)?;
+
+    let response = client
+        .put(&file_url)
+        .basic_auth(&username, Some(&password))
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload to the WebDAV server: {}", e))?;
+    check_status(response.status())?;
+
+    // Only remember credentials the server accepted
+    let conn = db::connect(&app)?;
+    secrets::store(&conn, WEBDAV_USERNAME, &username)?;
+    secrets::store(&conn, WEBDAV_PASSWORD, &password)?;
+    settings::write(&conn, "sync", &json!({ "webdavUrl": url })).map_err(|e| e.to_string())?;
+    Ok(result)
+}
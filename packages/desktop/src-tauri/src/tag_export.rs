@@ -0,0 +1,110 @@
+use crate::markdown::{self, checklist_line};
+use crate::tasks::{Task, TASK_COLUMNS};
+use crate::{dates, db, export};
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::path::Path;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+const CSV_HEADER: &[&str] = &["Title", "List", "Due Date", "Priority", "Tags", "Completed", "Notes"];
+
+// Live tasks carrying `tag` (compared case-insensitively), grouped by list
+pub fn tagged(conn: &Connection, tag: &str) -> rusqlite::Result<Vec<Task>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"SELECT {} FROM tasks
+           WHERE tags IS NOT NULL AND tags != '[]' AND deleted_at IS NULL AND list_name != 'Trash'
+           ORDER BY list_name, "order""#,
+        TASK_COLUMNS
+    ))?;
+    let key = tag.trim().to_lowercase();
+    let rows = stmt.query_map([], Task::from_row)?;
+    let mut found = Vec::new();
+    for task in rows {
+        let task = task?;
+        if task.tags.iter().any(|t| t.trim().to_lowercase() == key) {
+            found.push(task);
+        }
+    }
+    Ok(found)
+}
+
+fn render_csv(tasks: &[Task]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADER).map_err(|e| e.to_string())?;
+    for task in tasks {
+        let due = task
+            .due_date
+            .and_then(dates::to_local_naive)
+            .map(|due| due.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let priority = task.priority.map(|p| p.to_string()).unwrap_or_default();
+        let tags = task.tags.join(", ");
+        writer
+            .write_record([
+                task.title.as_str(),
+                task.list_name.as_str(),
+                due.as_str(),
+                priority.as_str(),
+                tags.as_str(),
+                if task.completed { "yes" } else { "no" },
+                task.content.as_deref().unwrap_or(""),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+// A checklist per list, like `markdown::render_list`
+fn render_markdown(tag: &str, tasks: &[Task]) -> String {
+    let mut lines = vec![format!("# {}", markdown::escape(&format!("#{}", tag.trim())))];
+    let mut current_list: Option<&str> = None;
+    for task in tasks {
+        if current_list != Some(task.list_name.as_str()) {
+            current_list = Some(&task.list_name);
+            lines.push(String::new());
+            lines.push(format!("## {}", markdown::escape(&task.list_name)));
+            lines.push(String::new());
+        }
+        let extras: Vec<String> = markdown::priority_emoji(task.priority)
+            .map(String::from)
+            .into_iter()
+            .chain(markdown::due_label(task.due_date))
+            .collect();
+        lines.push(checklist_line("", task.completed, &task.title, &extras));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+pub fn render(tag: &str, tasks: &[Task], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(tasks).map_err(|e| e.to_string()),
+        ExportFormat::Csv => render_csv(tasks),
+        ExportFormat::Markdown => Ok(render_markdown(tag, tasks)),
+    }
+}
+
+// Write every task tagged `tag` to `path`, returns how many. Each record carries its list
+// name. No matching task still writes a valid (empty) file.
+#[tauri::command]
+pub async fn export_by_tag(app: AppHandle, tag: String, format: ExportFormat, path: String) -> Result<usize, String> {
+    if tag.trim().is_empty() {
+        return Err("Tag names can't be empty".to_string());
+    }
+    let tasks = {
+        let conn = db::connect(&app)?;
+        tagged(&conn, &tag).map_err(|e| e.to_string())?
+    };
+    let contents = render(&tag, &tasks, format)?;
+    export::write_file_atomic(Path::new(&path), &contents)?;
+    Ok(tasks.len())
+}
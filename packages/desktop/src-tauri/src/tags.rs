@@ -0,0 +1,226 @@
+use crate::tasks;
+use crate::{dates, db, journal};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    // Number of tasks carrying the tag
+    pub count: usize,
+}
+
+fn tagged_tasks(conn: &Connection, live_only: bool) -> rusqlite::Result<Vec<(String, Vec<String>)>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, tags FROM tasks WHERE tags IS NOT NULL AND tags != '[]' {}",
+        if live_only { "AND deleted_at IS NULL AND list_name != 'Trash'" } else { "" }
+    ))?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, tasks::parse_tags(row.get(1)?))))?;
+    rows.collect()
+}
+
+// Tags compare case-insensitively; each one is reported under its most used spelling
+pub fn count_tags(conn: &Connection) -> rusqlite::Result<Vec<TagCount>> {
+    let mut counts: HashMap<String, (usize, HashMap<String, usize>)> = HashMap::new();
+    for (_, tags) in tagged_tasks(conn, true)? {
+        for tag in tasks::normalize_tags(tags) {
+            let (count, spellings) = counts.entry(tag.to_lowercase()).or_default();
+            *count += 1;
+            *spellings.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut result: Vec<TagCount> = counts
+        .into_values()
+        .map(|(count, spellings)| {
+            let tag = spellings
+                .into_iter()
+                .max_by(|(a, a_uses), (b, b_uses)| a_uses.cmp(b_uses).then_with(|| b.cmp(a)))
+                .map(|(tag, _)| tag)
+                .unwrap_or_default();
+            TagCount { tag, count }
+        })
+        .collect();
+    result.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    Ok(result)
+}
+
+// Rewrite `old` (matched case-insensitively) to `new` on every task, returns how many
+// tasks changed. Tasks whose tags can't be parsed are left alone.
+pub fn rename(conn: &mut Connection, old: &str, new: &str) -> Result<usize, String> {
+    let old = old.trim().to_lowercase();
+    let new = new.trim();
+    if old.is_empty() || new.is_empty() {
+        return Err("Tag names can't be empty".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "rename_tag").map_err(|e| e.to_string())?;
+    let now = dates::now_ms();
+    let mut changed = 0;
+    for (id, tags) in tagged_tasks(&tx, false).map_err(|e| e.to_string())? {
+        if !tags.iter().any(|t| t.trim().to_lowercase() == old) {
+            continue;
+        }
+        let renamed: Vec<String> = tags
+            .into_iter()
+            .map(|t| if t.trim().to_lowercase() == old { new.to_string() } else { t })
+            .collect();
+        let renamed = serde_json::to_string(&tasks::normalize_tags(renamed)).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE tasks SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+            params![renamed, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+        changed += 1;
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(changed)
+}
+
+// Add `tag` to (or remove it from) each task, returns how many tasks actually changed.
+// Matching is case-insensitive like everywhere else, so adding "Work" to a task tagged
+// "work" is a no-op. Unknown ids are skipped.
+pub fn retag(conn: &mut Connection, task_ids: &[String], tag: &str, add: bool) -> Result<usize, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("Tag names can't be empty".to_string());
+    }
+    let key = tag.to_lowercase();
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, if add { "add_tag" } else { "remove_tag" }).map_err(|e| e.to_string())?;
+    let now = dates::now_ms();
+    let mut seen = HashSet::new();
+    let mut changed = 0;
+    for id in task_ids {
+        if !seen.insert(id) {
+            continue;
+        }
+        let tags: Option<Option<String>> = tx
+            .query_row("SELECT tags FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(tags) = tags else {
+            continue;
+        };
+        let tags = tasks::normalize_tags(tasks::parse_tags(tags));
+        let has_tag = tags.iter().any(|t| t.to_lowercase() == key);
+        let updated: Vec<String> = match (add, has_tag) {
+            (true, false) => tags.into_iter().chain([tag.to_string()]).collect(),
+            (false, true) => tags.into_iter().filter(|t| t.to_lowercase() != key).collect(),
+            _ => continue,
+        };
+        let updated = if updated.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&updated).map_err(|e| e.to_string())?)
+        };
+        tx.execute(
+            "UPDATE tasks SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+            params![updated, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+        changed += 1;
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(changed)
+}
+
+// What a raw `tags` value should be: `None` when it is fine as is. Arrays are trimmed and
+// deduplicated (numbers and booleans kept as text, anything else dropped), a bare string,
+// quoted or not, becomes a one-tag array and any other value is reset to an empty array.
+fn sanitized(raw: &str) -> Option<Vec<String>> {
+    let tags = match serde_json::from_str::<Value>(raw) {
+        Ok(Value::Array(values)) => {
+            let strict: Option<Vec<String>> = values.iter().map(|v| v.as_str().map(String::from)).collect();
+            let tags: Vec<String> = values
+                .into_iter()
+                .filter_map(|value| match value {
+                    Value::String(tag) => Some(tag),
+                    Value::Number(n) => Some(n.to_string()),
+                    Value::Bool(b) => Some(b.to_string()),
+                    _ => None,
+                })
+                .collect();
+            let tags = tasks::normalize_tags(tags);
+            if strict.as_ref() == Some(&tags) {
+                return None;
+            }
+            tags
+        }
+        Ok(Value::String(tag)) => tasks::normalize_tags(vec![tag]),
+        Ok(_) => Vec::new(),
+        Err(_) => tasks::normalize_tags(vec![raw.to_string()]),
+    };
+    Some(tags)
+}
+
+// Repair the `tags` column of every task (trashed and deleted ones too), returns how many
+// rows were rewritten. Rows without tags are left alone.
+pub fn sanitize(conn: &mut Connection) -> Result<usize, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "sanitize_tags").map_err(|e| e.to_string())?;
+    let rows: Vec<(String, String)> = {
+        let mut stmt = tx
+            .prepare("SELECT id, tags FROM tasks WHERE tags IS NOT NULL")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+    };
+    let now = dates::now_ms();
+    let mut fixed = 0;
+    for (id, raw) in rows {
+        let Some(tags) = sanitized(&raw) else {
+            continue;
+        };
+        let tags = serde_json::to_string(&tags).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE tasks SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+            params![tags, now, id],
+        )
+        .map_err(|e| e.to_string())?;
+        fixed += 1;
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(fixed)
+}
+
+#[tauri::command]
+pub async fn list_tags(app: AppHandle) -> Result<Vec<TagCount>, String> {
+    let conn = db::connect(&app)?;
+    count_tags(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_tag(app: AppHandle, old: String, new: String) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    rename(&mut conn, &old, &new)
+}
+
+#[tauri::command]
+pub async fn add_tag_to_tasks(app: AppHandle, task_ids: Vec<String>, tag: String) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    retag(&mut conn, &task_ids, &tag, true)
+}
+
+#[tauri::command]
+pub async fn remove_tag_from_tasks(app: AppHandle, task_ids: Vec<String>, tag: String) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    retag(&mut conn, &task_ids, &tag, false)
+}
+
+#[tauri::command]
+pub async fn sanitize_tags(app: AppHandle) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    sanitize(&mut conn)
+}
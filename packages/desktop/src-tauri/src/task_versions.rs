@@ -0,0 +1,103 @@
+use crate::reminders::ReminderScheduler;
+use crate::tasks::{self, Task};
+use crate::{dates, db, journal, tray};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+// The values a task had before an edit, recorded by the `task_versions_update` trigger
+// (migration 25)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskVersion {
+    pub id: i64,
+    pub task_id: String,
+    pub title: String,
+    pub content: Option<String>,
+    pub due_date: Option<i64>,
+    // When these values were replaced
+    pub created_at: i64,
+}
+
+impl TaskVersion {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            title: row.get("title")?,
+            content: row.get("content")?,
+            due_date: row.get("due_date")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+// Newest first
+pub fn for_task(conn: &Connection, task_id: &str) -> rusqlite::Result<Vec<TaskVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, task_id, title, content, due_date, created_at FROM task_versions
+         WHERE task_id = ?1 ORDER BY id DESC",
+    )?;
+    let rows = stmt.query_map([task_id], TaskVersion::from_row)?;
+    rows.collect()
+}
+
+// Put back the title, content and due date of a version. The values replaced are recorded
+// as a version of their own by the trigger, so a revert can be reverted too.
+pub fn revert(conn: &mut Connection, task_id: &str, version_id: i64) -> Result<Task, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let version = tx
+        .query_row(
+            "SELECT id, task_id, title, content, due_date, created_at FROM task_versions WHERE id = ?1 AND task_id = ?2",
+            params![version_id, task_id],
+            TaskVersion::from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Version {} of task {} not found", version_id, task_id))?;
+    let completed: bool = tx
+        .query_row("SELECT completed FROM tasks WHERE id = ?1 AND deleted_at IS NULL", [task_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", task_id))?;
+
+    journal::begin(&tx, "revert_task").map_err(|e| e.to_string())?;
+    let now = dates::now_ms();
+    tx.execute(
+        "UPDATE tasks SET title = ?1, content = ?2, due_date = ?3, group_category = ?4, updated_at = ?5 WHERE id = ?6",
+        params![
+            version.title,
+            version.content,
+            version.due_date,
+            dates::group_category(version.due_date, completed, now),
+            now,
+            task_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    let task = tasks::get(&tx, task_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Task {} not found", task_id))?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn get_task_versions(app: AppHandle, task_id: String) -> Result<Vec<TaskVersion>, String> {
+    let conn = db::connect(&app)?;
+    for_task(&conn, &task_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn revert_task(app: AppHandle, task_id: String, version_id: i64) -> Result<Task, String> {
+    let task = {
+        let mut conn = db::connect(&app)?;
+        revert(&mut conn, &task_id, version_id)?
+    };
+    app.state::<ReminderScheduler>().wake();
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(task)
+}
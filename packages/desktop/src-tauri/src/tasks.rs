@@ -0,0 +1,251 @@
+use crate::{dates, sort_keys};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+use serde::{Deserialize, Serialize};
+
+pub const TASK_COLUMNS: &str = r#"id, title, completed, completed_at, complete_percentage, due_date, list_id, list_name,
+    content, "order", created_at, updated_at, tags, priority, group_category"#;
+
+pub const SUBTASK_COLUMNS: &str =
+    r#"id, parent_id, title, completed, completed_at, due_date, "order", created_at, updated_at"#;
+
+// Mirrors the frontend `Task` type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: String,
+    pub title: String,
+    pub completed: bool,
+    pub completed_at: Option<i64>,
+    pub complete_percentage: Option<i64>,
+    pub due_date: Option<i64>,
+    pub list_id: Option<String>,
+    pub list_name: String,
+    pub content: Option<String>,
+    pub order: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub tags: Vec<String>,
+    pub priority: Option<i64>,
+    pub group_category: String,
+}
+
+impl Task {
+    // Expects the columns of `TASK_COLUMNS`
+    pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            title: row.get("title")?,
+            completed: row.get::<_, i64>("completed")? != 0,
+            completed_at: row.get("completed_at")?,
+            complete_percentage: row.get("complete_percentage")?,
+            due_date: row.get("due_date")?,
+            list_id: row.get("list_id")?,
+            list_name: row.get("list_name")?,
+            content: row.get("content")?,
+            order: row.get("order")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+            tags: parse_tags(row.get("tags")?),
+            priority: row.get("priority")?,
+            group_category: row.get("group_category")?,
+        })
+    }
+}
+
+// Mirrors the frontend `Subtask` type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subtask {
+    pub id: String,
+    pub parent_id: String,
+    pub title: String,
+    pub completed: bool,
+    pub completed_at: Option<i64>,
+    pub due_date: Option<i64>,
+    pub order: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Subtask {
+    // Expects the columns of `SUBTASK_COLUMNS`
+    pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            parent_id: row.get("parent_id")?,
+            title: row.get("title")?,
+            completed: row.get::<_, i64>("completed")? != 0,
+            completed_at: row.get("completed_at")?,
+            due_date: row.get("due_date")?,
+            order: row.get("order")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+// `tags` is a JSON array in a TEXT column; anything unreadable counts as no tags
+pub fn parse_tags(raw: Option<String>) -> Vec<String> {
+    raw.and_then(|raw| serde_json::from_str::<Vec<String>>(&raw).ok())
+        .unwrap_or_default()
+}
+
+// Trim tags, drop empty ones and collapse duplicates that only differ in case, keeping
+// the first spelling
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !out.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            out.push(tag.to_string());
+        }
+    }
+    out
+}
+
+// Next free "order" value at the end of a list (`None` is the list-less bucket).
+pub fn next_order(conn: &Connection, list_id: Option<&str>) -> rusqlite::Result<i64> {
+    conn.query_row(
+        r#"SELECT COALESCE(MAX("order"), 0) + 1 FROM tasks WHERE list_id IS ?1"#,
+        [list_id],
+        |row| row.get(0),
+    )
+}
+
+// Name of a live list, `None` if it doesn't exist or is in the trash
+pub fn list_name(conn: &Connection, list_id: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT name FROM lists WHERE id = ?1 AND deleted_at IS NULL",
+        [list_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+// What native commands fill in when they create a task, everything else gets the defaults
+#[derive(Debug, Default)]
+pub struct NewTask {
+    pub title: String,
+    pub content: Option<String>,
+    pub due_date: Option<i64>,
+    pub priority: Option<i64>,
+    pub tags: Vec<String>,
+    // Set for tasks that are imported as already done
+    pub completed_at: Option<i64>,
+    pub reminder_offset_minutes: Option<i64>,
+}
+
+// Append a task to the end of a list, returns its id
+pub fn insert(conn: &Connection, list_id: &str, list_name: &str, task: &NewTask) -> rusqlite::Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+    let order = next_order(conn, Some(list_id))?;
+    let sort_key = sort_keys::next_key(conn, list_id)?;
+    let tags = if task.tags.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&task.tags).unwrap_or_default())
+    };
+    let completed = task.completed_at.is_some();
+    conn.execute(
+        r#"
+        INSERT INTO tasks (id, title, completed, completed_at, due_date, list_id, list_name, content, "order",
+                           created_at, updated_at, tags, priority, group_category, reminder_offset_minutes, sort_key)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10, ?11, ?12, ?13, ?14, ?15)
+        "#,
+        params![
+            id,
+            task.title,
+            completed,
+            task.completed_at,
+            task.due_date,
+            list_id,
+            list_name,
+            task.content,
+            order,
+            now,
+            tags,
+            task.priority,
+            dates::group_category(task.due_date, completed, now),
+            task.reminder_offset_minutes,
+            sort_key,
+        ],
+    )?;
+    Ok(id)
+}
+
+#[derive(Debug, Default)]
+pub struct NewSubtask {
+    pub title: String,
+    pub due_date: Option<i64>,
+    pub completed_at: Option<i64>,
+}
+
+// Append a subtask to a task, returns its id
+pub fn insert_subtask(conn: &Connection, parent_id: &str, subtask: &NewSubtask) -> rusqlite::Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+    let order: i64 = conn.query_row(
+        r#"SELECT COALESCE(MAX("order"), 0) + 1 FROM subtasks WHERE parent_id = ?1"#,
+        [parent_id],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        r#"
+        INSERT INTO subtasks (id, parent_id, title, completed, completed_at, due_date, "order", created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+        "#,
+        params![
+            id,
+            parent_id,
+            subtask.title,
+            subtask.completed_at.is_some(),
+            subtask.completed_at,
+            subtask.due_date,
+            order,
+            now
+        ],
+    )?;
+    Ok(id)
+}
+
+// Create a list at the end of the sidebar like the frontend does (its order is the
+// creation time), returns its id
+pub fn insert_list(conn: &Connection, name: &str) -> rusqlite::Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+    conn.execute(
+        r#"INSERT INTO lists (id, name, icon, "order", created_at, updated_at) VALUES (?1, ?2, 'list', ?3, ?3, ?3)"#,
+        params![id, name, now],
+    )?;
+    Ok(id)
+}
+
+// Read a priority written as 1-3 or as a word, anything else means no priority
+pub fn parse_priority(text: &str) -> Option<i64> {
+    match text.trim().to_lowercase().as_str() {
+        "1" | "high" | "高" | "!!!" => Some(1),
+        "2" | "medium" | "med" | "中" | "!!" => Some(2),
+        "3" | "low" | "低" | "!" => Some(3),
+        _ => None,
+    }
+}
+
+pub fn get(conn: &Connection, id: &str) -> rusqlite::Result<Option<Task>> {
+    conn.query_row(
+        &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+        [id],
+        Task::from_row,
+    )
+    .optional()
+}
+
+pub fn get_subtask(conn: &Connection, id: &str) -> rusqlite::Result<Option<Subtask>> {
+    conn.query_row(
+        &format!("SELECT {} FROM subtasks WHERE id = ?1", SUBTASK_COLUMNS),
+        [id],
+        Subtask::from_row,
+    )
+    .optional()
+}
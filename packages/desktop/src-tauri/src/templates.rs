@@ -0,0 +1,296 @@
+use crate::reminders::ReminderScheduler;
+use crate::tasks::{self, NewSubtask, NewTask};
+use crate::{dates, db, journal, list_defaults, tray};
+use chrono::{NaiveTime, TimeDelta};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+// A due date relative to the day the template is used: `days` after it, at `minutes` past
+// local midnight (`None` for an all-day date)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DueOffset {
+    pub days: i64,
+    pub minutes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSubtask {
+    pub title: String,
+    pub due: Option<DueOffset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateTask {
+    pub title: String,
+    pub content: Option<String>,
+    pub priority: Option<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub reminder_offset_minutes: Option<i64>,
+    pub due: Option<DueOffset>,
+    #[serde(default)]
+    pub subtasks: Vec<TemplateSubtask>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    // Name, icon and color of the list a new list is created with
+    pub list_name: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub tasks: Vec<TemplateTask>,
+    pub created_at: i64,
+}
+
+impl Template {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        let tasks: String = row.get("tasks")?;
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            list_name: row.get("list_name")?,
+            icon: row.get("icon")?,
+            color: row.get("color")?,
+            // Unreadable JSON reads as an empty template rather than hiding the row
+            tasks: serde_json::from_str(&tasks).unwrap_or_default(),
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+const TEMPLATE_COLUMNS: &str = "id, name, list_name, icon, color, tasks, created_at";
+
+fn offset_of(due_date: Option<i64>, anchor: chrono::NaiveDate) -> Option<DueOffset> {
+    let due = dates::to_local_naive(due_date?)?;
+    let minutes = (due.time() != NaiveTime::MIN).then(|| (due.time() - NaiveTime::MIN).num_minutes());
+    Some(DueOffset {
+        days: (due.date() - anchor).num_days(),
+        minutes,
+    })
+}
+
+fn due_from(offset: &Option<DueOffset>, today: chrono::NaiveDate) -> Option<i64> {
+    let offset = offset.as_ref()?;
+    let day = today.checked_add_signed(TimeDelta::days(offset.days))?;
+    let time = NaiveTime::MIN + TimeDelta::minutes(offset.minutes.unwrap_or(0).clamp(0, 24 * 60 - 1));
+    Some(dates::from_local_naive(day.and_time(time)))
+}
+
+// Store a live list with its tasks and subtasks (trashed ones left out) as a template,
+// returns its id. Completion isn't kept, due dates become offsets from the earliest due
+// date in the list, so the first task falls on the day the template is used.
+pub fn save(conn: &Connection, list_id: &str, name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A template needs a name".to_string());
+    }
+    let (list_name, icon, color): (String, Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT name, icon, color FROM lists WHERE id = ?1 AND deleted_at IS NULL",
+            [list_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+
+    let rows: Vec<(String, Option<i64>, TemplateTask)> = {
+        let mut stmt = conn
+            .prepare(
+                r#"SELECT id, title, content, priority, tags, reminder_offset_minutes, due_date FROM tasks
+                   WHERE list_id = ?1 AND deleted_at IS NULL AND list_name != 'Trash'
+                   ORDER BY "order""#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([list_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<i64>>(6)?,
+                    TemplateTask {
+                        title: row.get(1)?,
+                        content: row.get(2)?,
+                        priority: row.get(3)?,
+                        tags: tasks::normalize_tags(tasks::parse_tags(row.get(4)?)),
+                        reminder_offset_minutes: row.get(5)?,
+                        due: None,
+                        subtasks: Vec::new(),
+                    },
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+    };
+
+    let mut items = Vec::with_capacity(rows.len());
+    let mut subtask_dues = Vec::new();
+    for (id, due_date, mut task) in rows {
+        let mut stmt = conn
+            .prepare(r#"SELECT title, due_date FROM subtasks WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY "order""#)
+            .map_err(|e| e.to_string())?;
+        let subtasks = stmt
+            .query_map([&id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?;
+        task.subtasks = subtasks
+            .iter()
+            .map(|(title, _)| TemplateSubtask {
+                title: title.clone(),
+                due: None,
+            })
+            .collect();
+        subtask_dues.push(subtasks.into_iter().map(|(_, due)| due).collect::<Vec<_>>());
+        items.push((due_date, task));
+    }
+
+    let anchor = items
+        .iter()
+        .filter_map(|(due, _)| *due)
+        .chain(subtask_dues.iter().flatten().filter_map(|due| *due))
+        .min()
+        .and_then(dates::local_date);
+    let template_tasks: Vec<TemplateTask> = items
+        .into_iter()
+        .zip(subtask_dues)
+        .map(|((due_date, mut task), dues)| {
+            if let Some(anchor) = anchor {
+                task.due = offset_of(due_date, anchor);
+                for (subtask, due) in task.subtasks.iter_mut().zip(dues) {
+                    subtask.due = offset_of(due, anchor);
+                }
+            }
+            task
+        })
+        .collect();
+
+    let id = Uuid::new_v4().to_string();
+    let now = dates::now_ms();
+    conn.execute(
+        "INSERT INTO templates (id, name, list_name, icon, color, tasks, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        params![
+            id,
+            name,
+            list_name,
+            icon,
+            color,
+            serde_json::to_string(&template_tasks).map_err(|e| e.to_string())?,
+            now
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+pub fn all(conn: &Connection) -> rusqlite::Result<Vec<Template>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM templates ORDER BY name, created_at", TEMPLATE_COLUMNS))?;
+    let rows = stmt.query_map([], Template::from_row)?;
+    rows.collect()
+}
+
+// Create the template's tasks afresh, in `target_list_id` or in a new list named after the
+// one the template was saved from. Due offsets count from today. Returns the list's id.
+pub fn instantiate(conn: &mut Connection, template_id: &str, target_list_id: Option<&str>) -> Result<String, String> {
+    let template = conn
+        .query_row(
+            &format!("SELECT {} FROM templates WHERE id = ?1", TEMPLATE_COLUMNS),
+            [template_id],
+            Template::from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Template {} not found", template_id))?;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "instantiate_template").map_err(|e| e.to_string())?;
+    let list_id = match target_list_id {
+        Some(id) => id.to_string(),
+        None => {
+            let id = tasks::insert_list(&tx, &template.list_name).map_err(|e| e.to_string())?;
+            tx.execute(
+                "UPDATE lists SET icon = COALESCE(?1, icon), color = ?2 WHERE id = ?3",
+                params![template.icon, template.color, id],
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+    };
+    let list_name = tasks::list_name(&tx, &list_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("List {} not found", list_id))?;
+    let defaults = list_defaults::load(&tx, &list_id)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+
+    let today = dates::local_date(dates::now_ms()).ok_or("Invalid local time")?;
+    for item in &template.tasks {
+        let mut task = NewTask {
+            title: item.title.clone(),
+            content: item.content.clone(),
+            due_date: due_from(&item.due, today),
+            priority: item.priority,
+            tags: item.tags.clone(),
+            reminder_offset_minutes: item.reminder_offset_minutes,
+            ..Default::default()
+        };
+        defaults.apply(&mut task);
+        let task_id = tasks::insert(&tx, &list_id, &list_name, &task).map_err(|e| e.to_string())?;
+        for subtask in &item.subtasks {
+            let subtask = NewSubtask {
+                title: subtask.title.clone(),
+                due_date: due_from(&subtask.due, today),
+                completed_at: None,
+            };
+            tasks::insert_subtask(&tx, &task_id, &subtask).map_err(|e| e.to_string())?;
+        }
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(list_id)
+}
+
+#[tauri::command]
+pub async fn save_as_template(app: AppHandle, list_id: String, name: String) -> Result<String, String> {
+    let conn = db::connect(&app)?;
+    save(&conn, &list_id, &name)
+}
+
+#[tauri::command]
+pub async fn get_templates(app: AppHandle) -> Result<Vec<Template>, String> {
+    let conn = db::connect(&app)?;
+    all(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_template(app: AppHandle, template_id: String) -> Result<bool, String> {
+    let conn = db::connect(&app)?;
+    conn.execute("DELETE FROM templates WHERE id = ?1", [&template_id])
+        .map(|removed| removed > 0)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn instantiate_template(
+    app: AppHandle,
+    template_id: String,
+    target_list_id: Option<String>,
+) -> Result<String, String> {
+    let list_id = {
+        let mut conn = db::connect(&app)?;
+        instantiate(&mut conn, &template_id, target_list_id.as_deref())?
+    };
+    app.state::<ReminderScheduler>().wake();
+    if let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(list_id)
+}
@@ -0,0 +1,503 @@
+use crate::export::{self, INBOX_LIST_ID};
+use crate::tasks::{self, NewSubtask, NewTask};
+use crate::{dates, db, journal};
+use chrono::DateTime;
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+// Tasks marked "Won't do" are imported as completed and tagged with this
+const ABANDONED_TAG: &str = "abandoned";
+// Checklist items in the CSV export's Content column, open and done
+const OPEN_ITEM: char = '▫';
+const DONE_ITEM: char = '▪';
+
+// Columns of the CSV backup that are read, the rest is reported as unmapped
+const CSV_COLUMNS: &[&str] = &[
+    "Folder Name",
+    "List Name",
+    "Title",
+    "Kind",
+    "Tags",
+    "Content",
+    "Is Check list",
+    "Due Date",
+    "Priority",
+    "Status",
+    "Completed Time",
+    "Order",
+    "taskId",
+    "parentId",
+];
+// Same for the fields of a task in a JSON export
+const JSON_FIELDS: &[&str] = &[
+    "id",
+    "projectId",
+    "title",
+    "content",
+    "desc",
+    "kind",
+    "tags",
+    "items",
+    "dueDate",
+    "priority",
+    "status",
+    "completedTime",
+    "sortOrder",
+    "parentId",
+    "childIds",
+    "etag",
+    "modifiedTime",
+    "createdTime",
+];
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    // Lists created for TickTick lists without a list of the same name
+    pub lists: usize,
+    pub tasks: usize,
+    // Sub-tasks and checklist items
+    pub subtasks: usize,
+    pub invalid_dates: usize,
+    // Items that were left out, with the reason
+    pub unmapped: Vec<String>,
+    // Columns (or JSON fields) that had values but have no counterpart here, e.g. "Repeat"
+    pub unmapped_columns: Vec<String>,
+}
+
+struct Project {
+    key: String,
+    name: String,
+    inbox: bool,
+}
+
+struct ChecklistItem {
+    title: String,
+    completed_at: Option<i64>,
+}
+
+struct Item {
+    key: String,
+    project: String,
+    parent: Option<String>,
+    title: String,
+    content: Option<String>,
+    priority: Option<i64>,
+    tags: Vec<String>,
+    due_date: Option<i64>,
+    completed_at: Option<i64>,
+    order: i64,
+    checklist: Vec<ChecklistItem>,
+}
+
+#[derive(Default)]
+struct Backup {
+    projects: Vec<Project>,
+    items: Vec<Item>,
+    invalid_dates: usize,
+    unmapped_columns: BTreeSet<String>,
+}
+
+impl Backup {
+    // Lists are only known by name in the CSV backup
+    fn project_named(&mut self, name: &str) -> String {
+        let key = format!("name:{}", name);
+        if !self.projects.iter().any(|p| p.key == key) {
+            self.projects.push(Project {
+                key: key.clone(),
+                name: name.to_string(),
+                inbox: name.eq_ignore_ascii_case("inbox"),
+            });
+        }
+        key
+    }
+}
+
+// TickTick counts 0 (none), 1 (low), 3 (medium) and 5 (high)
+fn priority(value: i64) -> Option<i64> {
+    match value {
+        5 => Some(1),
+        3 => Some(2),
+        1 => Some(3),
+        _ => None,
+    }
+}
+
+// Exports write offsets without a colon ("2024-01-05T16:00:00+0000")
+fn parse_date(text: &str) -> Option<i64> {
+    let text = text.trim();
+    dates::parse_due_date(text).or_else(|| {
+        ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%dT%H:%M:%S%z"]
+            .iter()
+            .find_map(|format| DateTime::parse_from_str(text, format).ok())
+            .map(|dt| dt.timestamp_millis())
+    })
+}
+
+// The task's status: 0 open, 1 or 2 completed, -1 won't do. Returns when it was completed
+// and whether it was abandoned.
+fn completion(status: i64, completed_time: Option<&str>) -> (Option<i64>, bool) {
+    if status == 0 {
+        return (None, false);
+    }
+    let at = completed_time.and_then(parse_date).unwrap_or_else(dates::now_ms);
+    (Some(at), status < 0)
+}
+
+fn split_tags(cell: &str) -> Vec<String> {
+    cell.split(',').map(|tag| tag.trim().trim_start_matches('#').to_string()).collect()
+}
+
+// A checklist task's Content holds its items, one per line, marked open or done. Lines
+// without a marker stay in the notes.
+fn split_checklist(content: &str) -> (Option<String>, Vec<ChecklistItem>) {
+    let mut notes = Vec::new();
+    let mut items = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let (done, title) = if let Some(title) = trimmed.strip_prefix(OPEN_ITEM) {
+            (false, title)
+        } else if let Some(title) = trimmed.strip_prefix(DONE_ITEM) {
+            (true, title)
+        } else {
+            notes.push(line);
+            continue;
+        };
+        let title = title.trim();
+        if !title.is_empty() {
+            items.push(ChecklistItem {
+                title: title.to_string(),
+                completed_at: done.then(dates::now_ms),
+            });
+        }
+    }
+    let notes = notes.join("\n").trim().to_string();
+    (Some(notes).filter(|n| !n.is_empty()), items)
+}
+
+// The CSV backup starts with a few lines of metadata (export date, version, the meaning of
+// the status values) before the header row
+fn read_csv(path: &Path, backup: &mut Backup) -> Result<(), String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+    let mut records = reader.records();
+    let mut headers: Option<Vec<String>> = None;
+    for record in records.by_ref() {
+        let record = record.map_err(|e| e.to_string())?;
+        let row: Vec<String> = record.iter().map(|h| h.trim_start_matches('\u{feff}').trim().to_string()).collect();
+        if row.iter().any(|h| h == "Title") && row.iter().any(|h| h == "List Name") {
+            headers = Some(row);
+            break;
+        }
+    }
+    let headers = headers.ok_or_else(|| format!("'{}' is not a TickTick backup", path.display()))?;
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let read: Vec<Option<usize>> = CSV_COLUMNS.iter().map(|name| column(name)).collect();
+    let col = |name: &str| CSV_COLUMNS.iter().position(|c| *c == name).and_then(|i| read[i]);
+
+    for (index, record) in records.enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        let cell = |name: &str| col(name).and_then(|c| record.get(c)).map(str::trim).unwrap_or("");
+        for (i, header) in headers.iter().enumerate() {
+            let known = CSV_COLUMNS.iter().any(|c| c.eq_ignore_ascii_case(header));
+            if !known && !header.is_empty() && record.get(i).is_some_and(|v| !v.trim().is_empty()) {
+                backup.unmapped_columns.insert(header.clone());
+            }
+        }
+
+        let list = match cell("List Name") {
+            "" => "Inbox",
+            name => name,
+        };
+        let project = backup.project_named(list);
+        let due_text = cell("Due Date");
+        let due_date = parse_date(due_text);
+        if due_date.is_none() && !due_text.is_empty() {
+            backup.invalid_dates += 1;
+        }
+        let (completed_at, abandoned) = completion(
+            cell("Status").parse().unwrap_or(0),
+            Some(cell("Completed Time")).filter(|t| !t.is_empty()),
+        );
+        let mut tags = split_tags(cell("Tags"));
+        if abandoned {
+            tags.push(ABANDONED_TAG.to_string());
+        }
+        let is_checklist = cell("Is Check list").eq_ignore_ascii_case("y") || cell("Kind").eq_ignore_ascii_case("checklist");
+        let (content, checklist) = if is_checklist {
+            split_checklist(cell("Content"))
+        } else {
+            (Some(cell("Content").to_string()).filter(|c| !c.is_empty()), Vec::new())
+        };
+
+        backup.items.push(Item {
+            key: Some(cell("taskId").to_string())
+                .filter(|k| !k.is_empty())
+                .unwrap_or_else(|| format!("row:{}", index)),
+            project,
+            parent: Some(cell("parentId").to_string()).filter(|p| !p.is_empty()),
+            title: cell("Title").to_string(),
+            content,
+            priority: cell("Priority").parse().ok().and_then(priority),
+            tags: tasks::normalize_tags(tags),
+            due_date,
+            completed_at,
+            order: cell("Order").parse().unwrap_or(index as i64),
+            checklist,
+        });
+    }
+    Ok(())
+}
+
+fn text(value: &Value, field: &str) -> Option<String> {
+    value.get(field).and_then(Value::as_str).map(str::trim).filter(|s| !s.is_empty()).map(String::from)
+}
+
+// An Open API dump (a task array, or `projects` + `tasks`) or a full sync payload
+// (`projectProfiles` + `syncTaskBean.update`)
+fn read_json(raw: &str, backup: &mut Backup) -> Result<(), String> {
+    let root: Value = serde_json::from_str(raw).map_err(|e| format!("Not a TickTick JSON export: {}", e))?;
+    let projects = root.get("projects").or_else(|| root.get("projectProfiles")).and_then(Value::as_array);
+    for project in projects.into_iter().flatten() {
+        let (Some(key), Some(name)) = (text(project, "id"), text(project, "name")) else {
+            continue;
+        };
+        backup.projects.push(Project {
+            inbox: key.starts_with("inbox"),
+            key,
+            name,
+        });
+    }
+
+    let items = match &root {
+        Value::Array(items) => Some(items),
+        _ => root
+            .get("tasks")
+            .or_else(|| root.get("syncTaskBean").and_then(|bean| bean.get("update")))
+            .and_then(Value::as_array),
+    };
+    for (index, item) in items.into_iter().flatten().enumerate() {
+        let Some(key) = text(item, "id") else {
+            continue;
+        };
+        if let Value::Object(fields) = item {
+            for (field, value) in fields {
+                let empty = value.is_null() || value.as_str().is_some_and(str::is_empty) || value.as_array().is_some_and(Vec::is_empty);
+                if !empty && !JSON_FIELDS.contains(&field.as_str()) {
+                    backup.unmapped_columns.insert(field.clone());
+                }
+            }
+        }
+
+        // Tasks of the inbox point at a project id that isn't listed
+        let project = text(item, "projectId").unwrap_or_default();
+        if project.starts_with("inbox") && !backup.projects.iter().any(|p| p.key == project) {
+            backup.projects.push(Project {
+                key: project.clone(),
+                name: "Inbox".to_string(),
+                inbox: true,
+            });
+        }
+        let due_text = text(item, "dueDate");
+        let due_date = due_text.as_deref().and_then(parse_date);
+        if due_text.is_some() && due_date.is_none() {
+            backup.invalid_dates += 1;
+        }
+        let (completed_at, abandoned) = completion(
+            item.get("status").and_then(Value::as_i64).unwrap_or(0),
+            text(item, "completedTime").as_deref(),
+        );
+        let mut tags: Vec<String> = item
+            .get("tags")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str().map(String::from))
+            .collect();
+        if abandoned {
+            tags.push(ABANDONED_TAG.to_string());
+        }
+        let checklist = item
+            .get("items")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let title = text(entry, "title")?;
+                let done = entry.get("status").and_then(Value::as_i64).unwrap_or(0) != 0;
+                let completed_at = done.then(|| {
+                    text(entry, "completedTime")
+                        .as_deref()
+                        .and_then(parse_date)
+                        .unwrap_or_else(dates::now_ms)
+                });
+                Some(ChecklistItem { title, completed_at })
+            })
+            .collect();
+
+        backup.items.push(Item {
+            key,
+            project,
+            parent: text(item, "parentId"),
+            title: text(item, "title").unwrap_or_default(),
+            content: text(item, "content").or_else(|| text(item, "desc")),
+            priority: item.get("priority").and_then(Value::as_i64).and_then(priority),
+            tags: tasks::normalize_tags(tags),
+            due_date,
+            completed_at,
+            order: item.get("sortOrder").and_then(Value::as_i64).unwrap_or(index as i64),
+            checklist,
+        });
+    }
+    Ok(())
+}
+
+fn read_backup(path: &Path) -> Result<Backup, String> {
+    let mut backup = Backup::default();
+    let raw = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let trimmed = raw.trim_start_matches('\u{feff}').trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        read_json(trimmed, &mut backup)?;
+    } else {
+        read_csv(path, &mut backup)?;
+    }
+    Ok(backup)
+}
+
+// Live list with this name, or a new one
+fn list_for(conn: &Connection, project: &Project, summary: &mut ImportSummary) -> rusqlite::Result<(String, String)> {
+    if project.inbox {
+        return Ok((INBOX_LIST_ID.to_string(), export::ensure_inbox(conn)?));
+    }
+    let existing: Option<String> = conn
+        .query_row(
+            r#"SELECT id FROM lists WHERE name = ?1 AND deleted_at IS NULL ORDER BY "order" LIMIT 1"#,
+            [&project.name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let id = match existing {
+        Some(id) => id,
+        None => {
+            summary.lists += 1;
+            tasks::insert_list(conn, &project.name)?
+        }
+    };
+    Ok((id, project.name.clone()))
+}
+
+// Follow parents up to the top-level task, `None` if the chain breaks or loops
+fn root_of<'a>(item: &'a Item, by_key: &HashMap<&str, &'a Item>) -> Option<&'a Item> {
+    let mut current = item;
+    for _ in 0..by_key.len() {
+        match &current.parent {
+            None => return Some(current),
+            Some(parent) => current = by_key.get(parent.as_str())?,
+        }
+    }
+    None
+}
+
+fn insert_checklist(conn: &Connection, task_id: &str, items: &[ChecklistItem], summary: &mut ImportSummary) -> rusqlite::Result<()> {
+    for item in items {
+        let subtask = NewSubtask {
+            title: item.title.clone(),
+            completed_at: item.completed_at,
+            ..Default::default()
+        };
+        tasks::insert_subtask(conn, task_id, &subtask)?;
+        summary.subtasks += 1;
+    }
+    Ok(())
+}
+
+fn apply(conn: &Connection, mut backup: Backup) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary {
+        invalid_dates: backup.invalid_dates,
+        unmapped_columns: backup.unmapped_columns.iter().cloned().collect(),
+        ..Default::default()
+    };
+    backup.items.sort_by_key(|item| item.order);
+
+    let mut lists: HashMap<&str, (String, String)> = HashMap::new();
+    for project in &backup.projects {
+        let list = list_for(conn, project, &mut summary).map_err(|e| e.to_string())?;
+        lists.insert(&project.key, list);
+    }
+
+    let by_key: HashMap<&str, &Item> = backup.items.iter().map(|item| (item.key.as_str(), item)).collect();
+    // TickTick id -> id of the task created for it
+    let mut task_ids: HashMap<&str, String> = HashMap::new();
+
+    for item in backup.items.iter().filter(|item| item.parent.is_none()) {
+        if item.title.is_empty() {
+            summary.unmapped.push(format!("Task {} has no title", item.key));
+            continue;
+        }
+        let Some((list_id, list_name)) = lists.get(item.project.as_str()) else {
+            summary.unmapped.push(format!("'{}' belongs to a list that isn't in the export", item.title));
+            continue;
+        };
+        let task = NewTask {
+            title: item.title.clone(),
+            content: item.content.clone(),
+            due_date: item.due_date,
+            priority: item.priority,
+            tags: item.tags.clone(),
+            completed_at: item.completed_at,
+            ..Default::default()
+        };
+        let id = tasks::insert(conn, list_id, list_name, &task).map_err(|e| e.to_string())?;
+        insert_checklist(conn, &id, &item.checklist, &mut summary).map_err(|e| e.to_string())?;
+        task_ids.insert(&item.key, id);
+        summary.tasks += 1;
+    }
+
+    // Sub-tasks become subtasks of their top-level task, Tada has a single level. Their
+    // own checklist items and tags have nowhere to go.
+    for item in backup.items.iter().filter(|item| item.parent.is_some()) {
+        let parent_id = root_of(item, &by_key).and_then(|root| task_ids.get(root.key.as_str()));
+        let Some(parent_id) = parent_id else {
+            summary.unmapped.push(format!("The parent of sub-task '{}' isn't in the export", item.title));
+            continue;
+        };
+        if item.title.is_empty() {
+            summary.unmapped.push(format!("Task {} has no title", item.key));
+            continue;
+        }
+        if !item.checklist.is_empty() || !item.tags.is_empty() {
+            summary.unmapped.push(format!("Sub-task '{}' was imported without its checklist and tags", item.title));
+        }
+        let subtask = NewSubtask {
+            title: item.title.clone(),
+            due_date: item.due_date,
+            completed_at: item.completed_at,
+        };
+        tasks::insert_subtask(conn, parent_id, &subtask).map_err(|e| e.to_string())?;
+        summary.subtasks += 1;
+    }
+    Ok(summary)
+}
+
+// Import a TickTick backup: the CSV from Settings > Backup or a JSON export. Lists map to
+// lists (an existing list of the same name is reused), sub-tasks and checklist items to
+// subtasks. Folders have no counterpart and are reported as an unmapped column.
+#[tauri::command]
+pub async fn import_ticktick(app: AppHandle, path: String) -> Result<ImportSummary, String> {
+    let backup = read_backup(Path::new(&path))?;
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_ticktick").map_err(|e| e.to_string())?;
+    let summary = apply(&tx, backup)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
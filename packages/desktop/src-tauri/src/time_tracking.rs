@@ -0,0 +1,220 @@
+use crate::settings::{self, Preferences};
+use crate::{dates, db};
+use chrono::TimeDelta;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::AppHandle;
+
+// Below this many tasks the averages say little, the report flags it
+const MIN_SAMPLE_SIZE: usize = 5;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeAccuracyReport {
+    // Completed tasks with both an estimate and logged time, archived ones included
+    pub sample_size: usize,
+    pub small_sample: bool,
+    pub total_estimate_minutes: i64,
+    pub total_actual_minutes: i64,
+    // Mean of (actual - estimate) / estimate: 0.25 means tasks took a quarter longer than
+    // planned, -0.25 a quarter less. `None` without samples.
+    pub average_deviation: Option<f64>,
+    // Mean of the same ratio without its sign, how far off estimates are either way
+    pub average_absolute_deviation: Option<f64>,
+    pub underestimated: usize,
+    pub overestimated: usize,
+    pub exact: usize,
+}
+
+fn samples(conn: &Connection, list_id: Option<&str>) -> rusqlite::Result<Vec<(i64, i64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT estimate_minutes, actual_minutes FROM tasks
+         WHERE completed = 1 AND deleted_at IS NULL AND list_name != 'Trash'
+           AND estimate_minutes > 0 AND actual_minutes IS NOT NULL AND (?1 IS NULL OR list_id = ?1)
+         UNION ALL
+         SELECT estimate_minutes, actual_minutes FROM archived_tasks
+         WHERE estimate_minutes > 0 AND actual_minutes IS NOT NULL AND (?1 IS NULL OR list_id = ?1)",
+    )?;
+    let rows = stmt.query_map([list_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+// Tasks missing either value (or estimated at zero minutes) are left out
+pub fn accuracy(conn: &Connection, list_id: Option<&str>) -> rusqlite::Result<TimeAccuracyReport> {
+    let samples = samples(conn, list_id)?;
+    let mut report = TimeAccuracyReport {
+        sample_size: samples.len(),
+        small_sample: samples.len() < MIN_SAMPLE_SIZE,
+        ..Default::default()
+    };
+    if samples.is_empty() {
+        return Ok(report);
+    }
+    let mut deviation = 0.0;
+    let mut absolute = 0.0;
+    for (estimate, actual) in &samples {
+        report.total_estimate_minutes += estimate;
+        report.total_actual_minutes += actual;
+        let ratio = (actual - estimate) as f64 / *estimate as f64;
+        deviation += ratio;
+        absolute += ratio.abs();
+        match actual.cmp(estimate) {
+            std::cmp::Ordering::Greater => report.underestimated += 1,
+            std::cmp::Ordering::Less => report.overestimated += 1,
+            std::cmp::Ordering::Equal => report.exact += 1,
+        }
+    }
+    let count = samples.len() as f64;
+    report.average_deviation = Some(deviation / count);
+    report.average_absolute_deviation = Some(absolute / count);
+    Ok(report)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListWorkload {
+    pub list_id: String,
+    pub list_name: String,
+    pub tasks: usize,
+    // Tasks that have an estimate of their own, the rest count as the default
+    pub estimated_tasks: usize,
+    pub estimated_minutes: i64,
+    pub minutes_per_day: f64,
+    // `minutes_per_day` beyond `preferences.dailyCapacityMinutes`
+    pub over_capacity: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workload {
+    // Heaviest first, lists with nothing due are left out
+    pub lists: Vec<ListWorkload>,
+    pub total_tasks: usize,
+    pub total_minutes: i64,
+    pub minutes_per_day: f64,
+    pub over_capacity: bool,
+    pub daily_capacity_minutes: i64,
+    pub default_estimate_minutes: i64,
+}
+
+// Incomplete live tasks due before the end of the `horizon_days`-th local day from
+// `now_ms` (today counts as the first), overdue ones included since they're still to do.
+// Tasks without a due date aren't scheduled work and are left out.
+pub fn workload(conn: &Connection, now_ms: i64, horizon_days: i64) -> Result<Workload, String> {
+    if horizon_days < 1 {
+        return Err("horizon_days must be at least 1".to_string());
+    }
+    let prefs = settings::get_typed::<Preferences>(conn).map_err(|e| e.to_string())?;
+    let capacity = prefs.daily_capacity_minutes;
+    let default_estimate = prefs.default_estimate_minutes;
+    let horizon_end = dates::local_date(now_ms)
+        .and_then(|today| today.checked_add_signed(TimeDelta::days(horizon_days)))
+        .map(dates::local_midnight)
+        .ok_or("Invalid horizon")?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT list_id, MAX(list_name), COUNT(*),
+                    SUM(CASE WHEN estimate_minutes > 0 THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN estimate_minutes > 0 THEN estimate_minutes ELSE ?2 END) AS minutes
+             FROM tasks
+             WHERE completed = 0 AND due_date IS NOT NULL AND due_date < ?1
+               AND deleted_at IS NULL AND list_name != 'Trash' AND list_id IS NOT NULL
+             GROUP BY list_id
+             ORDER BY minutes DESC, MAX(list_name)",
+        )
+        .map_err(|e| e.to_string())?;
+    let days = horizon_days as f64;
+    let lists = stmt
+        .query_map(params![horizon_end, default_estimate], |row| {
+            let minutes: i64 = row.get(4)?;
+            let per_day = minutes as f64 / days;
+            Ok(ListWorkload {
+                list_id: row.get(0)?,
+                list_name: row.get(1)?,
+                tasks: row.get::<_, i64>(2)? as usize,
+                estimated_tasks: row.get::<_, i64>(3)? as usize,
+                estimated_minutes: minutes,
+                minutes_per_day: per_day,
+                over_capacity: per_day > capacity as f64,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let total_minutes: i64 = lists.iter().map(|list| list.estimated_minutes).sum();
+    let minutes_per_day = total_minutes as f64 / days;
+    Ok(Workload {
+        total_tasks: lists.iter().map(|list| list.tasks).sum(),
+        total_minutes,
+        minutes_per_day,
+        over_capacity: minutes_per_day > capacity as f64,
+        daily_capacity_minutes: capacity,
+        default_estimate_minutes: default_estimate,
+        lists,
+    })
+}
+
+fn check_live(conn: &Connection, task_id: &str) -> Result<(), String> {
+    let found = conn
+        .query_row("SELECT 1 FROM tasks WHERE id = ?1 AND deleted_at IS NULL", [task_id], |_| Ok(()))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    found.ok_or_else(|| format!("Task {} not found", task_id))
+}
+
+// `None` clears the estimate
+pub fn set_estimate_minutes(conn: &Connection, task_id: &str, minutes: Option<i64>) -> Result<(), String> {
+    if minutes.is_some_and(|m| m < 0) {
+        return Err("An estimate can't be negative".to_string());
+    }
+    check_live(conn, task_id)?;
+    conn.execute(
+        "UPDATE tasks SET estimate_minutes = ?1, updated_at = ?2 WHERE id = ?3",
+        params![minutes, dates::now_ms(), task_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Add to the time spent on a task, a negative amount corrects an earlier entry (the total
+// never drops below zero). Returns the new total.
+pub fn add_time(conn: &Connection, task_id: &str, minutes: i64) -> Result<i64, String> {
+    if minutes == 0 {
+        return Err("Nothing to log".to_string());
+    }
+    check_live(conn, task_id)?;
+    conn.query_row(
+        "UPDATE tasks SET actual_minutes = MAX(0, COALESCE(actual_minutes, 0) + ?1), updated_at = ?2
+         WHERE id = ?3
+         RETURNING actual_minutes",
+        params![minutes, dates::now_ms(), task_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_estimate(app: AppHandle, task_id: String, minutes: Option<i64>) -> Result<(), String> {
+    let conn = db::connect(&app)?;
+    set_estimate_minutes(&conn, &task_id, minutes)
+}
+
+#[tauri::command]
+pub async fn log_time(app: AppHandle, task_id: String, minutes: i64) -> Result<i64, String> {
+    let conn = db::connect(&app)?;
+    add_time(&conn, &task_id, minutes)
+}
+
+#[tauri::command]
+pub async fn get_time_accuracy(app: AppHandle, list_id: Option<String>) -> Result<TimeAccuracyReport, String> {
+    let conn = db::connect(&app)?;
+    accuracy(&conn, list_id.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_workload(app: AppHandle, now_ms: i64, horizon_days: i64) -> Result<Workload, String> {
+    let conn = db::connect(&app)?;
+    workload(&conn, now_ms, horizon_days)
+}
@@ -0,0 +1,397 @@
+use crate::export::{self, INBOX_LIST_ID};
+use crate::natural_dates::{self, Language};
+use crate::tasks::{self, NewSubtask, NewTask};
+use crate::{dates, db, journal};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    // Lists created for projects without a list of the same name
+    pub lists: usize,
+    pub tasks: usize,
+    pub subtasks: usize,
+    // Items imported without a due date because it couldn't be read (e.g. "every day")
+    pub invalid_dates: usize,
+    // Items that were left out, with the reason
+    pub unmapped: Vec<String>,
+}
+
+struct Project {
+    key: String,
+    name: String,
+    inbox: bool,
+}
+
+struct Item {
+    key: String,
+    project: String,
+    parent: Option<String>,
+    title: String,
+    description: Option<String>,
+    priority: Option<i64>,
+    labels: Vec<String>,
+    due_date: Option<i64>,
+    completed_at: Option<i64>,
+    order: i64,
+}
+
+#[derive(Default)]
+struct Backup {
+    projects: Vec<Project>,
+    items: Vec<Item>,
+    invalid_dates: usize,
+}
+
+// Ids are strings in current exports and numbers in older ones
+fn key(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn text(value: &Value, field: &str) -> Option<String> {
+    value.get(field).and_then(Value::as_str).map(str::trim).filter(|s| !s.is_empty()).map(String::from)
+}
+
+// The API counts priorities up from 1 (none) to 4 (urgent)
+fn api_priority(priority: i64) -> Option<i64> {
+    match priority {
+        4 => Some(1),
+        3 => Some(2),
+        2 => Some(3),
+        _ => None,
+    }
+}
+
+// CSV templates count the other way, from 1 (p1, urgent) to 4 (none)
+fn csv_priority(priority: i64) -> Option<i64> {
+    match priority {
+        1..=3 => Some(priority),
+        _ => None,
+    }
+}
+
+fn is_checked(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_i64().is_some_and(|n| n != 0),
+        _ => false,
+    }
+}
+
+// Sync API backups (`projects` + `items`) as well as REST exports (`tasks`)
+fn read_json(raw: &str) -> Result<Backup, String> {
+    let root: Value = serde_json::from_str(raw).map_err(|e| format!("Not a Todoist JSON export: {}", e))?;
+    let mut backup = Backup::default();
+
+    // Older backups reference labels by id
+    let label_names: HashMap<String, String> = root
+        .get("labels")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|label| Some((key(label.get("id"))?, text(label, "name")?)))
+        .collect();
+
+    for project in root.get("projects").and_then(Value::as_array).into_iter().flatten() {
+        let (Some(key), Some(name)) = (key(project.get("id")), text(project, "name")) else {
+            continue;
+        };
+        let inbox = is_checked(project.get("inbox_project")) || is_checked(project.get("is_inbox_project"));
+        backup.projects.push(Project { key, name, inbox });
+    }
+
+    let items = root.get("items").or_else(|| root.get("tasks")).and_then(Value::as_array);
+    for (index, item) in items.into_iter().flatten().enumerate() {
+        let Some(item_key) = key(item.get("id")) else {
+            continue;
+        };
+        let due_text = item.get("due").and_then(|due| text(due, "date"));
+        let due_date = due_text.as_deref().and_then(dates::parse_due_date);
+        if due_text.is_some() && due_date.is_none() {
+            backup.invalid_dates += 1;
+        }
+        let completed = is_checked(item.get("checked")) || is_checked(item.get("is_completed"));
+        let completed_at = completed.then(|| {
+            text(item, "completed_at")
+                .or_else(|| text(item, "date_completed"))
+                .and_then(|t| dates::parse_due_date(&t))
+                .unwrap_or_else(dates::now_ms)
+        });
+        let labels = item
+            .get("labels")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|label| match label {
+                Value::String(name) => Some(name.clone()),
+                other => key(Some(other)).and_then(|id| label_names.get(&id).cloned()),
+            })
+            .collect();
+
+        backup.items.push(Item {
+            key: item_key,
+            project: key(item.get("project_id")).unwrap_or_default(),
+            parent: key(item.get("parent_id")),
+            title: text(item, "content").unwrap_or_default(),
+            description: text(item, "description"),
+            priority: item.get("priority").and_then(Value::as_i64).and_then(api_priority),
+            labels,
+            due_date,
+            completed_at,
+            order: item
+                .get("child_order")
+                .or_else(|| item.get("order"))
+                .and_then(Value::as_i64)
+                .unwrap_or(index as i64),
+        });
+    }
+    Ok(backup)
+}
+
+// Labels are written into the task text as `@label`
+fn split_labels(content: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut labels = Vec::new();
+    for word in content.split_whitespace() {
+        match word.strip_prefix('@').filter(|label| !label.is_empty()) {
+            Some(label) => labels.push(label.to_string()),
+            None => words.push(word),
+        }
+    }
+    (words.join(" "), labels)
+}
+
+// One CSV template per project, named after it. Sub-items are marked by INDENT,
+// comments are NOTE rows below their task.
+fn read_csv(path: &Path, backup: &mut Backup) -> Result<(), String> {
+    let project_key = format!("csv:{}", path.display());
+    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Todoist").to_string();
+    let inbox = name.eq_ignore_ascii_case("inbox");
+    backup.projects.push(Project {
+        key: project_key.clone(),
+        name,
+        inbox,
+    });
+
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim_start_matches('\u{feff}').eq_ignore_ascii_case(name))
+    };
+    let (type_col, content_col) = match (column("TYPE"), column("CONTENT")) {
+        (Some(type_col), Some(content_col)) => (type_col, content_col),
+        _ => return Err(format!("'{}' is not a Todoist CSV export", path.display())),
+    };
+    let (description_col, priority_col, indent_col) = (column("DESCRIPTION"), column("PRIORITY"), column("INDENT"));
+    let (date_col, date_lang_col) = (column("DATE"), column("DATE_LANG"));
+
+    let mut top_level: Option<String> = None;
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        let cell = |col: Option<usize>| col.and_then(|col| record.get(col)).unwrap_or("");
+
+        match cell(Some(type_col)).to_lowercase().as_str() {
+            "task" => {}
+            // Comments go into the task's notes
+            "note" => {
+                let note = cell(Some(content_col));
+                if let Some(item) = backup.items.last_mut().filter(|item| item.project == project_key && !note.is_empty()) {
+                    let notes = item.description.get_or_insert_with(String::new);
+                    if !notes.is_empty() {
+                        notes.push_str("\n\n");
+                    }
+                    notes.push_str(note);
+                }
+                continue;
+            }
+            _ => continue,
+        }
+
+        let item_key = format!("{}:{}", project_key, index);
+        let parent = match cell(indent_col).parse::<i64>().unwrap_or(1) {
+            1 => {
+                top_level = Some(item_key.clone());
+                None
+            }
+            // Tada has a single level of subtasks, deeper items hang off the top-level task
+            _ => Some(top_level.clone().unwrap_or_default()),
+        };
+
+        let date_text = cell(date_col);
+        let due_date = dates::parse_due_date(date_text).or_else(|| {
+            let language = Language::from_setting(Some(cell(date_lang_col)));
+            natural_dates::find(date_text, dates::now_ms(), language)
+                .filter(|found| found.range.len() == date_text.len())
+                .map(|found| found.due_date)
+        });
+        if due_date.is_none() && !date_text.is_empty() {
+            backup.invalid_dates += 1;
+        }
+
+        let (title, labels) = split_labels(cell(Some(content_col)));
+        backup.items.push(Item {
+            key: item_key,
+            project: project_key.clone(),
+            parent,
+            title,
+            description: Some(cell(description_col).to_string()).filter(|d| !d.is_empty()),
+            priority: cell(priority_col).parse().ok().and_then(csv_priority),
+            labels,
+            due_date,
+            completed_at: None,
+            order: index as i64,
+        });
+    }
+    Ok(())
+}
+
+fn read_backup(path: &Path) -> Result<Backup, String> {
+    let mut backup = Backup::default();
+    // An unzipped full backup is a folder of per-project CSV files
+    if path.is_dir() {
+        let mut files: Vec<_> = fs::read_dir(path)
+            .map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv")))
+            .collect();
+        files.sort();
+        for file in files {
+            read_csv(&file, &mut backup)?;
+        }
+        return Ok(backup);
+    }
+
+    let raw = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    if raw.trim_start().starts_with('{') {
+        read_json(&raw)
+    } else {
+        read_csv(path, &mut backup)?;
+        Ok(backup)
+    }
+}
+
+// Live list with this name, or a new one
+fn list_for(conn: &Connection, project: &Project, summary: &mut ImportSummary) -> rusqlite::Result<(String, String)> {
+    if project.inbox {
+        return Ok((INBOX_LIST_ID.to_string(), export::ensure_inbox(conn)?));
+    }
+    let existing: Option<String> = conn
+        .query_row(
+            r#"SELECT id FROM lists WHERE name = ?1 AND deleted_at IS NULL ORDER BY "order" LIMIT 1"#,
+            [&project.name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let id = match existing {
+        Some(id) => id,
+        None => {
+            summary.lists += 1;
+            tasks::insert_list(conn, &project.name)?
+        }
+    };
+    Ok((id, project.name.clone()))
+}
+
+// Follow parents up to the top-level item, `None` if the chain breaks or loops
+fn root_of<'a>(item: &'a Item, by_key: &HashMap<&str, &'a Item>) -> Option<&'a Item> {
+    let mut current = item;
+    for _ in 0..by_key.len() {
+        match &current.parent {
+            None => return Some(current),
+            Some(parent) => current = by_key.get(parent.as_str())?,
+        }
+    }
+    None
+}
+
+fn apply(conn: &Connection, mut backup: Backup) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary {
+        invalid_dates: backup.invalid_dates,
+        ..Default::default()
+    };
+    backup.items.sort_by_key(|item| item.order);
+
+    let mut lists: HashMap<&str, (String, String)> = HashMap::new();
+    for project in &backup.projects {
+        let list = list_for(conn, project, &mut summary).map_err(|e| e.to_string())?;
+        lists.insert(&project.key, list);
+    }
+
+    let by_key: HashMap<&str, &Item> = backup.items.iter().map(|item| (item.key.as_str(), item)).collect();
+    // Todoist id -> id of the task created for it
+    let mut task_ids: HashMap<&str, String> = HashMap::new();
+
+    for item in backup.items.iter().filter(|item| item.parent.is_none()) {
+        if item.title.is_empty() {
+            summary.unmapped.push(format!("Item {} has no content", item.key));
+            continue;
+        }
+        let Some((list_id, list_name)) = lists.get(item.project.as_str()) else {
+            summary.unmapped.push(format!("'{}' belongs to a project that isn't in the export", item.title));
+            continue;
+        };
+        let task = NewTask {
+            title: item.title.clone(),
+            content: item.description.clone(),
+            due_date: item.due_date,
+            priority: item.priority,
+            tags: tasks::normalize_tags(item.labels.clone()),
+            completed_at: item.completed_at,
+            ..Default::default()
+        };
+        let id = tasks::insert(conn, list_id, list_name, &task).map_err(|e| e.to_string())?;
+        task_ids.insert(&item.key, id);
+        summary.tasks += 1;
+    }
+
+    for item in backup.items.iter().filter(|item| item.parent.is_some()) {
+        let parent_id = root_of(item, &by_key).and_then(|root| task_ids.get(root.key.as_str()));
+        let Some(parent_id) = parent_id else {
+            summary.unmapped.push(format!("The parent of sub-item '{}' isn't in the export", item.title));
+            continue;
+        };
+        if item.title.is_empty() {
+            summary.unmapped.push(format!("Item {} has no content", item.key));
+            continue;
+        }
+        let subtask = NewSubtask {
+            title: item.title.clone(),
+            due_date: item.due_date,
+            completed_at: item.completed_at,
+        };
+        tasks::insert_subtask(conn, parent_id, &subtask).map_err(|e| e.to_string())?;
+        summary.subtasks += 1;
+    }
+    Ok(summary)
+}
+
+// Import a Todoist export: a JSON backup, a project's CSV template or a folder of them.
+// Projects become lists (an existing list of the same name is reused), items tasks and
+// sub-items subtasks, labels tags.
+#[tauri::command]
+pub async fn import_todoist(app: AppHandle, path: String) -> Result<ImportSummary, String> {
+    let backup = read_backup(Path::new(&path))?;
+    let mut conn = db::connect(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "import_todoist").map_err(|e| e.to_string())?;
+    let summary = apply(&tx, backup)?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
@@ -0,0 +1,203 @@
+use crate::export::{self, INBOX_LIST_ID};
+use crate::{attachments, dates, db, journal, settings, tray};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use tauri::AppHandle;
+
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+// Live completed tasks, of the list bound to ?1 or of every list when it is NULL
+const COMPLETED: &str = "completed = 1 AND deleted_at IS NULL AND list_name != 'Trash' AND (?1 IS NULL OR list_id = ?1)";
+
+// Retention comes from `preferences.trashRetentionDays`, falling back to the default
+pub fn retention_days(conn: &Connection) -> i64 {
+    settings::read(conn, "preferences")
+        .ok()
+        .flatten()
+        .and_then(|p| p.get("trashRetentionDays").and_then(|d| d.as_i64()))
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+// Subtasks are trashed with the same timestamp as their parent, which is how a restore
+// tells them apart from subtasks that were deleted on their own earlier.
+pub fn trash(conn: &mut Connection, id: &str) -> Result<(), String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "trash_task").map_err(|e| e.to_string())?;
+    let updated = tx
+        .execute(
+            "UPDATE tasks SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        let exists: Option<i64> = tx
+            .query_row("SELECT 1 FROM tasks WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if exists.is_none() {
+            return Err(format!("Task {} not found", id));
+        }
+        // Already in the trash
+        return Ok(());
+    }
+    tx.execute(
+        "UPDATE subtasks SET deleted_at = ?1, updated_at = ?1 WHERE parent_id = ?2 AND deleted_at IS NULL",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn restore(conn: &mut Connection, id: &str) -> Result<(), String> {
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "restore_task").map_err(|e| e.to_string())?;
+    let row: Option<(Option<i64>, Option<String>)> = tx
+        .query_row("SELECT deleted_at, list_id FROM tasks WHERE id = ?1", [id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((deleted_at, list_id)) = row else {
+        return Err(format!("Task {} not found", id));
+    };
+    let Some(deleted_at) = deleted_at else {
+        return Ok(());
+    };
+
+    tx.execute(
+        "UPDATE tasks SET deleted_at = NULL, updated_at = ?1 WHERE id = ?2",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE subtasks SET deleted_at = NULL, updated_at = ?1 WHERE parent_id = ?2 AND deleted_at = ?3",
+        params![now, id, deleted_at],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // The list may have been deleted in the meantime, the inbox takes the task back
+    let list_alive: Option<i64> = match &list_id {
+        Some(list_id) => tx
+            .query_row(
+                "SELECT 1 FROM lists WHERE id = ?1 AND deleted_at IS NULL",
+                [list_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
+    if list_alive.is_none() {
+        let inbox_name = export::ensure_inbox(&tx).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE tasks SET list_id = ?1, list_name = ?2 WHERE id = ?3",
+            params![INBOX_LIST_ID, inbox_name, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+// Permanently delete trashed rows older than `older_than_days`, returns how many tasks
+// and lists were removed. Subtasks and attachments of purged tasks go with them (ON DELETE
+// CASCADE), the attachment files in `attachments_dir` once that's committed.
+pub fn purge(conn: &mut Connection, attachments_dir: &Path, older_than_days: i64) -> Result<usize, String> {
+    let cutoff = dates::now_ms() - older_than_days.max(0) * DAY_MS;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::pause(&tx).map_err(|e| e.to_string())?;
+    let files = attachments::stored_paths(&tx, "SELECT id FROM tasks WHERE deleted_at <= ?1", [cutoff])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM subtasks WHERE deleted_at <= ?1", [cutoff])
+        .map_err(|e| e.to_string())?;
+    let tasks = tx
+        .execute("DELETE FROM tasks WHERE deleted_at <= ?1", [cutoff])
+        .map_err(|e| e.to_string())?;
+    let lists = tx
+        .execute(
+            "DELETE FROM lists WHERE deleted_at <= ?1 AND id != ?2",
+            params![cutoff, INBOX_LIST_ID],
+        )
+        .map_err(|e| e.to_string())?;
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    attachments::remove_files(attachments_dir, &files);
+    Ok(tasks + lists)
+}
+
+// Remove the completed tasks of a list (of every list with `None`) with their subtasks,
+// returns how many tasks went. They move to the trash like `trash` does, unless the trash
+// keeps nothing (a retention of 0 days), then they are deleted right away, attachment
+// files included.
+pub fn remove_completed(conn: &mut Connection, attachments_dir: &Path, list_id: Option<&str>) -> Result<usize, String> {
+    let permanent = retention_days(conn) == 0;
+    let now = dates::now_ms();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    journal::begin(&tx, "clear_completed").map_err(|e| e.to_string())?;
+    let mut files = Vec::new();
+    let removed = if permanent {
+        files = attachments::stored_paths(&tx, &format!("SELECT id FROM tasks WHERE {}", COMPLETED), params![list_id])
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            &format!("DELETE FROM subtasks WHERE parent_id IN (SELECT id FROM tasks WHERE {})", COMPLETED),
+            params![list_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(&format!("DELETE FROM tasks WHERE {}", COMPLETED), params![list_id])
+            .map_err(|e| e.to_string())?
+    } else {
+        tx.execute(
+            &format!(
+                "UPDATE subtasks SET deleted_at = ?2, updated_at = ?2
+                 WHERE deleted_at IS NULL AND parent_id IN (SELECT id FROM tasks WHERE {})",
+                COMPLETED
+            ),
+            params![list_id, now],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            &format!("UPDATE tasks SET deleted_at = ?2, updated_at = ?2 WHERE {}", COMPLETED),
+            params![list_id, now],
+        )
+        .map_err(|e| e.to_string())?
+    };
+    journal::end(&tx).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    attachments::remove_files(attachments_dir, &files);
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn trash_task(app: AppHandle, id: String) -> Result<(), String> {
+    let mut conn = db::connect(&app)?;
+    trash(&mut conn, &id)
+}
+
+#[tauri::command]
+pub async fn restore_task(app: AppHandle, id: String) -> Result<(), String> {
+    let mut conn = db::connect(&app)?;
+    restore(&mut conn, &id)
+}
+
+#[tauri::command]
+pub async fn purge_trash(app: AppHandle, older_than_days: Option<i64>) -> Result<usize, String> {
+    let mut conn = db::connect(&app)?;
+    let days = older_than_days.unwrap_or_else(|| retention_days(&conn));
+    purge(&mut conn, &attachments::attachments_dir(&app)?, days)
+}
+
+#[tauri::command]
+pub async fn clear_completed(app: AppHandle, list_id: Option<String>) -> Result<usize, String> {
+    let removed = {
+        let mut conn = db::connect(&app)?;
+        remove_completed(&mut conn, &attachments::attachments_dir(&app)?, list_id.as_deref())?
+    };
+    if removed > 0 && let Err(e) = tray::refresh(&app) {
+        eprintln!("Failed to refresh tray: {}", e);
+    }
+    Ok(removed)
+}
@@ -0,0 +1,120 @@
+use crate::{dates, db, settings, AppState};
+use chrono::TimeDelta;
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::sync::atomic::Ordering;
+use tauri::{
+    image::Image,
+    menu::{Menu, MenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle, Manager, Runtime,
+};
+
+const TRAY_ID: &str = "tray";
+
+fn toggle_main_window<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+}
+
+pub fn create(app: &App) -> tauri::Result<()> {
+    // Create a tray menu
+    let add_i = MenuItem::with_id(app, "add", "Add Task", true, None::<&str>)?;
+    let toggle_i = MenuItem::with_id(app, "toggle", "Show/Hide Tada", true, None::<&str>)?;
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&add_i, &toggle_i, &quit_i])?;
+
+    let icon_bytes = include_bytes!("../icons/tray-icon.png");
+    let icon = Image::from_bytes(icon_bytes).expect("Failed to load tray icon");
+
+    // Build the tray icon
+    let tray_builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .icon(icon)
+        .tooltip("Tada")
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "add" => {
+                #[cfg(desktop)]
+                crate::quick_add::open_window(app);
+            }
+            "toggle" => toggle_main_window(app),
+            "quit" => {
+                // User clicked the exit button of the tray
+                let state = app.state::<AppState>();
+                state.is_quitting.store(true, Ordering::Relaxed);
+                app.exit(0);
+            }
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| match event {
+            // Left-click the tray icon on Windows/Linux to show or hide the window. Only the
+            // release counts, a click also reports the press.
+            TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } => toggle_main_window(tray.app_handle()),
+            _ => {}
+        });
+
+    #[cfg(target_os = "macos")]
+    let tray_builder = tray_builder.icon_as_template(true);
+
+    tray_builder.build(app)?;
+    Ok(())
+}
+
+// Open tasks due within the local today. Runs on every refresh, so it stays a range scan
+// on idx_tasks_due_date.
+pub fn due_today_count(conn: &Connection, now_ms: i64) -> rusqlite::Result<i64> {
+    let Some(today) = dates::local_date(now_ms) else {
+        return Ok(0);
+    };
+    let start = dates::local_midnight(today);
+    let end = dates::local_midnight(today + TimeDelta::days(1));
+    conn.query_row(
+        r#"
+        SELECT COUNT(*) FROM tasks
+        WHERE due_date >= ?1 AND due_date < ?2
+          AND completed = 0 AND deleted_at IS NULL AND list_name != 'Trash'
+          AND (list_id IS NULL OR list_id NOT IN (SELECT id FROM lists WHERE hidden = 1))
+        "#,
+        params![start, end],
+        |row| row.get(0),
+    )
+}
+
+fn tooltip(conn: &Connection, count: i64) -> String {
+    let language = settings::read(conn, "preferences")
+        .ok()
+        .flatten()
+        .and_then(|p| p.get("language").and_then(Value::as_str).map(String::from));
+    match language.as_deref() {
+        Some("en") if count == 1 => "Tada - 1 task due today".to_string(),
+        Some("en") => format!("Tada - {} tasks due today", count),
+        _ => format!("Tada - 今天有 {} 个任务", count),
+    }
+}
+
+pub fn refresh(app: &AppHandle) -> Result<i64, String> {
+    let conn = db::connect(app)?;
+    let count = due_today_count(&conn, dates::now_ms()).map_err(|e| e.to_string())?;
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_tooltip(Some(tooltip(&conn, count)))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(count)
+}
+
+// Called by the frontend after it changed tasks, returns the count shown
+#[tauri::command]
+pub async fn refresh_tray(app: AppHandle) -> Result<i64, String> {
+    refresh(&app)
+}
@@ -0,0 +1,145 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+pub const UPDATE_PROGRESS_EVENT: &str = "update-progress";
+pub const UPDATE_AVAILABLE_EVENT: &str = "update-available";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub date: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[cfg(desktop)]
+mod desktop {
+    use super::*;
+    use crate::{settings, AppState};
+    use serde_json::Value;
+    use std::sync::atomic::Ordering;
+    use tauri::{Emitter, Manager};
+    use tauri_plugin_updater::{Update, UpdaterExt};
+
+    #[derive(Debug, Clone, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct UpdateProgress {
+        downloaded: u64,
+        total: Option<u64>,
+    }
+
+    // Updates are only ever installed after their minisign signature checked out against
+    // `plugins.updater.pubkey` from tauri.conf.json; the plugin refuses unsigned or mis-signed
+    // packages. Builds without that key (local and community builds) have no updater at all.
+    // tauri.conf.json ships the endpoint with the key left empty: the project's public key
+    // goes there, together with `bundle.createUpdaterArtifacts` and the private key for the
+    // release workflow, once a signing key pair exists.
+    pub fn is_configured(app: &AppHandle) -> bool {
+        app.config()
+            .plugins
+            .0
+            .get("updater")
+            .and_then(|config| config.get("pubkey"))
+            .and_then(Value::as_str)
+            .is_some_and(|key| !key.trim().is_empty())
+    }
+
+    // `preferences.autoUpdate`, on unless turned off
+    fn auto_update_enabled(app: &AppHandle) -> bool {
+        crate::db::connect(app)
+            .ok()
+            .and_then(|conn| settings::read(&conn, "preferences").ok().flatten())
+            .and_then(|p| p.get("autoUpdate").and_then(Value::as_bool))
+            .unwrap_or(true)
+    }
+
+    fn ensure_configured(app: &AppHandle) -> Result<(), String> {
+        if is_configured(app) {
+            Ok(())
+        } else {
+            Err("This build has no update signing key configured".to_string())
+        }
+    }
+
+    pub async fn check(app: &AppHandle) -> Result<Option<Update>, String> {
+        ensure_configured(app)?;
+        let updater = app.updater().map_err(|e| e.to_string())?;
+        updater.check().await.map_err(|e| format!("Failed to check for updates: {}", e))
+    }
+
+    pub fn info(update: &Update) -> UpdateInfo {
+        UpdateInfo {
+            version: update.version.clone(),
+            current_version: update.current_version.clone(),
+            date: update.date.map(|date| date.to_string()),
+            notes: update.body.clone(),
+        }
+    }
+
+    pub async fn install(app: &AppHandle) -> Result<(), String> {
+        let update = check(app).await?.ok_or_else(|| "No update available".to_string())?;
+        let mut downloaded: u64 = 0;
+        update
+            .download_and_install(
+                |chunk, total| {
+                    downloaded += chunk as u64;
+                    let _ = app.emit(UPDATE_PROGRESS_EVENT, UpdateProgress { downloaded, total });
+                },
+                || {},
+            )
+            .await
+            .map_err(|e| format!("Failed to install the update: {}", e))?;
+
+        app.state::<AppState>().is_quitting.store(true, Ordering::Relaxed);
+        app.restart()
+    }
+
+    // Background check on startup, the frontend learns about an update via an event
+    pub fn check_on_startup(app: &AppHandle) {
+        if !is_configured(app) || !auto_update_enabled(app) {
+            return;
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            match check(&app).await {
+                Ok(Some(update)) => {
+                    let _ = app.emit(UPDATE_AVAILABLE_EVENT, info(&update));
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+        });
+    }
+}
+
+#[cfg(desktop)]
+pub use desktop::{check_on_startup, is_configured};
+
+#[tauri::command]
+pub async fn check_for_update(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    #[cfg(desktop)]
+    {
+        Ok(desktop::check(&app).await?.as_ref().map(desktop::info))
+    }
+    #[cfg(not(desktop))]
+    {
+        let _ = app;
+        Err("Updates are only available on desktop".to_string())
+    }
+}
+
+// Download, verify and install the pending update, then restart into it. Progress is
+// reported through `update-progress` events.
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    #[cfg(desktop)]
+    {
+        desktop::install(&app).await
+    }
+    #[cfg(not(desktop))]
+    {
+        let _ = app;
+        Err("Updates are only available on desktop".to_string())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::settings::{self, Preferences};
+use crate::{dates, db};
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+// Local wall-clock times, "HH:MM". `end` is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingWindow {
+    pub start: String,
+    pub end: String,
+}
+
+impl WorkingWindow {
+    fn times(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let start = NaiveTime::parse_from_str(&self.start, "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(&self.end, "%H:%M").ok()?;
+        (start < end).then_some((start, end))
+    }
+}
+
+// `preferences.workingHours`: a window per weekday, a missing day has no working hours
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkingHours {
+    pub monday: Option<WorkingWindow>,
+    pub tuesday: Option<WorkingWindow>,
+    pub wednesday: Option<WorkingWindow>,
+    pub thursday: Option<WorkingWindow>,
+    pub friday: Option<WorkingWindow>,
+    pub saturday: Option<WorkingWindow>,
+    pub sunday: Option<WorkingWindow>,
+}
+
+impl WorkingHours {
+    fn days(&self) -> [(&'static str, &Option<WorkingWindow>); 7] {
+        [
+            ("monday", &self.monday),
+            ("tuesday", &self.tuesday),
+            ("wednesday", &self.wednesday),
+            ("thursday", &self.thursday),
+            ("friday", &self.friday),
+            ("saturday", &self.saturday),
+            ("sunday", &self.sunday),
+        ]
+    }
+
+    fn window(&self, weekday: Weekday) -> Option<(NaiveTime, NaiveTime)> {
+        self.days()[weekday.num_days_from_monday() as usize].1.as_ref()?.times()
+    }
+
+    // Used by `Preferences::problems`
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (day, window) in self.days() {
+            if let Some(window) = window && window.times().is_none() {
+                problems.push(format!(
+                    "workingHours.{} must be two HH:MM times with start before end, got '{}'-'{}'",
+                    day, window.start, window.end
+                ));
+            }
+        }
+        if self.days().iter().all(|(_, window)| window.is_none()) {
+            problems.push("workingHours needs at least one day".to_string());
+        }
+        problems
+    }
+
+    // `desired_ms` itself when it falls inside a window, otherwise the start of the next
+    // window. Days are the local calendar days.
+    pub fn next_available(&self, desired_ms: i64) -> i64 {
+        let Some(desired) = dates::to_local_naive(desired_ms) else {
+            return desired_ms;
+        };
+        for days_ahead in 0..=7 {
+            let Some(date) = desired.date().checked_add_signed(TimeDelta::days(days_ahead)) else {
+                break;
+            };
+            let Some((start, end)) = self.window(date.weekday()) else {
+                continue;
+            };
+            if days_ahead == 0 {
+                if desired.time() >= end {
+                    continue;
+                }
+                if desired.time() >= start {
+                    return desired_ms;
+                }
+            }
+            return dates::from_local_naive(NaiveDateTime::new(date, start));
+        }
+        desired_ms
+    }
+}
+
+// `None` when no working hours are set, reminders then fire whenever they are due
+pub fn load(conn: &Connection) -> Option<WorkingHours> {
+    settings::get_typed::<Preferences>(conn).ok()?.working_hours
+}
+
+pub fn clamp(hours: Option<&WorkingHours>, fire_at: i64) -> i64 {
+    hours.map_or(fire_at, |hours| hours.next_available(fire_at))
+}
+
+#[tauri::command]
+pub async fn next_available_time(app: AppHandle, desired_ms: i64) -> Result<i64, String> {
+    let conn = db::connect(&app)?;
+    Ok(clamp(load(&conn).as_ref(), desired_ms))
+}
@@ -0,0 +1,33 @@
+import { invoke } from '@tauri-apps/api/core';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+
+// Small capture window opened by the global shortcut. Enter saves, Escape dismisses.
+const form = document.getElementById('quick-add-form') as HTMLFormElement;
+const input = document.getElementById('quick-add-input') as HTMLInputElement;
+const appWindow = getCurrentWindow();
+
+const dismiss = async () => {
+    input.value = '';
+    await appWindow.hide();
+};
+
+form.addEventListener('submit', async (event) => {
+    event.preventDefault();
+    const title = input.value.trim();
+    if (!title) return;
+    try {
+        await invoke('quick_add_task', { title });
+        await dismiss();
+    } catch (error) {
+        console.error('Failed to add task:', error);
+    }
+});
+
+document.addEventListener('keydown', (event) => {
+    if (event.key === 'Escape') void dismiss();
+});
+
+// The window is hidden rather than closed, put the cursor back when it returns
+void appWindow.onFocusChanged(({ payload: focused }) => {
+    if (focused) input.focus();
+});
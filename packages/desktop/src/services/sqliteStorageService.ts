@@ -1,4 +1,5 @@
 import Database from '@tauri-apps/plugin-sql';
+import { invoke } from '@tauri-apps/api/core';
 import { IStorageService } from '@tada/core/services/storageInterface';
 import {
     AISettings,
@@ -24,6 +25,9 @@ import {
     getTaskGroupCategory
 } from '@tada/core/store/jotai';
 
+// Name of the AI API key in the native encrypted secret store
+const AI_API_KEY_SECRET = 'ai.apiKey';
+
 interface DbTask {
     id: string;
     title: string;
@@ -119,10 +123,13 @@ export class SqliteStorageService implements IStorageService {
 
     async initialize(): Promise<void> {
         try {
-            this.db = await Database.load('sqlite:tada.db');
+            // Not always `sqlite:tada.db`, the database may have been moved to another directory
+            this.db = await Database.load(await invoke<string>('get_database_url'));
             console.log('Database connected successfully');
 
             await this.ensureIndexes();
+            // The undo journal's triggers follow the schema, so they're (re)installed after migrations
+            await invoke('install_journal').catch(error => console.warn('Failed to install the undo journal:', error));
 
             const lists = await this.db.select<DbList[]>('SELECT * FROM lists');
             if (lists.length === 0) {
@@ -248,6 +255,8 @@ export class SqliteStorageService implements IStorageService {
             }
         }
         this.isProcessingQueue = false;
+        // Keep the tray's due-today count in step with what was just written
+        invoke('refresh_tray').catch(error => console.warn('Failed to refresh tray:', error));
     }
 
     // Settings
@@ -283,6 +292,13 @@ export class SqliteStorageService implements IStorageService {
                 console.error(`Failed to parse setting ${setting.key}:`, error);
             }
         });
+        if (!result.ai.apiKey) {
+            try {
+                result.ai.apiKey = (await invoke<string | null>('get_secret', { key: AI_API_KEY_SECRET })) ?? '';
+            } catch (error) {
+                console.error('Failed to read AI API key from secure storage:', error);
+            }
+        }
         this.settingsCache = result;
         return result;
     }
@@ -312,7 +328,14 @@ export class SqliteStorageService implements IStorageService {
         this.queueWrite(async () => {
             const db = this.getDb();
             const now = Date.now();
-            await db.execute('INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?, ?, ?)', ['ai', JSON.stringify(settings), now]);
+            let stored = settings;
+            try {
+                await invoke('set_secret', { key: AI_API_KEY_SECRET, value: settings.apiKey });
+                stored = { ...settings, apiKey: '' };
+            } catch (error) {
+                console.error('Secure storage unavailable, keeping AI API key in settings:', error);
+            }
+            await db.execute('INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?, ?, ?)', ['ai', JSON.stringify(stored), now]);
         });
         return settings;
     }
@@ -334,7 +357,7 @@ export class SqliteStorageService implements IStorageService {
 
     async fetchListsAsync(): Promise<List[]> {
         const db = this.getDb();
-        const dbLists = await db.select<DbList[]>('SELECT * FROM lists ORDER BY "order", name');
+        const dbLists = await db.select<DbList[]>('SELECT * FROM lists WHERE deleted_at IS NULL ORDER BY "order", name');
         const lists = dbLists.map(this.mapDbListToList);
         this.listsCache = lists;
         return lists;
@@ -423,7 +446,7 @@ export class SqliteStorageService implements IStorageService {
             await db.execute('BEGIN TRANSACTION');
             try {
                 await db.execute(`UPDATE tasks SET list_id = CASE WHEN list_name = 'Trash' THEN NULL ELSE ? END, list_name = CASE WHEN list_name = 'Trash' THEN list_name ELSE ? END, updated_at = ? WHERE list_id = ?`, [inbox.id, inbox.name, now, listId]);
-                await db.execute('DELETE FROM lists WHERE id = ?', [listId]);
+                await db.execute('UPDATE lists SET deleted_at = ?, updated_at = ? WHERE id = ?', [now, now, listId]);
                 await db.execute('COMMIT');
             } catch (error) {
                 await db.execute('ROLLBACK');
@@ -461,8 +484,8 @@ export class SqliteStorageService implements IStorageService {
     async fetchTasksAsync(): Promise<Task[]> {
         const db = this.getDb();
         const [dbTasks, dbSubtasks] = await Promise.all([
-            db.select<DbTask[]>('SELECT * FROM tasks ORDER BY "order", created_at'),
-            db.select<DbSubtask[]>('SELECT * FROM subtasks ORDER BY parent_id, "order"')
+            db.select<DbTask[]>('SELECT * FROM tasks WHERE deleted_at IS NULL ORDER BY "order", created_at'),
+            db.select<DbSubtask[]>('SELECT * FROM subtasks WHERE deleted_at IS NULL ORDER BY parent_id, "order"')
         ]);
         const subtasksByParent: Record<string, Subtask[]> = {};
         dbSubtasks.forEach(dbSubtask => {
@@ -537,9 +560,8 @@ export class SqliteStorageService implements IStorageService {
     deleteTask(taskId: string): void {
         this.tasksCache = this.tasksCache.filter(t => t.id !== taskId);
         this.queueWrite(async () => {
-            const db = this.getDb();
-            await db.execute('DELETE FROM subtasks WHERE parent_id = ?', [taskId]);
-            await db.execute('DELETE FROM tasks WHERE id = ?', [taskId]);
+            // Soft delete, the native side purges trashed rows after the retention window
+            await invoke('trash_task', { id: taskId });
         });
     }
 
@@ -582,12 +604,19 @@ export class SqliteStorageService implements IStorageService {
             await db.execute('BEGIN TRANSACTION');
             try {
                 for (const task of tasks) {
+                    // Upsert rather than REPLACE so columns managed natively (reminders, recurrence) survive
                     await db.execute(`
-                        INSERT OR REPLACE INTO tasks (
+                        INSERT INTO tasks (
                             id, title, completed, completed_at, complete_percentage, due_date, 
                             list_id, list_name, content, "order", created_at, updated_at, 
                             tags, priority, group_category
                         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                        ON CONFLICT(id) DO UPDATE SET
+                            title = excluded.title, completed = excluded.completed, completed_at = excluded.completed_at,
+                            complete_percentage = excluded.complete_percentage, due_date = excluded.due_date,
+                            list_id = excluded.list_id, list_name = excluded.list_name, content = excluded.content,
+                            "order" = excluded."order", created_at = excluded.created_at, updated_at = excluded.updated_at,
+                            tags = excluded.tags, priority = excluded.priority, group_category = excluded.group_category
                     `, [
                         task.id, task.title, task.completed ? 1 : 0, task.completedAt, task.completePercentage, task.dueDate || null, task.listId, task.listName, task.content || null, task.order, task.createdAt, task.updatedAt, task.tags ? JSON.stringify(task.tags) : null, task.priority, task.groupCategory
                     ]);
@@ -608,7 +637,13 @@ export class SqliteStorageService implements IStorageService {
             await db.execute('BEGIN TRANSACTION');
             try {
                 for (const list of lists) {
-                    await db.execute('INSERT OR REPLACE INTO lists (id, name, icon, color, "order", created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)', [list.id, list.name, list.icon || null, list.color || null, list.order || 0, now, now]);
+                    // Upsert rather than REPLACE so columns managed natively (pinned, hidden, defaults) survive
+                    await db.execute(`
+                        INSERT INTO lists (id, name, icon, color, "order", created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)
+                        ON CONFLICT(id) DO UPDATE SET
+                            name = excluded.name, icon = excluded.icon, color = excluded.color,
+                            "order" = excluded."order", updated_at = excluded.updated_at
+                    `, [list.id, list.name, list.icon || null, list.color || null, list.order || 0, now, now]);
                 }
                 await db.execute('COMMIT');
             } catch (error) {
@@ -625,6 +660,14 @@ export class SqliteStorageService implements IStorageService {
         await this.processWriteQueue();
     }
 
+    async factoryReset(keepSettings: boolean): Promise<void> {
+        // Pending writes would recreate rows the reset just deleted
+        await this.flush();
+        await invoke('factory_reset', { keepSettings });
+        this.isDataLoaded = false;
+        await this.preloadData();
+    }
+
     // Subtasks
     createSubtask(taskId: string, subtaskData: { title: string; order: number; dueDate: number | null }): Subtask {
         const now = Date.now();
@@ -690,7 +733,8 @@ export class SqliteStorageService implements IStorageService {
         }
         this.queueWrite(async () => {
             const db = this.getDb();
-            await db.execute('DELETE FROM subtasks WHERE id = ?', [subtaskId]);
+            const now = Date.now();
+            await db.execute('UPDATE subtasks SET deleted_at = ?, updated_at = ? WHERE id = ? AND deleted_at IS NULL', [now, now, subtaskId]);
         });
     }
 
@@ -39,5 +39,11 @@ export default defineConfig({
         target: ['es2021', 'chrome97', 'safari13'],
         minify: !process.env.TAURI_ENV_DEBUG ? 'esbuild' : false,
         sourcemap: !!process.env.TAURI_ENV_DEBUG,
+        rollupOptions: {
+            input: {
+                main: path.resolve(__dirname, 'index.html'),
+                quickAdd: path.resolve(__dirname, 'quick-add.html'),
+            },
+        },
     },
 });
\ No newline at end of file